@@ -5,22 +5,26 @@ in vec2 TexCoords;
 in vec3 FragPos;
 in vec3 Normal;
 
-uniform sampler2D texture_diffuse1; // Основная текстура
-uniform vec3 lightPos;  // Позиция света
-uniform vec3 viewPos;   // Камера
-uniform vec3 lightColor;
+#include "uniforms.glsl"
+
+uniform sampler2D albedoMap; // Основная текстура
+uniform bool hasAlbedoMap;
+uniform vec4 albedoColor;    // Множитель цвета материала
 
 void main() {
     // Получаем цвет из текстуры
-    vec3 texColor = texture(texture_diffuse1, TexCoords).rgb;
+    vec4 albedo = albedoColor;
+    if (hasAlbedoMap) {
+        albedo *= texture(albedoMap, TexCoords);
+    }
 
     // Освещение (ламбертово освещение)
     vec3 norm = normalize(Normal);
-    vec3 lightDir = normalize(lightPos - FragPos);
+    vec3 lightDir = normalize(lightPosition - FragPos);
     float diff = max(dot(norm, lightDir), 0.0);
     vec3 diffuse = diff * lightColor;
 
     // Итоговый цвет
-    vec3 result = diffuse * texColor;
-    FragColor = vec4(result, 1.0);
+    vec3 result = diffuse * albedo.rgb;
+    FragColor = vec4(result, albedo.a);
 }
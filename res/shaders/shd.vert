@@ -7,13 +7,11 @@ out vec2 TexCoords;  // Передаём UV в фрагментный шейде
 out vec3 FragPos;    // Позиция для освещения
 out vec3 Normal;     // Нормаль для освещения
 
-uniform mat4 model;
-uniform mat4 view;
-uniform mat4 projection;
+#include "uniforms.glsl"
 
 void main() {
     FragPos = vec3(model * vec4(aPos, 1.0));
-    Normal = mat3(transpose(inverse(model))) * aNormal;
+    Normal = normalMatrix * aNormal;
     TexCoords = aTexCoords;
 
     gl_Position = projection * view * vec4(FragPos, 1.0);
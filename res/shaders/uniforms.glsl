@@ -0,0 +1,16 @@
+#pragma once
+// Блоки, общие для всех шейдеров движка
+
+layout (std140) uniform FrameData {
+    mat4 view;
+    mat4 projection;
+    vec3 cameraPosition;
+    float time;
+    vec3 lightPosition;
+    vec3 lightColor;
+};
+
+layout (std140) uniform ObjectData {
+    mat4 model;
+    mat3 normalMatrix;
+};
@@ -1,101 +1,414 @@
 use crate::app::init::{GLFWHolder, Settings};
-use crate::app::Init;
+use crate::app::{Game, Init, Plugin, Time};
 use crate::graphics::camera::Camera;
+use crate::graphics::material::Material;
+use crate::graphics::mesh_import::MeshCache;
 use crate::graphics::model::Model;
-use crate::graphics::shader_program::ShaderProgram;
-use gl::SHADER_SOURCE_LENGTH;
+use crate::graphics::shader_preprocessor::{ShaderDefines, ShaderPreprocessor};
+use crate::graphics::shader_program::{ShaderProgram, ShaderVariants};
+use crate::graphics::texture::Texture;
+use crate::graphics::uniform_buffer::{FrameData, ObjectData, UniformBuffer, FRAME_DATA_BINDING, OBJECT_DATA_BINDING};
+use crate::input::{GamepadEvent, Input, InputMap};
+use crate::math::Vec3;
+use crate::scene::scene_file::SceneFile;
+use crate::scene::Scene;
+use crate::utils::errors::{error_report, ERRUranXError};
+use crate::utils::file_watcher::FileWatcher;
 use glfw::Context;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
 
 pub struct App {
     pub is_end: bool,
-    pub glfw_holder: GLFWHolder,
     pub settings: Settings,
-    camera: Camera,
+    pub scene: Scene,
+    scene_file: SceneFile,
+    pub input: Input,
+    pub time: Time,
     shader: ShaderProgram,
-    model: Model,
+    /// Programs of materials that name their own shader.
+    shaders: ShaderVariants,
+    materials: HashMap<String, Rc<Material>>,
+    shader_watcher: Option<FileWatcher>,
+    frame_data: UniformBuffer<FrameData>,
+    object_data: UniformBuffer<ObjectData>,
+    framebuffer_size: (u32, u32),
+    resize_callbacks: Vec<Box<dyn FnMut(u32, u32)>>,
+    plugins: Vec<Box<dyn Plugin>>,
+    pending_plugins: Vec<Box<dyn Plugin>>,
+    /// Declared last so the GL context outlives every GL object above when dropped.
+    pub glfw_holder: GLFWHolder,
 }
 
 impl App {
-    pub fn new() -> Self {
-        let settings = Init::loadSettings().unwrap();
+    pub fn new() -> Result<Self, ERRUranXError> {
+        let settings = Init::loadSettings()?;
         let mut glfw_holder = Init::initGLFW(
-            settings.graphics.window_width,
-            settings.graphics.window_height,
+            &settings.graphics,
             &(settings.manifest.name.clone() + " - UranX"),
-            glfw::WindowMode::Windowed,
-        )
-        .unwrap();
+        )?;
         Init::initOpenGL(&mut glfw_holder);
 
-        glfw_holder.window.make_current();
         glfw_holder.window.set_cursor_pos_polling(true);
+        glfw_holder.window.set_key_polling(true);
+        glfw_holder.window.set_mouse_button_polling(true);
+        glfw_holder.window.set_scroll_polling(true);
+        glfw_holder.window.set_focus_polling(true);
+        glfw_holder.window.set_framebuffer_size_polling(true);
+        glfw_holder.window.set_cursor_mode(glfw::CursorMode::Disabled);
+
+        let input = Input::new(InputMap::from_settings(&settings.input)?);
+        let time = Time::new(&settings.time);
+
+        let paths = &settings.paths;
+        let scene_file = SceneFile::load(&paths.scene)?;
+        let shader = match &scene_file.shader {
+            Some(shader) => ShaderProgram::new(
+                &paths.shader_path(&shader.vertex),
+                &paths.shader_path(&shader.fragment),
+            ),
+            None => ShaderProgram::new(&paths.shaders.vertex, &paths.shaders.fragment),
+        }?;
+
+        let mut shaders = ShaderVariants::new(ShaderPreprocessor::new(&paths.shaders.directory));
+        let mut textures: HashMap<String, Rc<Texture>> = HashMap::new();
+        let mut materials = HashMap::new();
+        for desc in &scene_file.materials {
+            let material = desc.build(
+                |shader| {
+                    shaders.get(
+                        &paths.shader_path(&shader.vertex),
+                        &paths.shader_path(&shader.fragment),
+                        &ShaderDefines::new(),
+                    )
+                },
+                |path, slot| {
+                    let path = paths.texture_path(path);
+                    if let Some(texture) = textures.get(&path) {
+                        return Ok(texture.clone());
+                    }
+
+                    let texture = Rc::new(Texture::load(&path, slot.name().to_string())?);
+                    textures.insert(path, texture.clone());
+                    Ok(texture)
+                },
+            )?;
 
-        let camera = Camera::new(
-            [0.0, 0.0, 3.0],
-            [0.0, 1.0, 0.0],
-            0.3,
-            settings.graphics.window_width as f32,
-            settings.graphics.window_height as f32,
-        );
+            for problem in material.validate(&shader) {
+                eprintln!("[WARN] Material `{}`: {}", material.name, problem);
+            }
+            materials.insert(material.name.clone(), Rc::new(material));
+        }
+
+        let mesh_cache = paths.mesh_cache.as_deref().map(MeshCache::new);
+        let mut models: HashMap<String, Rc<Model>> = HashMap::new();
+        let scene = scene_file.build_with(|path| {
+            if let Some(model) = models.get(path) {
+                return Ok(model.clone());
+            }
 
-        let model = Model::new("res/objects/backpack.obj");
-        let shader = ShaderProgram::new("res/shaders/shd.vert", "res/shaders/shd.frag");
+            let model_path = paths.model_path(path);
+            let model = Rc::new(match &mesh_cache {
+                Some(cache) => Model::load(&model_path, cache)?,
+                None => Model::new(&model_path)?,
+            });
+            models.insert(path.to_string(), model.clone());
+            Ok(model)
+        })?;
 
-        Self {
+        for (_, node) in scene.iter() {
+            if let Some(material) = node.material.as_ref().filter(|name| !materials.contains_key(*name)) {
+                eprintln!("[WARN] Node `{}` uses the unknown material `{}`", node.name, material);
+            }
+        }
+
+        for model in models.values() {
+            let mut layouts = Vec::new();
+            for mesh in model.meshes() {
+                if !layouts.contains(&mesh.layout()) {
+                    layouts.push(mesh.layout());
+                }
+            }
+            for problem in layouts.iter().flat_map(|layout| layout.validate(&shader)) {
+                eprintln!("[WARN] Model `{}`: {}", model.path(), problem);
+            }
+        }
+
+        let shader_watcher = settings.hot_reload.shaders.then(|| {
+            let mut watcher = FileWatcher::new(Duration::from_secs_f64(settings.hot_reload.poll_interval.max(0.0)));
+            watcher.set_files(shader.files().iter().cloned().chain(shaders.files()));
+            watcher
+        });
+
+        let (framebuffer_width, framebuffer_height) = glfw_holder.window.get_framebuffer_size();
+
+        let mut app = Self {
             is_end: false,
             glfw_holder,
             settings,
-            camera,
+            scene,
+            scene_file,
+            input,
+            time,
             shader,
-            model,
+            shaders,
+            materials,
+            shader_watcher,
+            frame_data: UniformBuffer::new(FRAME_DATA_BINDING),
+            object_data: UniformBuffer::new(OBJECT_DATA_BINDING),
+            framebuffer_size: (0, 0),
+            resize_callbacks: Vec::new(),
+            plugins: Vec::new(),
+            pending_plugins: Vec::new(),
+        };
+
+        app.resize(framebuffer_width.max(0) as u32, framebuffer_height.max(0) as u32);
+        Ok(app)
+    }
+
+    /// Registers a callback invoked with the new framebuffer size in pixels after every resize.
+    pub fn on_resize(&mut self, callback: impl FnMut(u32, u32) + 'static) {
+        self.resize_callbacks.push(Box::new(callback));
+    }
+
+    pub fn framebuffer_size(&self) -> (u32, u32) {
+        self.framebuffer_size
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.framebuffer_size = (width, height);
+
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+
+        let camera_ids: Vec<_> = self.scene.cameras().map(|(id, _)| id).collect();
+        for id in camera_ids {
+            if let Some(camera) = self.scene.node_mut(id).and_then(|node| node.camera.as_mut()) {
+                camera.set_viewport_size(width, height);
+            }
+        }
+
+        for callback in &mut self.resize_callbacks {
+            callback(width, height);
+        }
+    }
+
+    fn process_events(&mut self, game: &mut dyn Game) {
+        let events: Vec<_> = glfw::flush_messages(&self.glfw_holder.events).collect();
+
+        for (_, event) in events {
+            self.input.handle_event(&event);
+
+            if let glfw::WindowEvent::FramebufferSize(width, height) = event {
+                self.resize(width.max(0) as u32, height.max(0) as u32);
+            }
+
+            self.each_plugin(|plugin, app| plugin.on_event(app, &event));
+            game.on_event(self, &event);
+        }
+
+        self.input.update_gamepads(&self.glfw_holder.GLFW);
+        for event in self.input.gamepad_events() {
+            match event {
+                GamepadEvent::Connected(id, name) => println!("Gamepad {:?} connected: {}", id, name),
+                GamepadEvent::Disconnected(id) => println!("Gamepad {:?} disconnected", id),
+            }
+        }
+    }
+
+    /// Rebuilds every program, the scene shader and material shaders alike, one of whose files
+    /// changed. A program that fails to build is reported and the previous one stays in use.
+    fn reload_changed_shaders(&mut self) {
+        let Some(watcher) = &mut self.shader_watcher else {
+            return;
+        };
+        let changed: Vec<String> = watcher.poll().iter().map(|path| path.to_string_lossy().to_string()).collect();
+        if changed.is_empty() {
+            return;
         }
+        let uses_changed = |program: &ShaderProgram| program.files().iter().any(|file| changed.contains(file));
+
+        if uses_changed(&self.shader) {
+            match self.shader.reload() {
+                Ok(()) => println!("Reloaded shader `{}`", self.shader.fragment_path()),
+                Err(error) => eprintln!("{}", error_report(&error)),
+            }
+        }
+
+        for error in self.shaders.reload(&changed) {
+            eprintln!("{}", error_report(&error));
+        }
+
+        // Materials hold on to their programs, so they are pointed at the rebuilt ones.
+        for material in self.materials.values_mut() {
+            let Some(shader) = material.shader().filter(|shader| uses_changed(shader)) else {
+                continue;
+            };
+            let Ok(program) = self.shaders.get(shader.vertex_path(), shader.fragment_path(), shader.defines()) else {
+                continue;
+            };
+            if !Rc::ptr_eq(&program, shader) {
+                println!("Reloaded shader `{}` of material `{}`", program.fragment_path(), material.name);
+                Rc::make_mut(material).set_shader(Some(program));
+            }
+        }
+
+        watcher.set_files(self.shader.files().iter().cloned().chain(self.shaders.files()));
     }
 
-    pub fn start(&mut self) {
+    /// Registers a plugin. Plugins added once [`App::run`] has started are initialised before
+    /// the next frame.
+    pub fn add_plugin(&mut self, plugin: impl Plugin + 'static) -> &mut Self {
+        self.pending_plugins.push(Box::new(plugin));
+        self
+    }
+
+    pub fn plugins(&self) -> impl Iterator<Item = &dyn Plugin> {
+        self.plugins.iter().map(|plugin| plugin.as_ref())
+    }
+
+    /// Calls `f` on every plugin while still letting it borrow the app mutably.
+    fn each_plugin(&mut self, mut f: impl FnMut(&mut dyn Plugin, &mut App)) {
+        let mut plugins = std::mem::take(&mut self.plugins);
+        for plugin in &mut plugins {
+            f(plugin.as_mut(), self);
+        }
+        self.plugins = plugins;
+    }
+
+    fn init_pending_plugins(&mut self) {
+        while !self.pending_plugins.is_empty() {
+            let mut plugins = std::mem::take(&mut self.pending_plugins);
+            for plugin in &mut plugins {
+                plugin.init(self);
+            }
+            self.plugins.append(&mut plugins);
+        }
+    }
+
+    pub fn active_camera_mut(&mut self) -> Option<&mut Camera> {
+        let id = self.scene.active_camera()?;
+        self.scene.node_mut(id)?.camera.as_mut()
+    }
+
+    /// Engine-wide actions: `quit` closes the window and `pause` stops the simulation clock.
+    fn handle_input(&mut self) {
+        if self.input.is_action_pressed("quit") {
+            self.glfw_holder.window.set_should_close(true);
+        }
+        if self.input.is_action_pressed("pause") {
+            self.time.toggle_pause();
+        }
+    }
+
+    /// Runs the main loop until the window closes, driving `game` and every plugin.
+    pub fn run(mut self, mut game: impl Game) {
+        self.init_pending_plugins();
+        game.init(&mut self);
+
         while !self.is_end {
+            self.init_pending_plugins();
+
+            self.time.tick();
+            self.reload_changed_shaders();
+            self.handle_input();
+
+            let delta = self.time.delta() as f32;
+            self.each_plugin(|plugin, app| plugin.frame(app, delta));
+            game.frame(&mut self, delta);
+
+            while self.time.step() {
+                let delta = self.time.fixed_delta() as f32;
+                self.each_plugin(|plugin, app| plugin.update(app, delta));
+                game.update(&mut self, delta);
+            }
+            self.scene.update();
+
             unsafe {
                 gl::Enable(gl::DEPTH_TEST);
                 gl::ClearColor(0.1, 0.1, 0.2, 1.0);
                 gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
             }
+            self.render();
 
-            self.shader.use_program();
-
-            let projection = Camera::perspective(
-                45.0,
-                self.settings.graphics.window_height as f32
-                    / self.settings.graphics.window_height as f32,
-                0.1,
-                100.0,
-            );
-
-            let view = Camera::look_at(
-                self.camera.gl_camera_pos,
-                [
-                    self.camera.gl_camera_pos[0] + self.camera.gl_camera_front[0],
-                    self.camera.gl_camera_pos[1] + self.camera.gl_camera_front[1],
-                    self.camera.gl_camera_pos[2] + self.camera.gl_camera_front[2],
-                ],
-                self.camera.gl_camera_up,
-            );
-
-            let mut model = Camera::identity_matrix();
-            model = Camera::translate(model, [0.0, 0.0, 3.0]);
-            model = Camera::scale(model, [1.0, 1.0, 1.0]);
-
-            self.shader
-                .gl_bind_uniform_mat4_fv("projection", &projection);
-            self.shader.gl_bind_uniform_mat4_fv("view", &view);
-            self.shader.gl_bind_uniform_mat4_fv("model", &model);
-
-            println!("drewing");
-            self.model.draw(self.shader.gl_shader_program);
+            let alpha = self.time.alpha() as f32;
+            self.each_plugin(|plugin, app| plugin.render(app, alpha));
+            game.render(&mut self, alpha);
 
             self.glfw_holder.window.swap_buffers();
+            self.input.begin_frame();
             self.glfw_holder.GLFW.poll_events();
+            self.process_events(&mut game);
+            self.time.limit_frame_rate();
             self.is_end = self.glfw_holder.window.should_close();
         }
+
+        game.shutdown(&mut self);
+        let mut plugins = std::mem::take(&mut self.plugins);
+        for plugin in plugins.iter_mut().rev() {
+            plugin.shutdown(&mut self);
+        }
     }
 
-    pub fn end(self) {}
+    /// Writes the current scene to `path`, with the shader and materials it was loaded with.
+    pub fn save_scene(&self, path: &str) -> Result<(), ERRUranXError> {
+        SceneFile::from_scene(&self.scene_file, &self.scene).save(path)
+    }
+
+    pub fn shader(&self) -> &ShaderProgram {
+        &self.shader
+    }
+
+    pub fn material(&self, name: &str) -> Option<&Rc<Material>> {
+        self.materials.get(name)
+    }
+
+    /// Registers `material` for nodes that name it, replacing any material of the same name.
+    pub fn add_material(&mut self, material: Material) -> Rc<Material> {
+        let material = Rc::new(material);
+        self.materials.insert(material.name.clone(), material.clone());
+        material
+    }
+
+    fn render(&mut self) {
+        let Some(camera_id) = self.scene.active_camera() else {
+            return;
+        };
+        // A camera under a zero scale has no view; there is nothing to draw until it has one.
+        let Some(view) = self.scene.view_matrix(camera_id) else {
+            return;
+        };
+        // Cameras added or made active since the last resize still have their default aspect.
+        let (width, height) = self.framebuffer_size;
+        let camera = self.scene.node_mut(camera_id).unwrap().camera.as_mut().unwrap();
+        camera.set_viewport_size(width, height);
+        let projection = camera.projection_matrix();
+        let camera_local_position = camera.gl_camera_pos;
+        let camera_position = self
+            .scene
+            .transform(camera_id)
+            .unwrap()
+            .world_matrix()
+            .transform_point3(camera_local_position);
+        let (light_position, light_color) = match self.scene.lights().next() {
+            Some((light_id, light)) => (
+                self.scene.transform(light_id).unwrap().world_translation(),
+                light.radiance(),
+            ),
+            None => (Vec3::ZERO, Vec3::ZERO),
+        };
+
+        self.frame_data.update(&FrameData {
+            view,
+            projection,
+            camera_position,
+            time: self.time.elapsed() as f32,
+            light_position,
+            light_color,
+        });
+
+        self.scene.draw(&self.shader, &self.materials, &mut self.object_data);
+    }
 }
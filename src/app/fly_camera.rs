@@ -0,0 +1,61 @@
+use crate::app::{App, Plugin};
+
+/// Free-flying control of the scene's active camera.
+///
+/// Reads the `move_forward`, `move_back`, `move_left` and `move_right` actions, the `look_x`
+/// and `look_y` axes (per-frame deltas such as the mouse) and the `turn_x` and `turn_y` axes
+/// (rates in degrees per second such as a stick). Nothing moves while the app is paused.
+pub struct FlyCamera {
+    /// Units per second.
+    pub speed: f32,
+}
+
+impl Default for FlyCamera {
+    fn default() -> Self {
+        Self { speed: 2.5 }
+    }
+}
+
+impl Plugin for FlyCamera {
+    fn frame(&mut self, app: &mut App, _delta: f32) {
+        if app.time.is_paused() {
+            return;
+        }
+
+        let look_x = app.input.axis("look_x");
+        let look_y = app.input.axis("look_y");
+        if let Some(camera) = app.active_camera_mut() {
+            let sensitivity = camera.gl_camera_sensitivity;
+            camera.rotate(look_x * sensitivity, look_y * sensitivity);
+        }
+    }
+
+    fn update(&mut self, app: &mut App, delta: f32) {
+        let move_forward = app.input.is_action_held("move_forward");
+        let move_back = app.input.is_action_held("move_back");
+        let move_left = app.input.is_action_held("move_left");
+        let move_right = app.input.is_action_held("move_right");
+        let turn_x = app.input.axis("turn_x");
+        let turn_y = app.input.axis("turn_y");
+
+        let Some(camera) = app.active_camera_mut() else {
+            return;
+        };
+
+        let distance = self.speed * delta;
+        if move_forward {
+            camera.move_forward(distance);
+        }
+        if move_back {
+            camera.move_back(distance);
+        }
+        if move_left {
+            camera.move_left(distance);
+        }
+        if move_right {
+            camera.move_right(distance);
+        }
+
+        camera.rotate(turn_x * delta, turn_y * delta);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::app::App;
+use glfw::WindowEvent;
+
+/// Game code driven by [`App::run`].
+///
+/// Every hook has an empty default, so a game only implements what it needs. Per frame the
+/// app calls [`Game::frame`] once, [`Game::update`] zero or more times at the fixed rate, and
+/// [`Game::render`] once after the scene is drawn.
+pub trait Game {
+    /// Called once before the first frame, after every plugin's `init`.
+    fn init(&mut self, app: &mut App) {
+        let _ = app;
+    }
+
+    /// Called once per frame with the scaled, variable frame time in seconds.
+    fn frame(&mut self, app: &mut App, delta: f32) {
+        let _ = (app, delta);
+    }
+
+    /// One fixed simulation step of `delta` seconds.
+    fn update(&mut self, app: &mut App, delta: f32) {
+        let _ = (app, delta);
+    }
+
+    /// Called after the scene is drawn. `alpha` is how far the clock is between the last two
+    /// fixed steps, for interpolating what is drawn.
+    fn render(&mut self, app: &mut App, alpha: f32) {
+        let _ = (app, alpha);
+    }
+
+    /// Called for every window event, after the engine has handled it.
+    fn on_event(&mut self, app: &mut App, event: &WindowEvent) {
+        let _ = (app, event);
+    }
+
+    /// Called once after the loop ends, before any plugin's `shutdown`.
+    fn shutdown(&mut self, app: &mut App) {
+        let _ = app;
+    }
+}
+
+/// A game with no content of its own; the scene file and plugins do all the work.
+impl Game for () {}
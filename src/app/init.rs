@@ -1,35 +1,96 @@
 use serde_derive::Deserialize;
 use std::fs;
+use std::path::Path;
+use crate::input::InputSettings;
 use crate::utils::errors::{ERRUranXError, Library};
+use glfw::Context;
 
 pub struct Init;
 
 impl Init {
-    pub fn initGLFW(width: u32, height: u32, title: &str, window_mode: glfw::WindowMode) -> Result<GLFWHolder, ERRUranXError> {
+    pub fn initGLFW(graphics: &GraphicsSettings, title: &str) -> Result<GLFWHolder, ERRUranXError> {
         let mut GLFW = match glfw::init(glfw::fail_on_errors) {
             Ok(it) => it,
             Err(_) => return Err(ERRUranXError::CANNOT_INIT_LIBRARY(Library::GLFW))
         };
 
-        let WINDOW = match GLFW
-            .create_window(width, height, title, window_mode) {
+        let display_mode = graphics.display_mode();
+        let samples = if graphics.msaa_samples > 0 { Some(graphics.msaa_samples) } else { None };
+
+        GLFW.window_hint(glfw::WindowHint::Samples(samples));
+        GLFW.window_hint(glfw::WindowHint::RefreshRate(graphics.refresh_rate));
+        GLFW.window_hint(glfw::WindowHint::Decorated(display_mode != DisplayMode::Borderless));
+
+        let WINDOW = GLFW.with_connected_monitors(|GLFW, monitors| {
+            let monitor = monitors.get(graphics.monitor).or(monitors.first());
+
+            match (display_mode, monitor) {
+                (DisplayMode::Fullscreen, Some(monitor)) => {
+                    let (width, height) = match monitor.get_video_mode() {
+                        Some(mode) => (mode.width, mode.height),
+                        None => (graphics.window_width, graphics.window_height)
+                    };
+                    GLFW.create_window(width, height, title, glfw::WindowMode::FullScreen(monitor))
+                }
+                (DisplayMode::Borderless, Some(monitor)) => {
+                    let (x, y, width, height) = borderless_rect(monitor);
+                    let window = GLFW.create_window(width, height, title, glfw::WindowMode::Windowed);
+                    window.map(|(mut window, events)| {
+                        window.set_pos(x, y);
+                        (window, events)
+                    })
+                }
+                _ => GLFW.create_window(graphics.window_width, graphics.window_height, title, glfw::WindowMode::Windowed)
+            }
+        });
+
+        let mut WINDOW = match WINDOW {
             Some(it) => it,
             None => return Err(ERRUranXError::RUNTIME_LIBRARY_ERROR(Library::GLFW))
         };
 
-        Ok(GLFWHolder { GLFW, window: WINDOW.0, events: WINDOW.1} )
+        WINDOW.0.make_current();
+        GLFW.set_swap_interval(graphics.swap_interval());
+
+        Ok(GLFWHolder {
+            GLFW,
+            window: WINDOW.0,
+            events: WINDOW.1,
+            display_mode,
+            windowed_rect: (100, 100, graphics.window_width, graphics.window_height)
+        })
     }
 
     pub fn initOpenGL(glfw_holder: &mut GLFWHolder) {
-        gl::load_with(|s| { glfw_holder.window.get_proc_address(s) as *const _ })
+        gl::load_with(|s| { glfw_holder.window.get_proc_address(s) as *const _ });
+
+        unsafe { gl::Enable(gl::MULTISAMPLE) };
     }
 
     pub fn loadSettings() -> Result<Settings, ERRUranXError> {
-        match toml::from_str::<Settings>(
-            fs::read_to_string("res/settings.toml").unwrap().as_str()
-        ) {
-            Ok(it) => Ok(it),
-            Err(_) => Err(ERRUranXError::CANNOT_INIT_SETTINGS)
+        let path = "res/settings.toml";
+        let source = fs::read_to_string(path).map_err(|source| ERRUranXError::io(path, source))?;
+        let settings = match toml::from_str::<Settings>(&source) {
+            Ok(it) => it,
+            Err(error) => return Err(ERRUranXError::toml(path, &source, &error))
+        };
+
+        settings.paths.validate()?;
+        Ok(settings)
+    }
+}
+
+/// Position and size that cover `monitor` exactly: its origin with the size of its video mode.
+/// Without a video mode the work area is used for both, so the window stays on the monitor.
+fn borderless_rect(monitor: &glfw::Monitor) -> (i32, i32, u32, u32) {
+    match monitor.get_video_mode() {
+        Some(mode) => {
+            let (x, y) = monitor.get_pos();
+            (x, y, mode.width, mode.height)
+        }
+        None => {
+            let (x, y, width, height) = monitor.get_workarea();
+            (x, y, width.max(0) as u32, height.max(0) as u32)
         }
     }
 }
@@ -37,24 +98,316 @@ impl Init {
 pub struct GLFWHolder {
     pub GLFW: glfw::Glfw,
     pub window: glfw::PWindow,
-    pub events: glfw::GlfwReceiver<(f64, glfw::WindowEvent)>
+    pub events: glfw::GlfwReceiver<(f64, glfw::WindowEvent)>,
+    display_mode: DisplayMode,
+    windowed_rect: (i32, i32, u32, u32)
+}
+
+impl GLFWHolder {
+    pub fn display_mode(&self) -> DisplayMode {
+        self.display_mode
+    }
+
+    /// Switches the existing window between windowed, fullscreen and borderless modes.
+    /// The last windowed position and size are restored when going back to windowed.
+    pub fn set_display_mode(&mut self, display_mode: DisplayMode, monitor: usize, refresh_rate: Option<u32>) {
+        if self.display_mode == DisplayMode::Windowed {
+            let (x, y) = self.window.get_pos();
+            let (width, height) = self.window.get_size();
+            self.windowed_rect = (x, y, width as u32, height as u32);
+        }
+
+        let window = &mut self.window;
+        let (x, y, width, height) = self.windowed_rect;
+
+        self.GLFW.with_connected_monitors(|_, monitors| {
+            let monitor = monitors.get(monitor).or(monitors.first());
+
+            match (display_mode, monitor) {
+                (DisplayMode::Fullscreen, Some(monitor)) => {
+                    let (width, height, rate) = match monitor.get_video_mode() {
+                        Some(mode) => (mode.width, mode.height, refresh_rate.or(Some(mode.refresh_rate))),
+                        None => (width, height, refresh_rate)
+                    };
+                    window.set_monitor(glfw::WindowMode::FullScreen(monitor), 0, 0, width, height, rate);
+                }
+                (DisplayMode::Borderless, Some(monitor)) => {
+                    let (monitor_x, monitor_y, width, height) = borderless_rect(monitor);
+                    window.set_decorated(false);
+                    window.set_monitor(glfw::WindowMode::Windowed, monitor_x, monitor_y, width, height, None);
+                }
+                _ => {
+                    window.set_decorated(true);
+                    window.set_monitor(glfw::WindowMode::Windowed, x, y, width, height, None);
+                }
+            }
+        });
+
+        self.display_mode = display_mode;
+    }
 }
 
 #[derive(Deserialize)]
 pub struct Settings {
     pub manifest: ManifestSettings,
-    pub graphics: GraphicsSettings
+    pub paths: PathsSettings,
+    pub graphics: GraphicsSettings,
+    #[serde(default)]
+    pub input: InputSettings,
+    #[serde(default)]
+    pub time: TimeSettings,
+    #[serde(default)]
+    pub hot_reload: HotReloadSettings
+}
+
+/// Reloading of assets edited while the game runs.
+#[derive(Deserialize, Debug, Clone)]
+pub struct HotReloadSettings {
+    #[serde(default)]
+    pub shaders: bool,
+    /// Seconds between checks of the watched files.
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: f64
+}
+
+impl Default for HotReloadSettings {
+    fn default() -> Self {
+        Self {
+            shaders: false,
+            poll_interval: default_poll_interval()
+        }
+    }
+}
+
+fn default_poll_interval() -> f64 {
+    0.5
 }
 
 #[derive(Deserialize)]
 pub struct GraphicsSettings {
     pub window_height: u32,
     pub window_width: u32,
-    pub is_fullscreen: bool
+    /// Kept for older settings files; `display_mode` takes precedence when present.
+    #[serde(default)]
+    pub is_fullscreen: bool,
+    #[serde(default, rename = "display_mode")]
+    pub display_mode_setting: Option<DisplayMode>,
+    /// Index into the connected monitors; falls back to the primary one.
+    #[serde(default)]
+    pub monitor: usize,
+    /// Fullscreen refresh rate in Hz; unset uses the monitor's current one.
+    #[serde(default)]
+    pub refresh_rate: Option<u32>,
+    #[serde(default = "default_vsync")]
+    pub vsync: bool,
+    /// Number of screen updates to wait per buffer swap when `vsync` is on.
+    #[serde(default = "default_swap_interval")]
+    pub swap_interval: u32,
+    /// MSAA sample count; `0` disables multisampling.
+    #[serde(default)]
+    pub msaa_samples: u32
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    Windowed,
+    Fullscreen,
+    Borderless
+}
+
+/// Main loop timing.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimeSettings {
+    /// Simulation steps per second.
+    #[serde(default = "default_fixed_update_rate")]
+    pub fixed_update_rate: f64,
+    /// Upper bound on steps run in one frame before the simulation is allowed to fall behind.
+    #[serde(default = "default_max_steps_per_frame")]
+    pub max_steps_per_frame: u32,
+    /// Frames per second cap; `0` leaves it to vsync.
+    #[serde(default)]
+    pub max_frame_rate: u32,
+    #[serde(default = "default_time_scale")]
+    pub time_scale: f64
+}
+
+impl Default for TimeSettings {
+    fn default() -> Self {
+        Self {
+            fixed_update_rate: default_fixed_update_rate(),
+            max_steps_per_frame: default_max_steps_per_frame(),
+            max_frame_rate: 0,
+            time_scale: default_time_scale()
+        }
+    }
+}
+
+fn default_fixed_update_rate() -> f64 {
+    60.0
+}
+
+fn default_max_steps_per_frame() -> u32 {
+    5
+}
+
+fn default_time_scale() -> f64 {
+    1.0
+}
+
+fn default_vsync() -> bool {
+    true
+}
+
+fn default_swap_interval() -> u32 {
+    1
+}
+
+impl GraphicsSettings {
+    pub fn display_mode(&self) -> DisplayMode {
+        match self.display_mode_setting {
+            Some(mode) => mode,
+            None if self.is_fullscreen => DisplayMode::Fullscreen,
+            None => DisplayMode::Windowed
+        }
+    }
+
+    pub fn swap_interval(&self) -> glfw::SwapInterval {
+        if self.vsync {
+            glfw::SwapInterval::Sync(self.swap_interval.max(1))
+        } else {
+            glfw::SwapInterval::None
+        }
+    }
 }
 
 #[derive(Deserialize)]
 pub struct ManifestSettings {
     pub name: String,
     pub version: String
-}
\ No newline at end of file
+}
+
+/// Asset locations, relative to the working directory.
+#[derive(Deserialize)]
+pub struct PathsSettings {
+    /// Last place asset paths from scenes and materials are looked up, after their own directory.
+    pub root: String,
+    pub models: String,
+    pub textures: String,
+    pub scene: String,
+    pub shaders: ShaderPathsSettings,
+    /// Directory for imported models in the engine's mesh format; unset imports every launch.
+    /// Created when the first model is cached.
+    #[serde(default)]
+    pub mesh_cache: Option<String>
+}
+
+#[derive(Deserialize)]
+pub struct ShaderPathsSettings {
+    pub directory: String,
+    pub vertex: String,
+    pub fragment: String
+}
+
+impl PathsSettings {
+    /// Checks that every configured directory and file exists.
+    pub fn validate(&self) -> Result<(), ERRUranXError> {
+        let directories = [
+            ("paths.root", &self.root),
+            ("paths.models", &self.models),
+            ("paths.textures", &self.textures),
+            ("paths.shaders.directory", &self.shaders.directory)
+        ];
+        let files = [
+            ("paths.scene", &self.scene),
+            ("paths.shaders.vertex", &self.shaders.vertex),
+            ("paths.shaders.fragment", &self.shaders.fragment)
+        ];
+
+        for (setting, path) in directories {
+            if !Path::new(path).is_dir() {
+                return Err(ERRUranXError::MISSING_PATH { setting: setting.to_string(), path: path.clone() });
+            }
+        }
+
+        for (setting, path) in files {
+            if !Path::new(path).is_file() {
+                return Err(ERRUranXError::MISSING_PATH { setting: setting.to_string(), path: path.clone() });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn model_path(&self, path: &str) -> String {
+        self.resolve(&self.models, path)
+    }
+
+    pub fn texture_path(&self, path: &str) -> String {
+        self.resolve(&self.textures, path)
+    }
+
+    pub fn shader_path(&self, path: &str) -> String {
+        self.resolve(&self.shaders.directory, path)
+    }
+
+    /// Keeps `path` if it already points at something, otherwise looks for it under `directory`,
+    /// then under `root`, so scenes can name assets either way (`cube.obj`, `objects/cube.obj`).
+    /// A path found nowhere is reported under `directory`.
+    fn resolve(&self, directory: &str, path: &str) -> String {
+        if Path::new(path).exists() {
+            return path.to_string();
+        }
+
+        let in_directory = Path::new(directory).join(path);
+        let in_root = Path::new(&self.root).join(path);
+        let resolved = if !in_directory.exists() && in_root.exists() { in_root } else { in_directory };
+        resolved.to_string_lossy().to_string()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths() -> PathsSettings {
+        toml::from_str(
+            r#"
+            root = "res"
+            models = "res/objects"
+            textures = "res/textures"
+            scene = "res/scenes/main.toml"
+            [shaders]
+            directory = "res/shaders"
+            vertex = "res/shaders/shd.vert"
+            fragment = "res/shaders/shd.frag"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn bundled_paths_are_valid() {
+        paths().validate().unwrap();
+    }
+
+    #[test]
+    fn resolves_against_directory_then_root() {
+        let paths = paths();
+        let cube = Path::new("res/objects/cube.obj").to_string_lossy().to_string();
+        assert_eq!(paths.model_path("res/objects/cube.obj"), "res/objects/cube.obj");
+        assert_eq!(paths.model_path("cube.obj"), cube);
+        assert_eq!(paths.model_path("objects/cube.obj"), cube);
+        assert_eq!(paths.texture_path("shaders/shd.frag"), Path::new("res/shaders/shd.frag").to_string_lossy());
+        assert_eq!(paths.model_path("missing.obj"), Path::new("res/objects/missing.obj").to_string_lossy());
+    }
+
+    #[test]
+    fn missing_path_names_the_setting() {
+        let mut paths = paths();
+        paths.shaders.vertex = "res/shaders/missing.vert".to_string();
+        match paths.validate() {
+            Err(ERRUranXError::MISSING_PATH { setting, .. }) => assert_eq!(setting, "paths.shaders.vertex"),
+            other => panic!("expected MISSING_PATH, got {:?}", other),
+        }
+    }
+}
@@ -1,5 +1,13 @@
 mod app;
+mod fly_camera;
+mod game;
 mod init;
+mod plugin;
+mod time;
 
 pub use app::App;
-pub use init::Init;
\ No newline at end of file
+pub use fly_camera::FlyCamera;
+pub use game::Game;
+pub use init::*;
+pub use plugin::Plugin;
+pub use time::Time;
@@ -0,0 +1,36 @@
+use crate::app::App;
+use glfw::WindowEvent;
+
+/// A reusable system registered with [`App::add_plugin`].
+///
+/// Plugins get the same hooks as a [`Game`](crate::app::Game) and run before it, in the order
+/// they were added; `shutdown` runs in reverse order.
+pub trait Plugin {
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn init(&mut self, app: &mut App) {
+        let _ = app;
+    }
+
+    fn frame(&mut self, app: &mut App, delta: f32) {
+        let _ = (app, delta);
+    }
+
+    fn update(&mut self, app: &mut App, delta: f32) {
+        let _ = (app, delta);
+    }
+
+    fn render(&mut self, app: &mut App, alpha: f32) {
+        let _ = (app, alpha);
+    }
+
+    fn on_event(&mut self, app: &mut App, event: &WindowEvent) {
+        let _ = (app, event);
+    }
+
+    fn shutdown(&mut self, app: &mut App) {
+        let _ = app;
+    }
+}
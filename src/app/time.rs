@@ -0,0 +1,270 @@
+use crate::app::init::TimeSettings;
+use std::time::{Duration, Instant};
+
+/// Frame timing for a fixed-timestep loop.
+///
+/// Every frame, [`Time::advance`] adds the scaled frame time to an accumulator, which is then
+/// drained in steps of [`Time::fixed_delta`]. Rendering happens once per frame and can blend
+/// between the last two simulation states with [`Time::alpha`].
+#[derive(Debug, Clone)]
+pub struct Time {
+    fixed_delta: f64,
+    max_steps_per_frame: u32,
+    max_frame_time: f64,
+    frame_duration: Option<Duration>,
+    time_scale: f64,
+    is_paused: bool,
+    delta: f64,
+    unscaled_delta: f64,
+    accumulator: f64,
+    elapsed: f64,
+    unscaled_elapsed: f64,
+    frame_count: u64,
+    step_count: u64,
+    last_frame: Option<Instant>,
+}
+
+impl Time {
+    pub fn new(settings: &TimeSettings) -> Self {
+        let fixed_delta = 1.0 / settings.fixed_update_rate.max(1.0);
+
+        let mut time = Self {
+            fixed_delta,
+            max_steps_per_frame: settings.max_steps_per_frame.max(1),
+            max_frame_time: fixed_delta * settings.max_steps_per_frame.max(1) as f64,
+            frame_duration: None,
+            time_scale: settings.time_scale.max(0.0),
+            is_paused: false,
+            delta: 0.0,
+            unscaled_delta: 0.0,
+            accumulator: 0.0,
+            elapsed: 0.0,
+            unscaled_elapsed: 0.0,
+            frame_count: 0,
+            step_count: 0,
+            last_frame: None,
+        };
+        time.set_max_frame_rate(settings.max_frame_rate);
+        time
+    }
+
+    /// Measures the time since the previous call and advances the clock by it.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        let frame_time = self
+            .last_frame
+            .map(|last_frame| now.duration_since(last_frame).as_secs_f64())
+            .unwrap_or(0.0);
+        self.last_frame = Some(now);
+        self.advance(frame_time);
+    }
+
+    /// Advances the clock by `frame_time` real seconds.
+    ///
+    /// Only the accumulator is clamped, so after a long frame (a breakpoint, a dragged window)
+    /// the simulation runs at most `max_steps_per_frame` steps and drops the rest, while the
+    /// frame deltas still report the time that really passed.
+    pub fn advance(&mut self, frame_time: f64) {
+        let frame_time = frame_time.max(0.0);
+
+        self.unscaled_delta = frame_time;
+        self.unscaled_elapsed += frame_time;
+        self.delta = if self.is_paused { 0.0 } else { frame_time * self.time_scale };
+        self.accumulator = (self.accumulator + self.delta).min(self.max_frame_time);
+        self.frame_count += 1;
+    }
+
+    /// Consumes one fixed step from the accumulator. Loop on it to run the simulation:
+    ///
+    /// ```ignore
+    /// while time.step() {
+    ///     update(time.fixed_delta());
+    /// }
+    /// ```
+    pub fn step(&mut self) -> bool {
+        if self.accumulator < self.fixed_delta {
+            return false;
+        }
+
+        self.accumulator -= self.fixed_delta;
+        self.elapsed += self.fixed_delta;
+        self.step_count += 1;
+        true
+    }
+
+    /// How far the clock is between the last fixed step and the next one, in `[0, 1)`.
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.fixed_delta
+    }
+
+    /// Sleeps out the remainder of the frame when a frame rate limit is set.
+    pub fn limit_frame_rate(&self) {
+        let (Some(frame_duration), Some(last_frame)) = (self.frame_duration, self.last_frame) else {
+            return;
+        };
+
+        let spent = last_frame.elapsed();
+        if spent < frame_duration {
+            std::thread::sleep(frame_duration - spent);
+        }
+    }
+
+    /// Scaled seconds since the previous frame; `0` while paused.
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+
+    /// Real seconds since the previous frame, ignoring pause and time scale.
+    pub fn unscaled_delta(&self) -> f64 {
+        self.unscaled_delta
+    }
+
+    /// Seconds per simulation step.
+    pub fn fixed_delta(&self) -> f64 {
+        self.fixed_delta
+    }
+
+    pub fn set_fixed_update_rate(&mut self, rate: f64) {
+        self.fixed_delta = 1.0 / rate.max(1.0);
+        self.max_frame_time = self.fixed_delta * self.max_steps_per_frame as f64;
+    }
+
+    /// Simulated seconds since start: the fixed steps run so far, so time dropped after a
+    /// long frame and time spent paused are not counted.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn unscaled_elapsed(&self) -> f64 {
+        self.unscaled_elapsed
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// `1` is real time, `0.5` half speed. Negative values are clamped to `0`.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    pub fn set_paused(&mut self, is_paused: bool) {
+        self.is_paused = is_paused;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.is_paused = !self.is_paused;
+    }
+
+    /// `0` removes the limit.
+    pub fn set_max_frame_rate(&mut self, max_frame_rate: u32) {
+        self.frame_duration = (max_frame_rate > 0).then(|| Duration::from_secs_f64(1.0 / max_frame_rate as f64));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 64 Hz keeps every step exact in binary floating point.
+    const STEP: f64 = 1.0 / 64.0;
+
+    fn time(time_scale: f64) -> Time {
+        Time::new(&TimeSettings {
+            fixed_update_rate: 64.0,
+            max_steps_per_frame: 5,
+            max_frame_rate: 0,
+            time_scale,
+        })
+    }
+
+    fn steps(time: &mut Time) -> u32 {
+        let mut steps = 0;
+        while time.step() {
+            steps += 1;
+        }
+        steps
+    }
+
+    #[test]
+    fn runs_fixed_steps_and_keeps_the_remainder() {
+        let mut time = time(1.0);
+        time.advance(2.5 * STEP);
+        assert_eq!(steps(&mut time), 2);
+        assert_eq!(time.alpha(), 0.5);
+
+        time.advance(0.5 * STEP);
+        assert_eq!(steps(&mut time), 1);
+        assert_eq!(time.alpha(), 0.0);
+    }
+
+    #[test]
+    fn long_frames_are_capped() {
+        let mut time = time(1.0);
+        time.advance(10.0);
+        assert_eq!(steps(&mut time), 5);
+        assert_eq!(time.alpha(), 0.0);
+        assert_eq!(time.unscaled_delta(), 10.0);
+        assert_eq!(time.delta(), 10.0);
+        assert_eq!(time.elapsed(), 5.0 * STEP);
+    }
+
+    #[test]
+    fn time_scale_never_exceeds_max_steps() {
+        let mut time = time(8.0);
+        for _ in 0..10 {
+            time.advance(STEP);
+            assert_eq!(steps(&mut time), 5);
+        }
+        assert_eq!(time.elapsed(), 50.0 * STEP);
+        assert_eq!(time.unscaled_elapsed(), 10.0 * STEP);
+    }
+
+    #[test]
+    fn time_scale_slows_the_simulation() {
+        let mut time = time(0.5);
+        time.advance(STEP);
+        assert_eq!(steps(&mut time), 0);
+        assert_eq!(time.alpha(), 0.5);
+        assert_eq!(time.delta(), 0.5 * STEP);
+        assert_eq!(time.unscaled_delta(), STEP);
+
+        time.advance(STEP);
+        assert_eq!(steps(&mut time), 1);
+        assert_eq!(time.elapsed(), STEP);
+        assert_eq!(time.unscaled_elapsed(), 2.0 * STEP);
+    }
+
+    #[test]
+    fn time_scale_speeds_up_the_simulation() {
+        let mut time = time(2.0);
+        time.advance(1.5 * STEP);
+        assert_eq!(steps(&mut time), 3);
+        assert_eq!(time.delta(), 3.0 * STEP);
+        assert_eq!(time.elapsed(), 3.0 * STEP);
+        assert_eq!(time.unscaled_delta(), 1.5 * STEP);
+    }
+
+    #[test]
+    fn pause_stops_the_simulation() {
+        let mut time = time(1.0);
+        time.set_paused(true);
+        time.advance(3.0 * STEP);
+        assert_eq!(steps(&mut time), 0);
+        assert_eq!(time.delta(), 0.0);
+        assert_eq!(time.elapsed(), 0.0);
+        assert_eq!(time.unscaled_delta(), 3.0 * STEP);
+    }
+}
@@ -0,0 +1,1243 @@
+use super::camera::Camera;
+use super::model::Model;
+use super::vertex_layout::MAX_TEXTURE_COORD_SETS;
+use crate::math::{Mat4, Quat, Vec3};
+use crate::scene::light::Light;
+use crate::scene::scene_file::CameraDesc;
+use crate::scene::transform::Transform;
+use crate::scene::{Node, NodeId, Scene};
+use crate::utils::base64;
+use crate::utils::errors::{line_column, ERRUranXError};
+use crate::utils::json::JsonValue;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Extensions the importer understands; a file that requires any other one is rejected.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["KHR_lights_punctual"];
+
+/// The contents of a glTF 2.0 file (`.gltf` or `.glb`), decoded into plain data.
+///
+/// Loading reads the buffers but nothing else: images stay encoded and no GL object is
+/// created, so a document can be inspected without a context. [`Model::new`] and
+/// [`GltfDocument::instantiate`] turn it into engine objects.
+///
+/// [`Model::new`]: super::model::Model::new
+#[derive(Debug, Clone, Default)]
+pub struct GltfDocument {
+    pub path: String,
+    pub buffers: Vec<Vec<u8>>,
+    pub images: Vec<GltfImage>,
+    pub textures: Vec<GltfTexture>,
+    pub materials: Vec<GltfMaterial>,
+    pub meshes: Vec<GltfMesh>,
+    pub cameras: Vec<GltfCamera>,
+    pub lights: Vec<GltfLight>,
+    pub nodes: Vec<GltfNode>,
+    pub scenes: Vec<GltfScene>,
+    /// Scene to show by default, if the file names one.
+    pub scene: Option<usize>,
+    pub skins: Vec<GltfSkin>,
+    pub animations: Vec<GltfAnimation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfImage {
+    pub name: String,
+    pub source: GltfImageSource,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GltfImageSource {
+    /// An image file, resolved against the glTF file's directory.
+    File(String),
+    /// Encoded image bytes from a data URI or a buffer view.
+    Embedded { mime_type: Option<String>, data: Vec<u8> },
+}
+
+/// An image plus the sampler settings to use it with. Filters and wrap modes are GL enums;
+/// `None` leaves the choice to the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GltfTexture {
+    pub image: Option<usize>,
+    pub mag_filter: Option<u32>,
+    pub min_filter: Option<u32>,
+    pub wrap_s: Option<u32>,
+    pub wrap_t: Option<u32>,
+}
+
+/// A material's reference to a texture and the UV set it is sampled with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GltfTextureRef {
+    pub texture: usize,
+    pub tex_coord: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaMode {
+    #[default]
+    Opaque,
+    Mask,
+    Blend,
+}
+
+/// A PBR metallic-roughness material.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfMaterial {
+    pub name: String,
+    pub base_color_factor: [f32; 4],
+    pub base_color_texture: Option<GltfTextureRef>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_texture: Option<GltfTextureRef>,
+    pub normal_texture: Option<GltfTextureRef>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<GltfTextureRef>,
+    pub occlusion_strength: f32,
+    pub emissive_texture: Option<GltfTextureRef>,
+    pub emissive_factor: [f32; 3],
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+impl Default for GltfMaterial {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color_factor: [1.0; 4],
+            base_color_texture: None,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive_texture: None,
+            emissive_factor: [0.0; 3],
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GltfMesh {
+    pub name: String,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+/// One draw of a mesh. Attributes a file doesn't provide are left empty; `indices` is always
+/// filled, with `0..n` for unindexed primitives.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GltfPrimitive {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub tangents: Vec<[f32; 4]>,
+    /// One array per UV set, `TEXCOORD_0` first.
+    pub texture_coords: Vec<Vec<[f32; 2]>>,
+    pub colors: Vec<[f32; 4]>,
+    pub joints: Vec<[u16; 4]>,
+    pub weights: Vec<[f32; 4]>,
+    pub indices: Vec<u32>,
+    pub material: Option<usize>,
+    /// `4` for triangles; see [`triangle_indices`] for the other modes.
+    pub mode: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfCamera {
+    pub name: String,
+    pub projection: GltfProjection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GltfProjection {
+    /// `y_fov` is in radians. A missing `z_far` means an infinite projection.
+    Perspective {
+        y_fov: f32,
+        aspect_ratio: Option<f32>,
+        z_near: f32,
+        z_far: Option<f32>,
+    },
+    Orthographic {
+        x_mag: f32,
+        y_mag: f32,
+        z_near: f32,
+        z_far: f32,
+    },
+}
+
+/// A `KHR_lights_punctual` light. Point and spot lights without a range get an infinite one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfLight {
+    pub name: String,
+    pub light: Light,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfNode {
+    pub name: String,
+    pub children: Vec<usize>,
+    pub mesh: Option<usize>,
+    pub camera: Option<usize>,
+    pub light: Option<usize>,
+    pub skin: Option<usize>,
+    /// Local transform; a `matrix` in the file is decomposed into these.
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl GltfNode {
+    pub fn local_matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GltfScene {
+    pub name: String,
+    pub nodes: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GltfSkin {
+    pub name: String,
+    /// Node indices of the joints.
+    pub joints: Vec<usize>,
+    /// One per joint; identity when the file leaves them out.
+    pub inverse_bind_matrices: Vec<Mat4>,
+    pub skeleton: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GltfAnimation {
+    pub name: String,
+    pub channels: Vec<GltfChannel>,
+    pub samplers: Vec<GltfAnimationSampler>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GltfChannel {
+    pub sampler: usize,
+    /// Animated node; `None` when an extension targets something else.
+    pub node: Option<usize>,
+    pub path: AnimationPath,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationPath {
+    Translation,
+    Rotation,
+    Scale,
+    Weights,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    Step,
+    CubicSpline,
+}
+
+/// Keyframes: `input` holds the times in seconds, `output` the values flattened with
+/// `components` floats each (tangents included for cubic splines).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GltfAnimationSampler {
+    pub input: Vec<f32>,
+    pub output: Vec<f32>,
+    pub components: usize,
+    pub interpolation: Interpolation,
+}
+
+/// The triangle list for a primitive of `mode`: lists are returned as they are, strips and
+/// fans are unrolled. `None` for points and lines.
+pub fn triangle_indices(mode: u32, indices: &[u32]) -> Option<Vec<u32>> {
+    match mode {
+        gl::TRIANGLES => Some(indices.to_vec()),
+        gl::TRIANGLE_STRIP => Some(
+            (2..indices.len())
+                .flat_map(|i| {
+                    if i % 2 == 0 {
+                        [indices[i - 2], indices[i - 1], indices[i]]
+                    } else {
+                        [indices[i - 1], indices[i - 2], indices[i]]
+                    }
+                })
+                .collect(),
+        ),
+        gl::TRIANGLE_FAN => Some(
+            (2..indices.len())
+                .flat_map(|i| [indices[0], indices[i - 1], indices[i]])
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON_CHUNK: u32 = 0x4E4F534A;
+const GLB_BIN_CHUNK: u32 = 0x004E4942;
+
+/// Decoded values of an accessor, one `f64` per component so every component type fits.
+struct Accessor {
+    values: Vec<f64>,
+    components: usize,
+    component_type: u32,
+    normalized: bool,
+}
+
+impl Accessor {
+    fn floats(&self) -> Vec<f32> {
+        if !self.normalized {
+            return self.values.iter().map(|value| *value as f32).collect();
+        }
+
+        let max = match self.component_type {
+            5120 => 127.0,
+            5121 => 255.0,
+            5122 => 32767.0,
+            5123 => 65535.0,
+            5125 => u32::MAX as f64,
+            _ => 1.0,
+        };
+        self.values.iter().map(|value| (value / max).max(-1.0) as f32).collect()
+    }
+
+    fn chunks<const N: usize>(&self) -> Vec<[f32; N]> {
+        self.floats()
+            .chunks_exact(self.components)
+            .map(|chunk| std::array::from_fn(|i| chunk.get(i).copied().unwrap_or(if i == 3 { 1.0 } else { 0.0 })))
+            .collect()
+    }
+}
+
+impl GltfDocument {
+    pub fn load(path: &str) -> Result<Self, ERRUranXError> {
+        Self::load_with(path, |path| std::fs::read(path))
+    }
+
+    /// Like [`GltfDocument::load`], reading the file and its external buffers through `read`.
+    pub fn load_with(path: &str, mut read: impl FnMut(&Path) -> io::Result<Vec<u8>>) -> Result<Self, ERRUranXError> {
+        let bytes = read(Path::new(path)).map_err(|source| ERRUranXError::io(path, source))?;
+        let error = |message: String| ERRUranXError::GLTF_ERROR { path: path.to_string(), message };
+
+        let (json, binary) = if bytes.starts_with(GLB_MAGIC) {
+            let (json, binary) = parse_glb(&bytes).map_err(|message| error(message.to_string()))?;
+            (json, binary)
+        } else {
+            (bytes.as_slice(), None)
+        };
+
+        let json = std::str::from_utf8(json).map_err(|_| error("the JSON is not valid UTF-8".to_string()))?;
+        let root = JsonValue::parse(json).map_err(|json_error| {
+            let (line, column) = line_column(json, json_error.offset);
+            ERRUranXError::PARSE_ERROR { path: path.to_string(), line, column, message: json_error.message }
+        })?;
+
+        let directory = Path::new(path).parent().map(Path::to_path_buf).unwrap_or_default();
+        let mut loader = Loader { root: &root, directory, document: GltfDocument::default() };
+        loader.document.path = path.to_string();
+        loader.load(binary, &mut read).map_err(error)?;
+        Ok(loader.document)
+    }
+
+    /// The scene to show: the default one, else the first. Files without scenes get every
+    /// node that isn't a child as a root.
+    pub fn root_nodes(&self) -> Vec<usize> {
+        if let Some(scene) = self.scene.or((!self.scenes.is_empty()).then_some(0)).and_then(|index| self.scenes.get(index)) {
+            return scene.nodes.clone();
+        }
+
+        let children: Vec<usize> = self.nodes.iter().flat_map(|node| node.children.iter().copied()).collect();
+        (0..self.nodes.len()).filter(|index| !children.contains(index)).collect()
+    }
+
+    /// Calls `f` with every node reachable from [`GltfDocument::root_nodes`] and its world matrix,
+    /// parents before children.
+    pub fn walk(&self, mut f: impl FnMut(usize, &GltfNode, Mat4)) {
+        let mut stack: Vec<(usize, Mat4)> = self.root_nodes().into_iter().rev().map(|index| (index, Mat4::IDENTITY)).collect();
+        let mut visited = vec![false; self.nodes.len()];
+
+        while let Some((index, parent_matrix)) = stack.pop() {
+            let Some(node) = self.nodes.get(index) else {
+                continue;
+            };
+            // A malformed file could list a node twice; glTF requires a strict tree.
+            if std::mem::replace(&mut visited[index], true) {
+                continue;
+            }
+
+            let world_matrix = parent_matrix * node.local_matrix();
+            f(index, node, world_matrix);
+            stack.extend(node.children.iter().rev().map(|child| (*child, world_matrix)));
+        }
+    }
+}
+
+impl GltfDocument {
+    /// Adds the nodes of [`GltfDocument::root_nodes`] to `scene` below `parent`, keeping the
+    /// hierarchy, and returns the ids of the roots. Each glTF mesh becomes one shared
+    /// [`Model`]; perspective cameras and lights are attached to their nodes. Orthographic
+    /// cameras have no engine counterpart yet and are skipped with a warning. A `parent` that is
+    /// not in `scene` is an error.
+    pub fn instantiate(&self, scene: &mut Scene, parent: Option<NodeId>) -> Result<Vec<NodeId>, ERRUranXError> {
+        if parent.is_some_and(|parent| !scene.contains(parent)) {
+            return Err(ERRUranXError::GLTF_ERROR {
+                path: self.path.clone(),
+                message: "the parent node is not in the scene".to_string(),
+            });
+        }
+
+        let materials = Model::load_gltf_materials(self)?;
+        let models: Vec<Rc<Model>> = (0..self.meshes.len())
+            .map(|mesh| Rc::new(Model::from_gltf_mesh(self, mesh, &materials)))
+            .collect();
+
+        let mut parents = vec![None; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            for child in &node.children {
+                parents[*child] = Some(index);
+            }
+        }
+
+        let mut ids: Vec<Option<NodeId>> = vec![None; self.nodes.len()];
+        let mut roots = Vec::new();
+        self.walk(|index, gltf_node, _| {
+            let name = if gltf_node.name.is_empty() { format!("node{}", index) } else { gltf_node.name.clone() };
+            let mut node = Node::new(&name);
+            node.model = gltf_node.mesh.map(|mesh| models[mesh].clone());
+            node.light = gltf_node.light.map(|light| self.lights[light].light);
+            node.camera = gltf_node.camera.and_then(|camera| match self.cameras[camera].projection {
+                GltfProjection::Perspective { y_fov, z_near, z_far, .. } => Some(Camera::from(CameraDesc {
+                    fov: y_fov.to_degrees(),
+                    near: z_near,
+                    far: z_far.unwrap_or(1000.0),
+                    ..CameraDesc::default()
+                })),
+                GltfProjection::Orthographic { .. } => {
+                    eprintln!("[WARN] Node `{}` of `{}` has an orthographic camera, which is not supported", name, self.path);
+                    None
+                }
+            });
+
+            let transform = Transform::from_trs(gltf_node.translation, gltf_node.rotation, gltf_node.scale);
+            let id = match parents[index].and_then(|parent| ids[parent]).or(parent) {
+                Some(parent_id) => scene.add_child(parent_id, node, transform).unwrap(),
+                None => scene.add(node, transform),
+            };
+            if parents[index].is_none() {
+                roots.push(id);
+            }
+            ids[index] = Some(id);
+        });
+
+        Ok(roots)
+    }
+}
+
+/// Splits a GLB container into its JSON chunk and optional binary chunk.
+fn parse_glb(bytes: &[u8]) -> Result<(&[u8], Option<Vec<u8>>), &'static str> {
+    let u32_at = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+    };
+
+    if u32_at(4) != Some(2) {
+        return Err("only GLB version 2 is supported");
+    }
+    let length = (u32_at(8).ok_or("the GLB header is truncated")? as usize).min(bytes.len());
+
+    let mut json = None;
+    let mut binary = None;
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = u32_at(offset).ok_or("a GLB chunk is truncated")? as usize;
+        let chunk_type = u32_at(offset + 4).ok_or("a GLB chunk is truncated")?;
+        let data = bytes
+            .get(offset + 8..offset + 8 + chunk_length)
+            .ok_or("a GLB chunk runs past the end of the file")?;
+
+        match chunk_type {
+            GLB_JSON_CHUNK if json.is_none() => json = Some(data),
+            GLB_BIN_CHUNK if binary.is_none() => binary = Some(data.to_vec()),
+            _ => {}
+        }
+        offset += 8 + chunk_length.next_multiple_of(4);
+    }
+
+    Ok((json.ok_or("the GLB file has no JSON chunk")?, binary))
+}
+
+struct Loader<'a> {
+    root: &'a JsonValue,
+    directory: PathBuf,
+    document: GltfDocument,
+}
+
+impl Loader<'_> {
+    fn load(&mut self, mut binary: Option<Vec<u8>>, read: &mut impl FnMut(&Path) -> io::Result<Vec<u8>>) -> Result<(), String> {
+        let root = self.root;
+        let version = root.get("asset").get("version").as_str().unwrap_or("");
+        if !version.starts_with("2.") {
+            return Err(format!("glTF version `{}` is not supported, expected 2.x", version));
+        }
+        for extension in root.get("extensionsRequired").elements() {
+            let extension = extension.as_str().unwrap_or("");
+            if !SUPPORTED_EXTENSIONS.contains(&extension) {
+                return Err(format!("the required extension `{}` is not supported", extension));
+            }
+        }
+
+        for (index, buffer) in root.get("buffers").elements().iter().enumerate() {
+            let length = buffer.get("byteLength").as_usize().unwrap_or(0);
+            let data = match buffer.get("uri").as_str() {
+                Some(uri) => self.read_uri(uri, read)?.1,
+                None if index == 0 => binary.take().ok_or("buffer 0 has no uri and there is no GLB binary chunk")?,
+                None => return Err(format!("buffer {} has no uri", index)),
+            };
+            if data.len() < length {
+                return Err(format!("buffer {} holds {} bytes, expected {}", index, data.len(), length));
+            }
+            self.document.buffers.push(data);
+        }
+
+        self.document.images = self.images(read)?;
+        self.document.textures = self.textures();
+        self.document.materials = self.materials();
+        self.document.meshes = self.meshes()?;
+        self.document.cameras = self.cameras();
+        self.document.lights = self.lights()?;
+        self.document.nodes = self.nodes()?;
+        self.document.scenes = root
+            .get("scenes")
+            .elements()
+            .iter()
+            .map(|scene| GltfScene {
+                name: name(scene),
+                nodes: indices(scene.get("nodes")),
+            })
+            .collect();
+        self.document.scene = root.get("scene").as_usize();
+        self.document.skins = self.skins()?;
+        self.document.animations = self.animations()?;
+
+        self.check_references()
+    }
+
+    /// Reads a data URI or a file next to the glTF file. Returns the MIME type of data URIs.
+    fn read_uri(
+        &self,
+        uri: &str,
+        read: &mut impl FnMut(&Path) -> io::Result<Vec<u8>>,
+    ) -> Result<(Option<String>, Vec<u8>), String> {
+        if let Some(data_uri) = uri.strip_prefix("data:") {
+            let (header, data) = data_uri.split_once(',').ok_or("a data URI has no `,`")?;
+            let mime_type = header.split(';').next().filter(|mime_type| !mime_type.is_empty());
+            if !header.ends_with(";base64") {
+                return Err("only base64 data URIs are supported".to_string());
+            }
+            let data = base64::decode(data).ok_or("a data URI is not valid base64")?;
+            return Ok((mime_type.map(str::to_string), data));
+        }
+
+        let path = self.directory.join(percent_decode(uri));
+        let data = read(&path).map_err(|error| format!("can't read `{}`: {}", path.display(), error))?;
+        Ok((None, data))
+    }
+
+    fn buffer_view(&self, index: usize) -> Result<(&[u8], Option<usize>), String> {
+        let view = self.root.get("bufferViews").at(index);
+        let buffer_index = view.get("buffer").as_usize().ok_or(format!("buffer view {} doesn't exist", index))?;
+        let buffer = self
+            .document
+            .buffers
+            .get(buffer_index)
+            .ok_or(format!("buffer view {} uses the missing buffer {}", index, buffer_index))?;
+
+        let offset = view.get("byteOffset").as_usize().unwrap_or(0);
+        let length = view.get("byteLength").as_usize().unwrap_or(0);
+        let data = buffer
+            .get(offset..offset.saturating_add(length))
+            .ok_or(format!("buffer view {} runs past the end of buffer {}", index, buffer_index))?;
+        Ok((data, view.get("byteStride").as_usize()))
+    }
+
+    fn accessor(&self, index: usize) -> Result<Accessor, String> {
+        let accessor = self.root.get("accessors").at(index);
+        if accessor.is_null() {
+            return Err(format!("accessor {} doesn't exist", index));
+        }
+
+        let count = accessor.get("count").as_usize().unwrap_or(0);
+        let component_type = accessor.get("componentType").as_usize().unwrap_or(0) as u32;
+        let components = match accessor.get("type").as_str().unwrap_or("") {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" | "MAT2" => 4,
+            "MAT3" => 9,
+            "MAT4" => 16,
+            other => return Err(format!("accessor {} has the unknown type `{}`", index, other)),
+        };
+        let component_size = component_size(component_type)
+            .ok_or(format!("accessor {} has the unknown component type {}", index, component_type))?;
+
+        let mut values = vec![0.0; count * components];
+        if let Some(view) = accessor.get("bufferView").as_usize() {
+            let (data, stride) = self.buffer_view(view)?;
+            let offset = accessor.get("byteOffset").as_usize().unwrap_or(0);
+            let element_size = component_size * components;
+            let stride = stride.unwrap_or(element_size);
+
+            if count > 0 && offset + stride * (count - 1) + element_size > data.len() {
+                return Err(format!("accessor {} runs past the end of buffer view {}", index, view));
+            }
+            for (element, values) in values.chunks_exact_mut(components.max(1)).enumerate() {
+                let start = offset + element * stride;
+                for (component, value) in values.iter_mut().enumerate() {
+                    *value = read_component(&data[start + component * component_size..], component_type);
+                }
+            }
+        }
+
+        let sparse = accessor.get("sparse");
+        if !sparse.is_null() {
+            self.apply_sparse(index, sparse, components, &mut values)?;
+        }
+
+        Ok(Accessor {
+            values,
+            components,
+            component_type,
+            normalized: accessor.get("normalized").as_bool().unwrap_or(false),
+        })
+    }
+
+    fn apply_sparse(&self, index: usize, sparse: &JsonValue, components: usize, values: &mut [f64]) -> Result<(), String> {
+        let count = sparse.get("count").as_usize().unwrap_or(0);
+        let component_type = self.root.get("accessors").at(index).get("componentType").as_usize().unwrap_or(0) as u32;
+        let sparse_indices = sparse.get("indices");
+        let index_type = sparse_indices.get("componentType").as_usize().unwrap_or(0) as u32;
+        let index_size = component_size(index_type).ok_or(format!("accessor {} has invalid sparse indices", index))?;
+        let value_size = component_size(component_type).unwrap_or(4);
+
+        let (index_data, _) = self.buffer_view(sparse_indices.get("bufferView").as_usize().unwrap_or(usize::MAX))?;
+        let index_data = index_data
+            .get(sparse_indices.get("byteOffset").as_usize().unwrap_or(0)..)
+            .unwrap_or(&[]);
+        let sparse_values = sparse.get("values");
+        let (value_data, _) = self.buffer_view(sparse_values.get("bufferView").as_usize().unwrap_or(usize::MAX))?;
+        let value_data = value_data
+            .get(sparse_values.get("byteOffset").as_usize().unwrap_or(0)..)
+            .unwrap_or(&[]);
+
+        if index_data.len() < count * index_size || value_data.len() < count * components * value_size {
+            return Err(format!("the sparse data of accessor {} is truncated", index));
+        }
+        for i in 0..count {
+            let element = read_component(&index_data[i * index_size..], index_type) as usize;
+            for component in 0..components {
+                let value = read_component(&value_data[(i * components + component) * value_size..], component_type);
+                *values
+                    .get_mut(element * components + component)
+                    .ok_or(format!("a sparse index of accessor {} is out of range", index))? = value;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn images(&self, read: &mut impl FnMut(&Path) -> io::Result<Vec<u8>>) -> Result<Vec<GltfImage>, String> {
+        let mut images = Vec::new();
+        for image in self.root.get("images").elements() {
+            let mime_type = image.get("mimeType").as_str().map(str::to_string);
+            let source = match (image.get("uri").as_str(), image.get("bufferView").as_usize()) {
+                (Some(uri), _) if uri.starts_with("data:") => {
+                    let (data_mime_type, data) = self.read_uri(uri, read)?;
+                    GltfImageSource::Embedded { mime_type: mime_type.or(data_mime_type), data }
+                }
+                (Some(uri), _) => GltfImageSource::File(self.directory.join(percent_decode(uri)).to_string_lossy().to_string()),
+                (None, Some(view)) => GltfImageSource::Embedded { mime_type, data: self.buffer_view(view)?.0.to_vec() },
+                (None, None) => return Err(format!("image {} has neither a uri nor a buffer view", images.len())),
+            };
+            images.push(GltfImage { name: name(image), source });
+        }
+        Ok(images)
+    }
+
+    fn textures(&self) -> Vec<GltfTexture> {
+        self.root
+            .get("textures")
+            .elements()
+            .iter()
+            .map(|texture| {
+                let sampler = self.root.get("samplers").at(texture.get("sampler").as_usize().unwrap_or(usize::MAX));
+                let gl_enum = |key: &str| sampler.get(key).as_usize().map(|value| value as u32);
+                GltfTexture {
+                    image: texture.get("source").as_usize(),
+                    mag_filter: gl_enum("magFilter"),
+                    min_filter: gl_enum("minFilter"),
+                    wrap_s: gl_enum("wrapS"),
+                    wrap_t: gl_enum("wrapT"),
+                }
+            })
+            .collect()
+    }
+
+    fn materials(&self) -> Vec<GltfMaterial> {
+        let defaults = GltfMaterial::default();
+        self.root
+            .get("materials")
+            .elements()
+            .iter()
+            .map(|material| {
+                let pbr = material.get("pbrMetallicRoughness");
+                GltfMaterial {
+                    name: name(material),
+                    base_color_factor: array(pbr.get("baseColorFactor")).unwrap_or(defaults.base_color_factor),
+                    base_color_texture: texture_ref(pbr.get("baseColorTexture")),
+                    metallic_factor: pbr.get("metallicFactor").as_f32().unwrap_or(defaults.metallic_factor),
+                    roughness_factor: pbr.get("roughnessFactor").as_f32().unwrap_or(defaults.roughness_factor),
+                    metallic_roughness_texture: texture_ref(pbr.get("metallicRoughnessTexture")),
+                    normal_texture: texture_ref(material.get("normalTexture")),
+                    normal_scale: material.get("normalTexture").get("scale").as_f32().unwrap_or(defaults.normal_scale),
+                    occlusion_texture: texture_ref(material.get("occlusionTexture")),
+                    occlusion_strength: material
+                        .get("occlusionTexture")
+                        .get("strength")
+                        .as_f32()
+                        .unwrap_or(defaults.occlusion_strength),
+                    emissive_texture: texture_ref(material.get("emissiveTexture")),
+                    emissive_factor: array(material.get("emissiveFactor")).unwrap_or(defaults.emissive_factor),
+                    alpha_mode: match material.get("alphaMode").as_str() {
+                        Some("MASK") => AlphaMode::Mask,
+                        Some("BLEND") => AlphaMode::Blend,
+                        _ => AlphaMode::Opaque,
+                    },
+                    alpha_cutoff: material.get("alphaCutoff").as_f32().unwrap_or(defaults.alpha_cutoff),
+                    double_sided: material.get("doubleSided").as_bool().unwrap_or(false),
+                }
+            })
+            .collect()
+    }
+
+    fn meshes(&self) -> Result<Vec<GltfMesh>, String> {
+        let mut meshes = Vec::new();
+        for mesh in self.root.get("meshes").elements() {
+            let mut primitives = Vec::new();
+            for primitive in mesh.get("primitives").elements() {
+                primitives.push(self.primitive(primitive)?);
+            }
+            meshes.push(GltfMesh { name: name(mesh), primitives });
+        }
+        Ok(meshes)
+    }
+
+    fn primitive(&self, primitive: &JsonValue) -> Result<GltfPrimitive, String> {
+        let attributes = primitive.get("attributes");
+        let attribute = |name: &str| -> Result<Option<Accessor>, String> {
+            attributes.get(name).as_usize().map(|index| self.accessor(index)).transpose()
+        };
+
+        let positions: Vec<[f32; 3]> = attribute("POSITION")?
+            .ok_or("a primitive has no POSITION attribute")?
+            .chunks();
+        let indices: Vec<u32> = match primitive.get("indices").as_usize() {
+            Some(index) => self.accessor(index)?.values.iter().map(|value| *value as u32).collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        if let Some(index) = indices.iter().find(|index| **index as usize >= positions.len()) {
+            return Err(format!("a primitive index {} is past its {} vertices", index, positions.len()));
+        }
+
+        let gltf_primitive = GltfPrimitive {
+            normals: attribute("NORMAL")?.map(|accessor| accessor.chunks()).unwrap_or_default(),
+            tangents: attribute("TANGENT")?.map(|accessor| accessor.chunks()).unwrap_or_default(),
+            texture_coords: self.texture_coord_sets(attributes)?,
+            colors: attribute("COLOR_0")?.map(|accessor| accessor.chunks()).unwrap_or_default(),
+            joints: attribute("JOINTS_0")?
+                .map(|accessor| {
+                    accessor
+                        .values
+                        .chunks_exact(accessor.components)
+                        .map(|chunk| std::array::from_fn(|i| chunk.get(i).copied().unwrap_or(0.0) as u16))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            weights: attribute("WEIGHTS_0")?.map(|accessor| accessor.chunks()).unwrap_or_default(),
+            positions,
+            indices,
+            material: primitive.get("material").as_usize(),
+            mode: primitive.get("mode").as_usize().map(|mode| mode as u32).unwrap_or(gl::TRIANGLES),
+        };
+
+        // Missing attributes are empty; present ones need a value per vertex.
+        let vertex_count = gltf_primitive.positions.len();
+        let mut counts = vec![
+            ("NORMAL".to_string(), gltf_primitive.normals.len()),
+            ("TANGENT".to_string(), gltf_primitive.tangents.len()),
+            ("COLOR_0".to_string(), gltf_primitive.colors.len()),
+            ("JOINTS_0".to_string(), gltf_primitive.joints.len()),
+            ("WEIGHTS_0".to_string(), gltf_primitive.weights.len()),
+        ];
+        counts.extend(
+            gltf_primitive
+                .texture_coords
+                .iter()
+                .enumerate()
+                .map(|(set, texture_coords)| (format!("TEXCOORD_{}", set), texture_coords.len())),
+        );
+        for (name, count) in counts {
+            let present = attributes.get(&name).as_usize().is_some();
+            if present && count != vertex_count {
+                return Err(format!("a primitive has {} {} values for {} positions", count, name, vertex_count));
+            }
+        }
+
+        Ok(gltf_primitive)
+    }
+
+    /// `TEXCOORD_0`, `TEXCOORD_1`… up to the first missing set or the engine's limit.
+    fn texture_coord_sets(&self, attributes: &JsonValue) -> Result<Vec<Vec<[f32; 2]>>, String> {
+        let mut sets = Vec::new();
+        for set in 0..MAX_TEXTURE_COORD_SETS {
+            let Some(index) = attributes.get(&format!("TEXCOORD_{}", set)).as_usize() else {
+                break;
+            };
+            sets.push(self.accessor(index)?.chunks());
+        }
+        Ok(sets)
+    }
+
+    fn cameras(&self) -> Vec<GltfCamera> {
+        self.root
+            .get("cameras")
+            .elements()
+            .iter()
+            .map(|camera| {
+                let projection = if camera.get("type").as_str() == Some("orthographic") {
+                    let orthographic = camera.get("orthographic");
+                    GltfProjection::Orthographic {
+                        x_mag: orthographic.get("xmag").as_f32().unwrap_or(1.0),
+                        y_mag: orthographic.get("ymag").as_f32().unwrap_or(1.0),
+                        z_near: orthographic.get("znear").as_f32().unwrap_or(0.0),
+                        z_far: orthographic.get("zfar").as_f32().unwrap_or(100.0),
+                    }
+                } else {
+                    let perspective = camera.get("perspective");
+                    GltfProjection::Perspective {
+                        y_fov: perspective.get("yfov").as_f32().unwrap_or(std::f32::consts::FRAC_PI_4),
+                        aspect_ratio: perspective.get("aspectRatio").as_f32(),
+                        z_near: perspective.get("znear").as_f32().unwrap_or(0.1),
+                        z_far: perspective.get("zfar").as_f32(),
+                    }
+                };
+                GltfCamera { name: name(camera), projection }
+            })
+            .collect()
+    }
+
+    fn lights(&self) -> Result<Vec<GltfLight>, String> {
+        let mut lights = Vec::new();
+        for light in self.root.get("extensions").get("KHR_lights_punctual").get("lights").elements() {
+            let color = array(light.get("color")).map(Vec3::from).unwrap_or(Vec3::ONE);
+            let intensity = light.get("intensity").as_f32().unwrap_or(1.0);
+            let range = light.get("range").as_f32().unwrap_or(f32::INFINITY);
+
+            let light_value = match light.get("type").as_str() {
+                Some("directional") => Light::directional(color, intensity),
+                Some("point") => Light::point(color, intensity, range),
+                Some("spot") => {
+                    let spot = light.get("spot");
+                    Light::spot(
+                        color,
+                        intensity,
+                        range,
+                        spot.get("innerConeAngle").as_f32().unwrap_or(0.0),
+                        spot.get("outerConeAngle").as_f32().unwrap_or(std::f32::consts::FRAC_PI_4),
+                    )
+                }
+                other => return Err(format!("light {} has the unknown type `{}`", lights.len(), other.unwrap_or(""))),
+            };
+            lights.push(GltfLight { name: name(light), light: light_value });
+        }
+        Ok(lights)
+    }
+
+    fn nodes(&self) -> Result<Vec<GltfNode>, String> {
+        let mut nodes = Vec::new();
+        for node in self.root.get("nodes").elements() {
+            let (scale, rotation, translation) = match array::<16>(node.get("matrix")) {
+                Some(matrix) => Mat4::from_cols_array(&matrix).to_scale_rotation_translation(),
+                None => (
+                    array(node.get("scale")).map(Vec3::from).unwrap_or(Vec3::ONE),
+                    array(node.get("rotation")).map(Quat::from).unwrap_or(Quat::IDENTITY),
+                    array(node.get("translation")).map(Vec3::from).unwrap_or(Vec3::ZERO),
+                ),
+            };
+
+            nodes.push(GltfNode {
+                name: name(node),
+                children: indices(node.get("children")),
+                mesh: node.get("mesh").as_usize(),
+                camera: node.get("camera").as_usize(),
+                light: node.get("extensions").get("KHR_lights_punctual").get("light").as_usize(),
+                skin: node.get("skin").as_usize(),
+                translation,
+                rotation,
+                scale,
+            });
+        }
+        Ok(nodes)
+    }
+
+    fn skins(&self) -> Result<Vec<GltfSkin>, String> {
+        let mut skins = Vec::new();
+        for skin in self.root.get("skins").elements() {
+            let joints = indices(skin.get("joints"));
+            let inverse_bind_matrices = match skin.get("inverseBindMatrices").as_usize() {
+                Some(index) => self
+                    .accessor(index)?
+                    .floats()
+                    .chunks_exact(16)
+                    .map(|matrix| Mat4::from_cols_array(matrix.try_into().unwrap()))
+                    .collect(),
+                None => vec![Mat4::IDENTITY; joints.len()],
+            };
+
+            skins.push(GltfSkin {
+                name: name(skin),
+                joints,
+                inverse_bind_matrices,
+                skeleton: skin.get("skeleton").as_usize(),
+            });
+        }
+        Ok(skins)
+    }
+
+    fn animations(&self) -> Result<Vec<GltfAnimation>, String> {
+        let mut animations = Vec::new();
+        for animation in self.root.get("animations").elements() {
+            let mut samplers = Vec::new();
+            for sampler in animation.get("samplers").elements() {
+                let input = self.accessor(sampler.get("input").as_usize().unwrap_or(usize::MAX))?;
+                let output = self.accessor(sampler.get("output").as_usize().unwrap_or(usize::MAX))?;
+                samplers.push(GltfAnimationSampler {
+                    input: input.floats(),
+                    components: output.components,
+                    output: output.floats(),
+                    interpolation: match sampler.get("interpolation").as_str() {
+                        Some("STEP") => Interpolation::Step,
+                        Some("CUBICSPLINE") => Interpolation::CubicSpline,
+                        _ => Interpolation::Linear,
+                    },
+                });
+            }
+
+            let mut channels = Vec::new();
+            for channel in animation.get("channels").elements() {
+                let target = channel.get("target");
+                let path = match target.get("path").as_str() {
+                    Some("translation") => AnimationPath::Translation,
+                    Some("rotation") => AnimationPath::Rotation,
+                    Some("scale") => AnimationPath::Scale,
+                    Some("weights") => AnimationPath::Weights,
+                    // Paths added by extensions (e.g. KHR_animation_pointer) are skipped.
+                    _ => continue,
+                };
+                channels.push(GltfChannel {
+                    sampler: channel.get("sampler").as_usize().ok_or("an animation channel has no sampler")?,
+                    node: target.get("node").as_usize(),
+                    path,
+                });
+            }
+
+            animations.push(GltfAnimation { name: name(animation), channels, samplers });
+        }
+        Ok(animations)
+    }
+
+    /// Rejects indices that point outside their arrays, so later lookups can index directly.
+    fn check_references(&self) -> Result<(), String> {
+        let document = &self.document;
+        let check = |what: &str, index: Option<usize>, count: usize| match index {
+            Some(index) if index >= count => Err(format!("{} {} doesn't exist", what, index)),
+            _ => Ok(()),
+        };
+
+        for texture in &document.textures {
+            check("image", texture.image, document.images.len())?;
+        }
+        for material in &document.materials {
+            for texture in [
+                material.base_color_texture,
+                material.metallic_roughness_texture,
+                material.normal_texture,
+                material.occlusion_texture,
+                material.emissive_texture,
+            ] {
+                check("texture", texture.map(|texture| texture.texture), document.textures.len())?;
+            }
+        }
+        for primitive in document.meshes.iter().flat_map(|mesh| &mesh.primitives) {
+            check("material", primitive.material, document.materials.len())?;
+        }
+        for node in &document.nodes {
+            check("mesh", node.mesh, document.meshes.len())?;
+            check("camera", node.camera, document.cameras.len())?;
+            check("light", node.light, document.lights.len())?;
+            check("skin", node.skin, document.skins.len())?;
+            for child in &node.children {
+                check("node", Some(*child), document.nodes.len())?;
+            }
+        }
+        for scene in &document.scenes {
+            for node in &scene.nodes {
+                check("node", Some(*node), document.nodes.len())?;
+            }
+        }
+        check("scene", document.scene, document.scenes.len())?;
+        for skin in &document.skins {
+            for joint in skin.joints.iter().chain(&skin.skeleton) {
+                check("node", Some(*joint), document.nodes.len())?;
+            }
+        }
+        for animation in &document.animations {
+            for channel in &animation.channels {
+                check("animation sampler", Some(channel.sampler), animation.samplers.len())?;
+                check("node", channel.node, document.nodes.len())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn name(value: &JsonValue) -> String {
+    value.get("name").as_str().unwrap_or("").to_string()
+}
+
+fn indices(value: &JsonValue) -> Vec<usize> {
+    value.elements().iter().filter_map(JsonValue::as_usize).collect()
+}
+
+fn array<const N: usize>(value: &JsonValue) -> Option<[f32; N]> {
+    value.as_f32_vec()?.try_into().ok()
+}
+
+fn texture_ref(value: &JsonValue) -> Option<GltfTextureRef> {
+    Some(GltfTextureRef {
+        texture: value.get("index").as_usize()?,
+        tex_coord: value.get("texCoord").as_usize().unwrap_or(0) as u32,
+    })
+}
+
+fn component_size(component_type: u32) -> Option<usize> {
+    match component_type {
+        5120 | 5121 => Some(1),
+        5122 | 5123 => Some(2),
+        5125 | 5126 => Some(4),
+        _ => None,
+    }
+}
+
+/// Reads one little-endian component; `data` must hold at least its size.
+fn read_component(data: &[u8], component_type: u32) -> f64 {
+    match component_type {
+        5120 => data[0] as i8 as f64,
+        5121 => data[0] as f64,
+        5122 => i16::from_le_bytes([data[0], data[1]]) as f64,
+        5123 => u16::from_le_bytes([data[0], data[1]]) as f64,
+        5125 => u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as f64,
+        _ => f32::from_le_bytes([data[0], data[1], data[2], data[3]]) as f64,
+    }
+}
+
+/// Decodes `%XX` escapes in a relative URI, e.g. `my%20texture.png`.
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| bytes.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUAD_GLTF: &str = "res/objects/quad.gltf";
+    const QUAD_GLB: &str = "res/objects/quad.glb";
+
+    /// Loads `json` as if it were `quad.gltf`, next to the real `quad.bin`.
+    fn load_json(json: &str) -> Result<GltfDocument, ERRUranXError> {
+        GltfDocument::load_with(QUAD_GLTF, |path| {
+            if path == Path::new(QUAD_GLTF) {
+                Ok(json.as_bytes().to_vec())
+            } else {
+                std::fs::read(path)
+            }
+        })
+    }
+
+    /// `quad.gltf` with `from` replaced by `to`, which must occur in it.
+    fn edited(from: &str, to: &str) -> String {
+        let json = std::fs::read_to_string(QUAD_GLTF).unwrap();
+        assert!(json.contains(from), "`{from}` is not in the fixture");
+        json.replace(from, to)
+    }
+
+    fn gltf_error(result: Result<GltfDocument, ERRUranXError>) -> String {
+        match result {
+            Err(ERRUranXError::GLTF_ERROR { message, .. }) => message,
+            other => panic!("expected a glTF error, got {:?}", other.map(|document| document.path)),
+        }
+    }
+
+    #[test]
+    fn loads_gltf_with_an_external_buffer() {
+        let mut read_paths = Vec::new();
+        let document = GltfDocument::load_with(QUAD_GLTF, |path| {
+            read_paths.push(path.to_path_buf());
+            std::fs::read(path)
+        })
+        .unwrap();
+
+        assert_eq!(read_paths, [PathBuf::from(QUAD_GLTF), PathBuf::from("res/objects/quad.bin")]);
+        assert_eq!(document.buffers[0].len(), 140);
+
+        let primitive = &document.meshes[0].primitives[0];
+        assert_eq!(primitive.positions, [[-1.0, -1.0, 0.0], [1.0, -1.0, 0.0], [1.0, 1.0, 0.0], [-1.0, 1.0, 0.0]]);
+        assert_eq!(primitive.normals, [[0.0, 0.0, 1.0]; 4]);
+        assert_eq!(primitive.texture_coords, [vec![[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]]]);
+        assert_eq!(primitive.indices, [0, 1, 2, 0, 2, 3]);
+        assert!(primitive.tangents.is_empty() && primitive.colors.is_empty());
+        assert_eq!(primitive.material, Some(0));
+        assert_eq!(primitive.mode, gl::TRIANGLES);
+
+        let material = &document.materials[0];
+        assert_eq!(material.name, "red");
+        assert_eq!(material.base_color_factor, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(material.metallic_factor, 0.0);
+        assert_eq!(material.roughness_factor, 0.5);
+        assert!(material.double_sided);
+        assert_eq!(material.alpha_mode, AlphaMode::Opaque);
+    }
+
+    #[test]
+    fn walks_nodes_with_world_matrices() {
+        let document = GltfDocument::load(QUAD_GLTF).unwrap();
+        assert_eq!(document.scene, Some(0));
+        assert_eq!(document.root_nodes(), [0]);
+
+        let mut visited = Vec::new();
+        document.walk(|index, node, world_matrix| {
+            visited.push((index, node.name.clone(), world_matrix.transform_point3(Vec3::new(1.0, 1.0, 0.0))));
+        });
+        assert_eq!(
+            visited,
+            [
+                (0, "root".to_string(), Vec3::new(1.0, 2.0, 0.0)),
+                (1, "quad".to_string(), Vec3::new(2.0, 3.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn glb_matches_gltf() {
+        let gltf = GltfDocument::load(QUAD_GLTF).unwrap();
+        let glb = GltfDocument::load(QUAD_GLB).unwrap();
+
+        assert_eq!(glb.buffers, gltf.buffers);
+        assert_eq!(glb.meshes, gltf.meshes);
+        assert_eq!(glb.materials, gltf.materials);
+        assert_eq!(glb.nodes, gltf.nodes);
+        assert_eq!(glb.scenes, gltf.scenes);
+    }
+
+    #[test]
+    fn rejects_broken_glb_containers() {
+        let glb = std::fs::read(QUAD_GLB).unwrap();
+        let load = |bytes: Vec<u8>| gltf_error(GltfDocument::load_with(QUAD_GLB, move |_| Ok(bytes.clone())));
+
+        let mut version_1 = glb.clone();
+        version_1[4] = 1;
+        assert_eq!(load(version_1), "only GLB version 2 is supported");
+        assert_eq!(load(glb[..10].to_vec()), "the GLB header is truncated");
+        assert_eq!(load(glb[..40].to_vec()), "a GLB chunk runs past the end of the file");
+        assert_eq!(load(glb[..12].to_vec()), "the GLB file has no JSON chunk");
+    }
+
+    #[test]
+    fn rejects_attribute_counts_that_differ_from_position() {
+        let json = edited(r#""count": 4, "type": "VEC3" }"#, r#""count": 3, "type": "VEC3" }"#);
+        assert_eq!(gltf_error(load_json(&json)), "a primitive has 3 NORMAL values for 4 positions");
+
+        let json = edited(r#""count": 4, "type": "VEC2""#, r#""count": 2, "type": "VEC2""#);
+        assert_eq!(gltf_error(load_json(&json)), "a primitive has 2 TEXCOORD_0 values for 4 positions");
+    }
+
+    #[test]
+    fn rejects_indices_past_the_vertices() {
+        let json = edited(r#""count": 4, "type": "VEC3", "min""#, r#""count": 3, "type": "VEC3", "min""#);
+        assert_eq!(gltf_error(load_json(&json)), "a primitive index 3 is past its 3 vertices");
+    }
+
+    #[test]
+    fn rejects_bad_references() {
+        let json = edited(r#""material": 0"#, r#""material": 1"#);
+        assert_eq!(gltf_error(load_json(&json)), "material 1 doesn't exist");
+
+        let json = edited(r#""byteOffset": 128, "byteLength": 12"#, r#""byteOffset": 132, "byteLength": 12"#);
+        assert_eq!(gltf_error(load_json(&json)), "buffer view 1 runs past the end of buffer 0");
+
+        let json = edited(r#""version": "2.0""#, r#""version": "1.0""#);
+        assert_eq!(gltf_error(load_json(&json)), "glTF version `1.0` is not supported, expected 2.x");
+
+        let json = edited(r#""scene": 0,"#, r#""scene": 0, "extensionsRequired": ["KHR_draco_mesh_compression"],"#);
+        assert_eq!(gltf_error(load_json(&json)), "the required extension `KHR_draco_mesh_compression` is not supported");
+    }
+
+    #[test]
+    fn locates_json_errors() {
+        let json = edited(r#""scene": 0,"#, r#""scene": 0,,"#);
+        match load_json(&json) {
+            Err(ERRUranXError::PARSE_ERROR { line, column, message, .. }) => {
+                assert_eq!((line, column), (3, 14));
+                assert_eq!(message, "expected a member name");
+            }
+            other => panic!("expected a parse error, got {:?}", other.map(|document| document.path)),
+        }
+    }
+
+    #[test]
+    fn reports_missing_files() {
+        let result = GltfDocument::load("res/objects/missing.gltf");
+        assert!(matches!(result, Err(ERRUranXError::IO_ERROR { .. })));
+
+        let json = edited(r#""uri": "quad.bin""#, r#""uri": "missing.bin""#);
+        assert!(gltf_error(load_json(&json)).starts_with("can't read `res/objects/missing.bin`"));
+    }
+}
@@ -0,0 +1,288 @@
+use super::shader_program::ShaderProgram;
+use super::texture::Texture;
+use super::uniform::{is_sampler, Sampler, Uniform};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+/// Named texture inputs of a material. Each slot has its own texture unit and sampler uniform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TextureSlot {
+    Albedo,
+    Normal,
+    MetallicRoughness,
+    Emissive,
+    Occlusion,
+    /// Specular color, from MTL `map_Ks`.
+    Specular,
+    /// Alpha, from MTL `map_d`.
+    Opacity,
+}
+
+impl TextureSlot {
+    pub const ALL: [TextureSlot; 7] = [
+        TextureSlot::Albedo,
+        TextureSlot::Normal,
+        TextureSlot::MetallicRoughness,
+        TextureSlot::Emissive,
+        TextureSlot::Occlusion,
+        TextureSlot::Specular,
+        TextureSlot::Opacity,
+    ];
+
+    /// Name used in scene files, e.g. `metallic_roughness`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TextureSlot::Albedo => "albedo",
+            TextureSlot::Normal => "normal",
+            TextureSlot::MetallicRoughness => "metallic_roughness",
+            TextureSlot::Emissive => "emissive",
+            TextureSlot::Occlusion => "occlusion",
+            TextureSlot::Specular => "specular",
+            TextureSlot::Opacity => "opacity",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|slot| slot.name() == name)
+    }
+
+    /// The `sampler2D` uniform the slot is bound to, e.g. `albedoMap`.
+    pub fn sampler_name(self) -> &'static str {
+        match self {
+            TextureSlot::Albedo => "albedoMap",
+            TextureSlot::Normal => "normalMap",
+            TextureSlot::MetallicRoughness => "metallicRoughnessMap",
+            TextureSlot::Emissive => "emissiveMap",
+            TextureSlot::Occlusion => "occlusionMap",
+            TextureSlot::Specular => "specularMap",
+            TextureSlot::Opacity => "opacityMap",
+        }
+    }
+
+    /// Optional `bool` uniform telling the shader whether the slot has a texture, e.g. `hasAlbedoMap`.
+    pub fn flag_name(self) -> &'static str {
+        match self {
+            TextureSlot::Albedo => "hasAlbedoMap",
+            TextureSlot::Normal => "hasNormalMap",
+            TextureSlot::MetallicRoughness => "hasMetallicRoughnessMap",
+            TextureSlot::Emissive => "hasEmissiveMap",
+            TextureSlot::Occlusion => "hasOcclusionMap",
+            TextureSlot::Specular => "hasSpecularMap",
+            TextureSlot::Opacity => "hasOpacityMap",
+        }
+    }
+
+    pub fn texture_unit(self) -> u32 {
+        self as u32
+    }
+}
+
+impl Display for TextureSlot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A scalar or vector material parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaterialValue {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Int(i32),
+    Bool(bool),
+}
+
+impl MaterialValue {
+    /// A float or float vector from 1 to 4 components, as written in scene files.
+    pub fn from_floats(values: &[f32]) -> Option<Self> {
+        match *values {
+            [x] => Some(MaterialValue::Float(x)),
+            [x, y] => Some(MaterialValue::Vec2([x, y])),
+            [x, y, z] => Some(MaterialValue::Vec3([x, y, z])),
+            [x, y, z, w] => Some(MaterialValue::Vec4([x, y, z, w])),
+            _ => None,
+        }
+    }
+
+    pub fn accepts(&self, gl_type: gl::types::GLenum) -> bool {
+        match self {
+            MaterialValue::Float(_) => <f32 as Uniform>::accepts(gl_type),
+            MaterialValue::Vec2(_) => <[f32; 2] as Uniform>::accepts(gl_type),
+            MaterialValue::Vec3(_) => <[f32; 3] as Uniform>::accepts(gl_type),
+            MaterialValue::Vec4(_) => <[f32; 4] as Uniform>::accepts(gl_type),
+            MaterialValue::Int(_) => <i32 as Uniform>::accepts(gl_type),
+            MaterialValue::Bool(_) => <bool as Uniform>::accepts(gl_type),
+        }
+    }
+
+    fn set(&self, shader: &ShaderProgram, name: &str) -> bool {
+        match self {
+            MaterialValue::Float(value) => shader.set_uniform(name, value),
+            MaterialValue::Vec2(value) => shader.set_uniform(name, value),
+            MaterialValue::Vec3(value) => shader.set_uniform(name, value),
+            MaterialValue::Vec4(value) => shader.set_uniform(name, value),
+            MaterialValue::Int(value) => shader.set_uniform(name, value),
+            MaterialValue::Bool(value) => shader.set_uniform(name, value),
+        }
+    }
+}
+
+/// Values written to the standard parameters a material doesn't set, so one material never
+/// inherits another's values from the program state.
+pub const DEFAULT_PARAMETERS: &[(&str, MaterialValue)] = &[
+    ("albedoColor", MaterialValue::Vec4([1.0, 1.0, 1.0, 1.0])),
+    ("ambientColor", MaterialValue::Vec3([0.0, 0.0, 0.0])),
+    ("specularColor", MaterialValue::Vec3([0.0, 0.0, 0.0])),
+    ("emissiveColor", MaterialValue::Vec3([0.0, 0.0, 0.0])),
+    ("shininess", MaterialValue::Float(0.0)),
+    ("metallic", MaterialValue::Float(0.0)),
+    ("roughness", MaterialValue::Float(1.0)),
+];
+
+/// How a surface is shaded: a shader, its parameters and its textures.
+///
+/// Materials are shared between meshes through `Rc`. A material without a shader of its own
+/// is drawn with whatever program the renderer passes to [`Material::bind`].
+#[derive(Debug, Clone, Default)]
+pub struct Material {
+    pub name: String,
+    shader: Option<Rc<ShaderProgram>>,
+    parameters: BTreeMap<String, MaterialValue>,
+    textures: BTreeMap<TextureSlot, Rc<Texture>>,
+}
+
+impl Material {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_shader(mut self, shader: Rc<ShaderProgram>) -> Self {
+        self.shader = Some(shader);
+        self
+    }
+
+    pub fn with_parameter(mut self, name: &str, value: MaterialValue) -> Self {
+        self.set_parameter(name, value);
+        self
+    }
+
+    pub fn with_texture(mut self, slot: TextureSlot, texture: Rc<Texture>) -> Self {
+        self.set_texture(slot, texture);
+        self
+    }
+
+    pub fn shader(&self) -> Option<&Rc<ShaderProgram>> {
+        self.shader.as_ref()
+    }
+
+    pub fn set_shader(&mut self, shader: Option<Rc<ShaderProgram>>) {
+        self.shader = shader;
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<&MaterialValue> {
+        self.parameters.get(name)
+    }
+
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, &MaterialValue)> {
+        self.parameters.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn set_parameter(&mut self, name: &str, value: MaterialValue) {
+        self.parameters.insert(name.to_string(), value);
+    }
+
+    pub fn texture(&self, slot: TextureSlot) -> Option<&Rc<Texture>> {
+        self.textures.get(&slot)
+    }
+
+    pub fn textures(&self) -> impl Iterator<Item = (TextureSlot, &Rc<Texture>)> {
+        self.textures.iter().map(|(slot, texture)| (*slot, texture))
+    }
+
+    pub fn set_texture(&mut self, slot: TextureSlot, texture: Rc<Texture>) {
+        self.textures.insert(slot, texture);
+    }
+
+    pub fn remove_texture(&mut self, slot: TextureSlot) {
+        self.textures.remove(&slot);
+    }
+
+    /// Checks the material against the uniforms `shader` (or the material's own shader)
+    /// actually declares. Returns one message per parameter or texture the shader has no
+    /// matching uniform for; an empty list means everything will reach the GPU.
+    pub fn validate(&self, shader: &ShaderProgram) -> Vec<String> {
+        let shader = self.shader.as_deref().unwrap_or(shader);
+        let mut problems = Vec::new();
+
+        for (name, value) in &self.parameters {
+            match shader.uniform(name) {
+                None => problems.push(format!("parameter `{}` is not an active uniform", name)),
+                Some(info) if !value.accepts(info.gl_type) => problems.push(format!(
+                    "parameter `{}` is {:?} but the uniform is GL type 0x{:04X}",
+                    name, value, info.gl_type
+                )),
+                Some(_) => {}
+            }
+        }
+
+        for slot in self.textures.keys() {
+            match shader.uniform(slot.sampler_name()) {
+                Some(info) if is_sampler(info.gl_type) => {}
+                _ => problems.push(format!(
+                    "texture slot `{}` has no active sampler `{}`",
+                    slot,
+                    slot.sampler_name()
+                )),
+            }
+        }
+
+        problems
+    }
+
+    /// Makes the material current: uses its shader (or `shader` when it has none), uploads
+    /// the parameters and binds every texture slot to its unit.
+    ///
+    /// Only uniforms the program declares are written; see [`Material::validate`] for the
+    /// ones that were left out. Unset slots get texture `0` and their `has*Map` flag cleared.
+    pub fn bind(&self, shader: &ShaderProgram) {
+        let shader = self.shader.as_deref().unwrap_or(shader);
+        shader.use_program();
+
+        for (name, value) in DEFAULT_PARAMETERS {
+            if !self.parameters.contains_key(*name) && shader.uniform(name).is_some() {
+                value.set(shader, name);
+            }
+        }
+        for (name, value) in &self.parameters {
+            if shader.uniform(name).is_some() {
+                value.set(shader, name);
+            }
+        }
+
+        for slot in TextureSlot::ALL {
+            let texture = self.textures.get(&slot);
+            if shader.uniform(slot.flag_name()).is_some() {
+                shader.set_uniform(slot.flag_name(), &texture.is_some());
+            }
+            if shader.uniform(slot.sampler_name()).is_none() {
+                continue;
+            }
+
+            shader.set_uniform(slot.sampler_name(), &Sampler(slot.texture_unit() as i32));
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + slot.texture_unit());
+                gl::BindTexture(gl::TEXTURE_2D, texture.map(|texture| texture.texture_id).unwrap_or(0));
+            }
+        }
+
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+}
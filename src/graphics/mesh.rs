@@ -1,9 +1,12 @@
-use super::shader_program::ShaderProgram;
-use super::texture::Texture;
+use super::material::Material;
+use super::mesh_file::IndexWidth;
+use super::vertex_data::VertexData;
+use super::vertex_layout::VertexLayout;
 use gl;
-use memoffset::offset_of;
-use std::mem;
+use std::rc::Rc;
 
+/// A vertex with the attributes of [`VertexLayout::standard`]. Meshes with more attributes
+/// are built from [`VertexData`].
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct Vertex {
@@ -14,16 +17,38 @@ pub struct Vertex {
 
 #[derive(Debug)]
 pub struct Mesh {
-    vertices: Vec<Vertex>,
-    indices: Vec<u32>,
-    textures: Vec<Texture>,
+    layout: VertexLayout,
+    vertex_count: usize,
+    index_count: usize,
+    index_width: IndexWidth,
+    material: Option<Rc<Material>>,
     vertex_array: u32,
     vertex_buffer: u32,
     element_buffer: u32,
 }
 
 impl Mesh {
-    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, textures: Vec<Texture>) -> Self {
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, material: Option<Rc<Material>>) -> Self {
+        Self::from_data(&VertexData::from_vertices(&vertices), &indices, material)
+    }
+
+    /// Uploads every attribute `vertices` has, with 16-bit indices when they can address
+    /// every vertex.
+    pub fn from_data(vertices: &VertexData, indices: &[u32], material: Option<Rc<Material>>) -> Self {
+        let layout = vertices.layout();
+        let index_width = IndexWidth::for_vertex_count(vertices.len());
+        Self::from_bytes(&layout, &vertices.interleave(&layout), &index_width.encode(indices), index_width, material)
+    }
+
+    /// Uploads vertex data interleaved as `layout` and indices of `index_width`, e.g. straight
+    /// from a mapped mesh file.
+    pub fn from_bytes(
+        layout: &VertexLayout,
+        vertex_bytes: &[u8],
+        index_bytes: &[u8],
+        index_width: IndexWidth,
+        material: Option<Rc<Material>>,
+    ) -> Self {
         let mut vertex_array = 0;
         let mut vertex_buffer = 0;
         let mut element_buffer = 0;
@@ -35,114 +60,87 @@ impl Mesh {
         }
 
         let mesh = Self {
-            vertices,
-            indices,
-            textures,
+            layout: layout.clone(),
+            vertex_count: vertex_bytes.len() / layout.stride().max(1),
+            index_count: index_bytes.len() / index_width.size(),
+            index_width,
+            material,
             vertex_array,
             vertex_buffer,
             element_buffer,
         };
 
-        mesh.bind_Mesh();
+        mesh.bind_Mesh(vertex_bytes, index_bytes);
         mesh
     }
 
-    pub fn bind_Mesh(&self) {
+    /// Uploads the buffers and sets up the attribute pointers from the mesh's layout.
+    pub fn bind_Mesh(&self, vertex_bytes: &[u8], index_bytes: &[u8]) {
         unsafe {
             gl::BindVertexArray(self.vertex_array);
 
             gl::BindBuffer(gl::ARRAY_BUFFER, self.vertex_buffer);
             gl::BufferData(
                 gl::ARRAY_BUFFER,
-                (self.vertices.len() * mem::size_of::<Vertex>()) as isize,
-                self.vertices.as_ptr() as *const _,
+                vertex_bytes.len() as isize,
+                vertex_bytes.as_ptr() as *const _,
                 gl::STATIC_DRAW,
             );
 
             gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.element_buffer);
             gl::BufferData(
                 gl::ELEMENT_ARRAY_BUFFER,
-                (self.indices.len() * mem::size_of::<u32>()) as isize,
-                self.indices.as_ptr() as *const _,
+                index_bytes.len() as isize,
+                index_bytes.as_ptr() as *const _,
                 gl::STATIC_DRAW,
             );
 
-            gl::EnableVertexAttribArray(0);
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                mem::size_of::<Vertex>() as i32,
-                std::ptr::null(),
-            );
-
-            gl::EnableVertexAttribArray(1);
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                mem::size_of::<Vertex>() as i32,
-                offset_of!(Vertex, normal) as *const std::ffi::c_void,
-            );
-
-            gl::EnableVertexAttribArray(2);
-            gl::VertexAttribPointer(
-                2,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                mem::size_of::<Vertex>() as i32,
-                offset_of!(Vertex, texture_coords) as *const std::ffi::c_void,
-            );
-
+            self.layout.enable();
             gl::BindVertexArray(0);
         }
     }
 
-    pub fn draw(&self, shader_program: u32) {
-        unsafe {
-            let mut shader_diffuse_normal: u32 = 1;
-            let mut shader_specular_normal: u32 = 1;
-
-            println!(
-                "vertices: {}, indices: {}",
-                self.vertices.len(),
-                self.indices.len()
-            );
-
-            for (i, texture) in self.textures.iter().enumerate() {
-                gl::ActiveTexture(gl::TEXTURE0 + i as u32);
+    pub fn layout(&self) -> &VertexLayout {
+        &self.layout
+    }
 
-                let number = if texture.texture_type == "texture_diffuse" {
-                    let num = shader_diffuse_normal;
-                    shader_diffuse_normal += 1;
-                    num
-                } else if texture.texture_type == "texture_specular" {
-                    let num = shader_specular_normal;
-                    shader_specular_normal += 1;
-                    num
-                } else {
-                    1
-                };
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
 
-                let name: String = format!("material{}{}", texture.texture_type, number);
-                ShaderProgram::set_int(shader_program, &name, i as f32);
+    pub fn index_count(&self) -> usize {
+        self.index_count
+    }
 
-                gl::BindTexture(gl::TEXTURE_2D, texture.texture_id);
-            }
+    pub fn material(&self) -> Option<&Rc<Material>> {
+        self.material.as_ref()
+    }
 
-            gl::ActiveTexture(gl::TEXTURE0);
+    pub fn set_material(&mut self, material: Option<Rc<Material>>) {
+        self.material = material;
+    }
 
+    /// Issues the draw call. The material has to be bound beforehand.
+    pub fn draw(&self) {
+        unsafe {
             gl::BindVertexArray(self.vertex_array);
             gl::DrawElements(
                 gl::TRIANGLES,
-                self.indices.len() as i32,
-                gl::UNSIGNED_INT,
+                self.index_count as i32,
+                self.index_width.gl_type(),
                 std::ptr::null(),
             );
             gl::BindVertexArray(0);
         }
     }
 }
+
+impl Drop for Mesh {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vertex_array);
+            gl::DeleteBuffers(1, &self.vertex_buffer);
+            gl::DeleteBuffers(1, &self.element_buffer);
+        }
+    }
+}
@@ -0,0 +1,642 @@
+use super::material::{MaterialValue, TextureSlot};
+use super::vertex_data::VertexData;
+use super::vertex_layout::{VertexAttribute, VertexLayout};
+use crate::math::Vec3;
+
+/// First bytes of a `.uxmesh` file, the engine's own mesh format. The importer writes it so a
+/// model can be read back without parsing its source again.
+///
+/// Everything is little-endian. The file is laid out so the vertex and index data can be
+/// uploaded straight from a mapped file:
+///
+/// | Part         | Contents                                                              |
+/// |--------------|-----------------------------------------------------------------------|
+/// | header       | magic, version, source hash, counts, index width, bounds, data offsets |
+/// | vertex layout| stride and one descriptor per attribute                                |
+/// | dependencies | the files the source hash covers                                       |
+/// | submeshes    | vertex and index ranges, material index, bounds                        |
+/// | materials    | parameters and texture references                                      |
+/// | vertex data  | `vertex_count * stride` bytes, 16-byte aligned                         |
+/// | index data   | `index_count` indices of `index_width` bytes, relative to their submesh |
+pub const MAGIC: &[u8; 8] = b"UXMESH\0\0";
+/// Bumped whenever the layout changes; files of another version are imported again.
+pub const VERSION: u32 = 2;
+pub const EXTENSION: &str = "uxmesh";
+
+const NO_MATERIAL: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexWidth {
+    U16,
+    U32,
+}
+
+impl IndexWidth {
+    /// The narrowest width that can address `vertex_count` vertices.
+    pub fn for_vertex_count(vertex_count: usize) -> Self {
+        if vertex_count <= u16::MAX as usize + 1 {
+            IndexWidth::U16
+        } else {
+            IndexWidth::U32
+        }
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            IndexWidth::U16 => 2,
+            IndexWidth::U32 => 4,
+        }
+    }
+
+    pub fn gl_type(self) -> gl::types::GLenum {
+        match self {
+            IndexWidth::U16 => gl::UNSIGNED_SHORT,
+            IndexWidth::U32 => gl::UNSIGNED_INT,
+        }
+    }
+
+    /// `indices` as little-endian bytes of this width. Indices that don't fit are truncated.
+    pub fn encode(self, indices: &[u32]) -> Vec<u8> {
+        match self {
+            IndexWidth::U16 => indices.iter().flat_map(|index| (*index as u16).to_le_bytes()).collect(),
+            IndexWidth::U32 => indices.iter().flat_map(|index| index.to_le_bytes()).collect(),
+        }
+    }
+
+    /// Reads one little-endian index from the start of `bytes`.
+    pub fn decode(self, bytes: &[u8]) -> u32 {
+        match self {
+            IndexWidth::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as u32,
+            IndexWidth::U32 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Bounds {
+    /// The box around `points`, or a zero-sized box at the origin when there are none.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        let mut points = points.into_iter();
+        let Some(first) = points.next() else {
+            return Self { min: Vec3::ZERO, max: Vec3::ZERO };
+        };
+        points.fold(Self { min: first, max: first }, |bounds, point| Self {
+            min: bounds.min.min(point),
+            max: bounds.max.max(point),
+        })
+    }
+
+    pub fn union(self, other: Bounds) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+/// A range of the vertex and index data drawn with one material.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Submesh {
+    pub vertex_offset: u32,
+    pub vertex_count: u32,
+    pub index_offset: u32,
+    pub index_count: u32,
+    /// Index into the file's materials.
+    pub material: Option<u32>,
+    pub bounds: Bounds,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureSource {
+    /// An image file, as a path usable from the working directory.
+    File(String),
+    /// Encoded image bytes embedded in the source, e.g. in a `.glb`. `name` is only used in errors.
+    Embedded { name: String, data: Vec<u8> },
+}
+
+/// A texture a material refers to. Images are not converted, only referenced or copied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshTexture {
+    pub source: TextureSource,
+    /// Set for sources with the UV origin at the bottom, like OBJ.
+    pub flip_vertically: bool,
+    /// GL filter and wrap modes; `None` keeps the texture defaults.
+    pub mag_filter: Option<u32>,
+    pub min_filter: Option<u32>,
+    pub wrap_s: Option<u32>,
+    pub wrap_t: Option<u32>,
+}
+
+impl MeshTexture {
+    pub fn file(path: String, flip_vertically: bool) -> Self {
+        Self {
+            source: TextureSource::File(path),
+            flip_vertically,
+            mag_filter: None,
+            min_filter: None,
+            wrap_s: None,
+            wrap_t: None,
+        }
+    }
+}
+
+/// Everything needed to create a [`Material`](super::material::Material) for a submesh.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MeshMaterial {
+    pub name: String,
+    pub parameters: Vec<(String, MaterialValue)>,
+    pub textures: Vec<(TextureSlot, MeshTexture)>,
+}
+
+/// Imported geometry on the CPU: every submesh's vertices and indices in one pair of arrays.
+#[derive(Debug, Clone, Default)]
+pub struct MeshData {
+    pub vertices: VertexData,
+    /// Relative to the first vertex of their submesh.
+    pub indices: Vec<u32>,
+    pub submeshes: Vec<Submesh>,
+    pub materials: Vec<MeshMaterial>,
+}
+
+impl MeshData {
+    /// Appends a submesh; `indices` index into `vertices`.
+    pub fn push_submesh(&mut self, vertices: VertexData, indices: Vec<u32>, material: Option<u32>) {
+        self.submeshes.push(Submesh {
+            vertex_offset: self.vertices.len() as u32,
+            vertex_count: vertices.len() as u32,
+            index_offset: self.indices.len() as u32,
+            index_count: indices.len() as u32,
+            material,
+            bounds: Bounds::from_points(vertices.positions.iter().map(|position| Vec3::from(*position))),
+        });
+        self.vertices.append(&vertices);
+        self.indices.extend(indices);
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.submeshes
+            .iter()
+            .map(|submesh| submesh.bounds)
+            .reduce(Bounds::union)
+            .unwrap_or_else(|| Bounds::from_points([]))
+    }
+
+    /// 16-bit when every submesh is small enough, since indices are relative to their submesh.
+    pub fn index_width(&self) -> IndexWidth {
+        let largest = self.submeshes.iter().map(|submesh| submesh.vertex_count as usize).max().unwrap_or(0);
+        IndexWidth::for_vertex_count(largest)
+    }
+
+    /// Serializes the data. `dependencies` are the files `source_hash` was computed from.
+    pub fn encode(&self, source_hash: u64, dependencies: &[String]) -> Vec<u8> {
+        let layout = self.vertices.layout();
+        let index_width = self.index_width();
+        let bounds = self.bounds();
+
+        let mut writer = Writer::default();
+        writer.bytes(MAGIC);
+        writer.u32(VERSION);
+        writer.u32(0);
+        writer.u64(source_hash);
+        writer.u32(self.vertices.len() as u32);
+        writer.u32(self.indices.len() as u32);
+        writer.u32(index_width.size() as u32);
+        writer.u32(self.submeshes.len() as u32);
+        writer.u32(self.materials.len() as u32);
+        writer.u32(dependencies.len() as u32);
+        writer.bounds(bounds);
+        // Data offsets, filled in once they are known.
+        let offsets = writer.0.len();
+        writer.u32(0);
+        writer.u32(0);
+
+        writer.u32(layout.stride() as u32);
+        writer.u32(layout.attributes().len() as u32);
+        for layout_attribute in layout.attributes() {
+            let attribute = layout_attribute.attribute;
+            let (kind, set) = attribute.code();
+            writer.bytes(&[kind, set, attribute.component_type().size() as u8, attribute.components() as u8]);
+            writer.u32(layout_attribute.offset as u32);
+        }
+
+        for dependency in dependencies {
+            writer.string(dependency);
+        }
+
+        for submesh in &self.submeshes {
+            writer.u32(submesh.vertex_offset);
+            writer.u32(submesh.vertex_count);
+            writer.u32(submesh.index_offset);
+            writer.u32(submesh.index_count);
+            writer.u32(submesh.material.unwrap_or(NO_MATERIAL));
+            writer.bounds(submesh.bounds);
+        }
+
+        for material in &self.materials {
+            writer.material(material);
+        }
+
+        writer.align(16);
+        let vertex_offset = writer.0.len();
+        writer.bytes(&self.vertices.interleave(&layout));
+
+        writer.align(4);
+        let index_offset = writer.0.len();
+        writer.bytes(&index_width.encode(&self.indices));
+
+        writer.0[offsets..offsets + 4].copy_from_slice(&(vertex_offset as u32).to_le_bytes());
+        writer.0[offsets + 4..offsets + 8].copy_from_slice(&(index_offset as u32).to_le_bytes());
+        writer.0
+    }
+}
+
+/// A mesh file read in place: the header is decoded, the vertex and index data are slices of
+/// the file's bytes.
+#[derive(Debug, Clone)]
+pub struct MeshFile<'a> {
+    pub source_hash: u64,
+    pub dependencies: Vec<String>,
+    pub layout: VertexLayout,
+    pub index_width: IndexWidth,
+    pub vertex_count: u32,
+    pub index_count: u32,
+    pub bounds: Bounds,
+    pub submeshes: Vec<Submesh>,
+    pub materials: Vec<MeshMaterial>,
+    pub vertex_bytes: &'a [u8],
+    pub index_bytes: &'a [u8],
+}
+
+impl<'a> MeshFile<'a> {
+    /// Reads the header of `bytes` and checks that every range it describes is inside the file
+    /// and every index is inside its submesh.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err("not a mesh file".to_string());
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(format!("version {} is not supported, expected {}", version, VERSION));
+        }
+        reader.u32()?;
+
+        let source_hash = reader.u64()?;
+        let vertex_count = reader.u32()?;
+        let index_count = reader.u32()?;
+        let index_width = match reader.u32()? {
+            2 => IndexWidth::U16,
+            4 => IndexWidth::U32,
+            width => return Err(format!("invalid index width {}", width)),
+        };
+        let submesh_count = reader.u32()?;
+        let material_count = reader.u32()?;
+        let dependency_count = reader.u32()?;
+        let bounds = reader.bounds()?;
+        let vertex_offset = reader.u32()? as usize;
+        let index_offset = reader.u32()? as usize;
+
+        let stride = reader.u32()? as usize;
+        let attribute_count = reader.u32()?;
+        let mut attributes = Vec::new();
+        let mut offsets = Vec::new();
+        for _ in 0..attribute_count {
+            let [kind, set, component_size, components] = reader.array::<4>()?;
+            let attribute = VertexAttribute::from_code(kind, set)
+                .filter(|attribute| {
+                    attribute.component_type().size() == component_size as usize && attribute.components() == components as usize
+                })
+                .ok_or_else(|| format!("unknown vertex attribute {}/{}", kind, set))?;
+            attributes.push(attribute);
+            offsets.push(reader.u32()? as usize);
+        }
+        // Layouts are always packed in order, so anything else was written by something else.
+        let layout = VertexLayout::new(attributes);
+        let packed = layout.attributes().iter().map(|attribute| attribute.offset);
+        if layout.stride() != stride || !packed.eq(offsets) {
+            return Err("the vertex layout is not packed".to_string());
+        }
+
+        let dependencies = (0..dependency_count).map(|_| reader.string()).collect::<Result<_, _>>()?;
+
+        let mut submeshes = Vec::new();
+        for _ in 0..submesh_count {
+            let submesh = Submesh {
+                vertex_offset: reader.u32()?,
+                vertex_count: reader.u32()?,
+                index_offset: reader.u32()?,
+                index_count: reader.u32()?,
+                material: Some(reader.u32()?).filter(|material| *material != NO_MATERIAL),
+                bounds: reader.bounds()?,
+            };
+            if submesh.vertex_offset as u64 + submesh.vertex_count as u64 > vertex_count as u64
+                || submesh.index_offset as u64 + submesh.index_count as u64 > index_count as u64
+            {
+                return Err("a submesh is out of range".to_string());
+            }
+            if submesh.material.is_some_and(|material| material >= material_count) {
+                return Err("a submesh uses a missing material".to_string());
+            }
+            submeshes.push(submesh);
+        }
+
+        let materials = (0..material_count).map(|_| reader.material()).collect::<Result<_, _>>()?;
+
+        let vertex_length = vertex_count as usize * stride;
+        let index_length = index_count as usize * index_width.size();
+        let vertex_bytes = bytes
+            .get(vertex_offset..vertex_offset.saturating_add(vertex_length))
+            .ok_or("the vertex data is truncated")?;
+        let index_bytes = bytes
+            .get(index_offset..index_offset.saturating_add(index_length))
+            .ok_or("the index data is truncated")?;
+
+        let file = Self {
+            source_hash,
+            dependencies,
+            layout,
+            index_width,
+            vertex_count,
+            index_count,
+            bounds,
+            submeshes,
+            materials,
+            vertex_bytes,
+            index_bytes,
+        };
+        // Meshes are drawn straight from these bytes, so an index past its submesh's vertices
+        // would make GL read outside the vertex buffer.
+        for submesh in &file.submeshes {
+            let indices = file.submesh_index_bytes(submesh).chunks_exact(index_width.size());
+            if indices.map(|bytes| index_width.decode(bytes)).any(|index| index >= submesh.vertex_count) {
+                return Err("a submesh index is past its vertices".to_string());
+            }
+        }
+
+        Ok(file)
+    }
+
+    pub fn submesh_vertex_bytes(&self, submesh: &Submesh) -> &'a [u8] {
+        let stride = self.layout.stride();
+        let start = submesh.vertex_offset as usize * stride;
+        &self.vertex_bytes[start..start + submesh.vertex_count as usize * stride]
+    }
+
+    pub fn submesh_index_bytes(&self, submesh: &Submesh) -> &'a [u8] {
+        let size = self.index_width.size();
+        let start = submesh.index_offset as usize * size;
+        &self.index_bytes[start..start + submesh.index_count as usize * size]
+    }
+
+    pub fn vertices(&self) -> VertexData {
+        VertexData::from_interleaved(&self.layout, self.vertex_bytes)
+    }
+
+    pub fn indices(&self) -> Vec<u32> {
+        self.index_bytes
+            .chunks_exact(self.index_width.size())
+            .map(|bytes| self.index_width.decode(bytes))
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.blob(value.as_bytes());
+    }
+
+    fn blob(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.bytes(value);
+    }
+
+    fn align(&mut self, alignment: usize) {
+        self.0.resize(self.0.len().next_multiple_of(alignment), 0);
+    }
+
+    fn bounds(&mut self, bounds: Bounds) {
+        for value in bounds.min.to_array().into_iter().chain(bounds.max.to_array()) {
+            self.f32(value);
+        }
+    }
+
+    fn material(&mut self, material: &MeshMaterial) {
+        self.string(&material.name);
+
+        self.u32(material.parameters.len() as u32);
+        for (name, value) in &material.parameters {
+            self.string(name);
+            match value {
+                MaterialValue::Float(value) => {
+                    self.u8(0);
+                    self.f32(*value);
+                }
+                MaterialValue::Vec2(values) => {
+                    self.u8(1);
+                    values.iter().for_each(|value| self.f32(*value));
+                }
+                MaterialValue::Vec3(values) => {
+                    self.u8(2);
+                    values.iter().for_each(|value| self.f32(*value));
+                }
+                MaterialValue::Vec4(values) => {
+                    self.u8(3);
+                    values.iter().for_each(|value| self.f32(*value));
+                }
+                MaterialValue::Int(value) => {
+                    self.u8(4);
+                    self.bytes(&value.to_le_bytes());
+                }
+                MaterialValue::Bool(value) => {
+                    self.u8(5);
+                    self.u8(*value as u8);
+                }
+            }
+        }
+
+        self.u32(material.textures.len() as u32);
+        for (slot, texture) in &material.textures {
+            self.u8(*slot as u8);
+            match &texture.source {
+                TextureSource::File(path) => {
+                    self.u8(0);
+                    self.string(path);
+                }
+                TextureSource::Embedded { name, data } => {
+                    self.u8(1);
+                    self.string(name);
+                    self.blob(data);
+                }
+            }
+            self.u8(texture.flip_vertically as u8);
+            for value in [texture.mag_filter, texture.min_filter, texture.wrap_s, texture.wrap_t] {
+                // GL enums are never 0.
+                self.u32(value.unwrap_or(0));
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position.saturating_add(length))
+            .ok_or("the header is truncated")?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    fn f32s<const N: usize>(&mut self) -> Result<[f32; N], String> {
+        let mut values = [0.0; N];
+        for value in &mut values {
+            *value = self.f32()?;
+        }
+        Ok(values)
+    }
+
+    fn blob(&mut self) -> Result<&'a [u8], String> {
+        let length = self.u32()? as usize;
+        self.bytes(length)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        String::from_utf8(self.blob()?.to_vec()).map_err(|_| "a string is not valid UTF-8".to_string())
+    }
+
+    fn bounds(&mut self) -> Result<Bounds, String> {
+        let [min_x, min_y, min_z, max_x, max_y, max_z] = self.f32s()?;
+        Ok(Bounds { min: Vec3::new(min_x, min_y, min_z), max: Vec3::new(max_x, max_y, max_z) })
+    }
+
+    fn material(&mut self) -> Result<MeshMaterial, String> {
+        let name = self.string()?;
+
+        let parameter_count = self.u32()?;
+        let mut parameters = Vec::new();
+        for _ in 0..parameter_count {
+            let name = self.string()?;
+            let value = match self.u8()? {
+                0 => MaterialValue::Float(self.f32()?),
+                1 => MaterialValue::Vec2(self.f32s()?),
+                2 => MaterialValue::Vec3(self.f32s()?),
+                3 => MaterialValue::Vec4(self.f32s()?),
+                4 => MaterialValue::Int(i32::from_le_bytes(self.array()?)),
+                5 => MaterialValue::Bool(self.u8()? != 0),
+                kind => return Err(format!("unknown parameter type {}", kind)),
+            };
+            parameters.push((name, value));
+        }
+
+        let texture_count = self.u32()?;
+        let mut textures = Vec::new();
+        for _ in 0..texture_count {
+            let slot = self.u8()?;
+            let slot = *TextureSlot::ALL
+                .get(slot as usize)
+                .ok_or_else(|| format!("unknown texture slot {}", slot))?;
+            let source = match self.u8()? {
+                0 => TextureSource::File(self.string()?),
+                1 => TextureSource::Embedded { name: self.string()?, data: self.blob()?.to_vec() },
+                kind => return Err(format!("unknown texture source {}", kind)),
+            };
+            let flip_vertically = self.u8()? != 0;
+            let [mag_filter, min_filter, wrap_s, wrap_t] = [self.u32()?, self.u32()?, self.u32()?, self.u32()?]
+                .map(|value| Some(value).filter(|value| *value != 0));
+            textures.push((slot, MeshTexture { source, flip_vertically, mag_filter, min_filter, wrap_s, wrap_t }));
+        }
+
+        Ok(MeshMaterial { name, parameters, textures })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(indices: Vec<u32>) -> MeshData {
+        let mut data = MeshData::default();
+        let vertices = VertexData {
+            positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            ..VertexData::default()
+        };
+        data.push_submesh(vertices.clone(), vec![0, 1, 2], None);
+        data.push_submesh(vertices, indices, None);
+        data
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let data = triangle(vec![2, 1, 0]);
+        let dependencies = ["res/objects/triangle.obj".to_string()];
+        let bytes = data.encode(7, &dependencies);
+        let file = MeshFile::parse(&bytes).unwrap();
+
+        assert_eq!(file.source_hash, 7);
+        assert_eq!(file.dependencies, dependencies);
+        assert_eq!(file.submeshes, data.submeshes);
+        assert_eq!(file.vertices(), data.vertices);
+        assert_eq!(file.indices(), data.indices);
+    }
+
+    #[test]
+    fn rejects_indices_past_their_submesh() {
+        // 3 is inside the file's 6 vertices but past the second submesh's 3.
+        let bytes = triangle(vec![0, 1, 3]).encode(0, &[]);
+        assert_eq!(MeshFile::parse(&bytes).unwrap_err(), "a submesh index is past its vertices");
+    }
+}
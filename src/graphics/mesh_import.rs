@@ -0,0 +1,466 @@
+use super::gltf::{triangle_indices, AlphaMode, GltfDocument, GltfImageSource, GltfPrimitive, GltfTextureRef};
+use super::material::{MaterialValue, TextureSlot};
+use super::mesh_file::{MeshData, MeshFile, MeshMaterial, MeshTexture, TextureSource, EXTENSION};
+use super::mesh_processing::{deduplicate, generate_normals, generate_tangents, NormalMode};
+use super::mtl::MtlMaterial;
+use super::vertex_data::VertexData;
+use crate::math::{Mat3, Mat4, Vec3};
+use crate::utils::errors::ERRUranXError;
+use crate::utils::hash::{fnv1a, Fnv1a};
+use crate::utils::mapped_file::MappedFile;
+use std::cell::RefCell;
+use std::fs;
+use std::hash::Hasher;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Bumped when the importer's output changes, so cache files written by an older importer
+/// are imported again although their sources didn't change.
+pub const IMPORTER_VERSION: u32 = 4;
+
+/// How normals are generated for OBJ meshes that have none. Edges sharper than 60° stay hard.
+pub const OBJ_NORMAL_MODE: NormalMode = NormalMode::Smooth { crease_angle: std::f32::consts::FRAC_PI_3 };
+
+/// A model converted to [`MeshData`], with every file the conversion read.
+#[derive(Debug, Clone)]
+pub struct ImportedMesh {
+    pub data: MeshData,
+    /// The source file first, then MTL files or external glTF buffers. Textures are only
+    /// referenced, so they are not listed.
+    pub dependencies: Vec<String>,
+}
+
+/// Imports a Wavefront OBJ, or a glTF 2.0 file (`.gltf`, `.glb`) with its whole default scene
+/// merged into one mesh, node transforms applied to the vertices.
+pub fn import(path: &str) -> Result<ImportedMesh, ERRUranXError> {
+    if is_gltf(path) {
+        import_gltf(path)
+    } else {
+        import_obj(path)
+    }
+}
+
+pub fn is_gltf(path: &str) -> bool {
+    let extension = Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    matches!(extension.as_deref(), Some("gltf" | "glb"))
+}
+
+/// Polygons are split into triangle fans, points and lines are dropped. Positions, normals and
+/// UVs keep their own indices so [`obj_vertices`] can pair them up per corner.
+pub const OBJ_LOAD_OPTIONS: tobj::LoadOptions = tobj::LoadOptions {
+    single_index: false,
+    triangulate: true,
+    ignore_points: true,
+    ignore_lines: true,
+};
+
+/// Imports an OBJ file with one submesh per object. A missing or broken MTL file leaves the
+/// submeshes without materials rather than failing the import.
+pub fn import_obj(path: &str) -> Result<ImportedMesh, ERRUranXError> {
+    let directory = Path::new(path).parent().unwrap_or(Path::new("")).to_path_buf();
+    let dependencies = RefCell::new(vec![path.to_string()]);
+
+    let file = fs::File::open(path).map_err(|source| ERRUranXError::io(path, source))?;
+    let (models, materials) = tobj::load_obj_buf(&mut BufReader::new(file), &OBJ_LOAD_OPTIONS, |mtl_path| {
+        let mtl_path = directory.join(mtl_path);
+        dependencies.borrow_mut().push(mtl_path.to_string_lossy().to_string());
+        tobj::load_mtl(mtl_path)
+    })
+    .map_err(|source| ERRUranXError::OBJ_PARSE_ERROR { path: path.to_string(), source })?;
+
+    let mut data = MeshData::default();
+    match materials {
+        Ok(materials) => {
+            data.materials = materials
+                .iter()
+                .map(|material| obj_material(&MtlMaterial::from(material), &directory))
+                .collect();
+        }
+        Err(error) => eprintln!("[WARN] Model `{}` has no usable materials: {}", path, error),
+    }
+
+    for model in &models {
+        let (vertices, indices) = obj_vertices(&model.mesh);
+        let material = model.mesh.material_id.filter(|material| *material < data.materials.len());
+        let normal_mapped = material.is_some_and(|material| {
+            data.materials[material].textures.iter().any(|(slot, _)| *slot == TextureSlot::Normal)
+        });
+        let (vertices, indices) = complete_vertices(vertices, indices, OBJ_NORMAL_MODE, normal_mapped);
+        data.push_submesh(vertices, indices, material.map(|material| material as u32));
+    }
+
+    Ok(ImportedMesh { data, dependencies: dependencies.into_inner() })
+}
+
+/// Builds one vertex per distinct (position, normal, UV) corner of a triangulated OBJ mesh,
+/// loaded with [`OBJ_LOAD_OPTIONS`]. Normals and UVs are only present when the file has them.
+pub fn obj_vertices(mesh: &tobj::Mesh) -> (VertexData, Vec<u32>) {
+    let corners = mesh.indices.len();
+    let has_normals = mesh.normal_indices.len() == corners && !mesh.normals.is_empty();
+    let has_texture_coords = mesh.texcoord_indices.len() == corners && !mesh.texcoords.is_empty();
+
+    let mut vertices = VertexData::default();
+    for corner in 0..corners {
+        let position = mesh.indices[corner] as usize * 3;
+        vertices.positions.push([mesh.positions[position], mesh.positions[position + 1], mesh.positions[position + 2]]);
+
+        if has_normals {
+            let normal = mesh.normal_indices[corner] as usize * 3;
+            vertices.normals.push(
+                mesh.normals
+                    .get(normal..normal + 3)
+                    .map(|normal| [normal[0], normal[1], normal[2]])
+                    .unwrap_or([0.0, 0.0, 0.0]),
+            );
+        }
+    }
+    if has_texture_coords {
+        vertices.texture_coords = vec![mesh
+            .texcoord_indices
+            .iter()
+            .map(|uv| {
+                let uv = *uv as usize * 2;
+                mesh.texcoords.get(uv..uv + 2).map(|uv| [uv[0], uv[1]]).unwrap_or([0.0, 0.0])
+            })
+            .collect()];
+    }
+
+    let corner_indices: Vec<u32> = (0..corners as u32).collect();
+    deduplicate(&vertices, &corner_indices)
+}
+
+fn obj_material(material: &MtlMaterial, directory: &Path) -> MeshMaterial {
+    MeshMaterial {
+        name: material.name.clone(),
+        parameters: material
+            .parameters()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+        textures: material
+            .textures()
+            .into_iter()
+            .map(|(slot, name)| (slot, MeshTexture::file(directory.join(name).to_string_lossy().to_string(), true)))
+            .collect(),
+    }
+}
+
+/// Imports a glTF file with one submesh per triangle primitive.
+pub fn import_gltf(path: &str) -> Result<ImportedMesh, ERRUranXError> {
+    let mut dependencies = Vec::new();
+    let document = GltfDocument::load_with(path, |path| {
+        dependencies.push(path.to_string_lossy().to_string());
+        fs::read(path)
+    })?;
+
+    let mut data = MeshData {
+        materials: gltf_materials(&document),
+        ..MeshData::default()
+    };
+    document.walk(|_, node, world_matrix| {
+        let Some(mesh) = node.mesh.map(|mesh| &document.meshes[mesh]) else {
+            return;
+        };
+        for primitive in &mesh.primitives {
+            if let Some((vertices, indices)) = gltf_primitive(&document, primitive, world_matrix) {
+                data.push_submesh(vertices, indices, primitive.material.map(|material| material as u32));
+            }
+        }
+    });
+
+    Ok(ImportedMesh { data, dependencies })
+}
+
+/// Every material of `document`, in order.
+pub fn gltf_materials(document: &GltfDocument) -> Vec<MeshMaterial> {
+    let texture = |reference: GltfTextureRef| -> Option<MeshTexture> {
+        let gltf_texture = &document.textures[reference.texture];
+        let image_index = gltf_texture.image?;
+        let source = match &document.images[image_index].source {
+            GltfImageSource::File(path) => TextureSource::File(path.clone()),
+            GltfImageSource::Embedded { data, .. } => TextureSource::Embedded {
+                name: format!("{} (image {})", document.path, image_index),
+                data: data.clone(),
+            },
+        };
+        Some(MeshTexture {
+            source,
+            flip_vertically: false,
+            mag_filter: gltf_texture.mag_filter,
+            min_filter: gltf_texture.min_filter,
+            wrap_s: gltf_texture.wrap_s,
+            wrap_t: gltf_texture.wrap_t,
+        })
+    };
+
+    document
+        .materials
+        .iter()
+        .map(|gltf_material| {
+            let [r, g, b] = gltf_material.emissive_factor;
+            let mut parameters = vec![
+                ("albedoColor", MaterialValue::Vec4(gltf_material.base_color_factor)),
+                ("metallic", MaterialValue::Float(gltf_material.metallic_factor)),
+                ("roughness", MaterialValue::Float(gltf_material.roughness_factor)),
+                ("emissiveColor", MaterialValue::Vec3([r, g, b])),
+                ("normalScale", MaterialValue::Float(gltf_material.normal_scale)),
+                ("occlusionStrength", MaterialValue::Float(gltf_material.occlusion_strength)),
+            ];
+            if gltf_material.alpha_mode == AlphaMode::Mask {
+                parameters.push(("alphaCutoff", MaterialValue::Float(gltf_material.alpha_cutoff)));
+            }
+
+            let textures = [
+                (gltf_material.base_color_texture, TextureSlot::Albedo),
+                (gltf_material.metallic_roughness_texture, TextureSlot::MetallicRoughness),
+                (gltf_material.normal_texture, TextureSlot::Normal),
+                (gltf_material.occlusion_texture, TextureSlot::Occlusion),
+                (gltf_material.emissive_texture, TextureSlot::Emissive),
+            ]
+            .into_iter()
+            .filter_map(|(reference, slot)| Some((slot, texture(reference?)?)))
+            .collect();
+
+            MeshMaterial {
+                name: gltf_material.name.clone(),
+                parameters: parameters.into_iter().map(|(name, value)| (name.to_string(), value)).collect(),
+                textures,
+            }
+        })
+        .collect()
+}
+
+/// The vertices and triangle indices of a primitive with `transform` applied. Point and line
+/// primitives are skipped with a warning.
+pub fn gltf_primitive(document: &GltfDocument, primitive: &GltfPrimitive, transform: Mat4) -> Option<(VertexData, Vec<u32>)> {
+    let Some(indices) = triangle_indices(primitive.mode, &primitive.indices) else {
+        eprintln!("[WARN] Model `{}` has a primitive of mode {}, only triangles are drawn", document.path, primitive.mode);
+        return None;
+    };
+
+    let normal_matrix = Mat3::from_mat4(transform).inverse().map(Mat3::transpose).unwrap_or(Mat3::IDENTITY);
+    let tangent_matrix = Mat3::from_mat4(transform);
+    let vertices = VertexData {
+        positions: primitive
+            .positions
+            .iter()
+            .map(|position| transform.transform_point3(Vec3::from(*position)).to_array())
+            .collect(),
+        normals: primitive
+            .normals
+            .iter()
+            .map(|normal| (normal_matrix * Vec3::from(*normal)).normalize().to_array())
+            .collect(),
+        tangents: primitive
+            .tangents
+            .iter()
+            .map(|[x, y, z, w]| {
+                let [x, y, z] = (tangent_matrix * Vec3::new(*x, *y, *z)).normalize().to_array();
+                [x, y, z, *w]
+            })
+            .collect(),
+        bitangents: Vec::new(),
+        colors: primitive.colors.clone(),
+        texture_coords: primitive.texture_coords.clone(),
+        joints: primitive.joints.clone(),
+        weights: primitive.weights.clone(),
+    };
+
+    // The glTF spec asks for flat normals when a primitive has none.
+    let normal_mapped = primitive
+        .material
+        .and_then(|material| document.materials.get(material))
+        .is_some_and(|material| material.normal_texture.is_some());
+    Some(complete_vertices(vertices, indices, NormalMode::Flat, normal_mapped))
+}
+
+/// Generates normals when `vertices` has none, and tangents when a normal-mapped mesh has
+/// none. Meshes without UVs get no tangents.
+fn complete_vertices(vertices: VertexData, indices: Vec<u32>, normal_mode: NormalMode, normal_mapped: bool) -> (VertexData, Vec<u32>) {
+    let (vertices, indices) = if vertices.normals.is_empty() {
+        generate_normals(&vertices, &indices, normal_mode)
+    } else {
+        (vertices, indices)
+    };
+
+    if normal_mapped && vertices.tangents.is_empty() && !vertices.texture_coords(0).is_empty() {
+        generate_tangents(&vertices, &indices)
+    } else {
+        (vertices, indices)
+    }
+}
+
+/// Hash of [`IMPORTER_VERSION`] and the paths and contents of `files`. A file that can't be read counts as absent,
+/// so creating it later, e.g. a missing MTL file, also changes the hash.
+pub fn source_hash(files: &[String]) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write_u32(IMPORTER_VERSION);
+    for file in files {
+        hasher.write(file.as_bytes());
+        match fs::read(file) {
+            Ok(contents) => {
+                hasher.write_u8(1);
+                hasher.write_u64(contents.len() as u64);
+                hasher.write(&contents);
+            }
+            Err(_) => hasher.write_u8(0),
+        }
+    }
+    hasher.finish()
+}
+
+/// Imported models kept as mesh files in a directory, so a model is only parsed again after
+/// its source changes.
+///
+/// Each source path has one cache file. It records the files the import read and a hash of
+/// their contents, and is only used while that hash still matches.
+#[derive(Debug, Clone)]
+pub struct MeshCache {
+    directory: PathBuf,
+}
+
+impl MeshCache {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self { directory: directory.into() }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Where the cache file of `source` goes, e.g. `backpack-1f0c….uxmesh`.
+    pub fn file_path(&self, source: &str) -> PathBuf {
+        let stem = Path::new(source)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.directory
+            .join(format!("{}-{:016x}.{}", stem, fnv1a(source.as_bytes()), EXTENSION))
+    }
+
+    /// Maps the cache file of `source` and passes it, parsed, to `f`. `None` when there is
+    /// no file or it is out of date; a file that can't be parsed is reported and ignored.
+    pub fn load<T>(&self, source: &str, f: impl FnOnce(&MeshFile) -> T) -> Option<T> {
+        let path = self.file_path(source);
+        let file = MappedFile::open(&path).ok()?;
+
+        let mesh_file = match MeshFile::parse(&file) {
+            Ok(mesh_file) => mesh_file,
+            Err(message) => {
+                eprintln!("[WARN] Ignoring the mesh cache `{}`: {}", path.display(), message);
+                return None;
+            }
+        };
+        let is_current = mesh_file.dependencies.first().map(String::as_str) == Some(source)
+            && mesh_file.source_hash == source_hash(&mesh_file.dependencies);
+
+        is_current.then(|| f(&mesh_file))
+    }
+
+    /// Writes the cache file of `source`. The file is written next to its final name and
+    /// renamed, so a crash never leaves a half-written cache behind.
+    pub fn store(&self, source: &str, imported: &ImportedMesh) -> Result<(), ERRUranXError> {
+        let directory = self.directory.to_string_lossy();
+        fs::create_dir_all(&self.directory).map_err(|error| ERRUranXError::io(&directory, error))?;
+
+        let bytes = imported.data.encode(source_hash(&imported.dependencies), &imported.dependencies);
+        let path = self.file_path(source);
+        let temporary = path.with_extension("tmp");
+        let path_name = path.to_string_lossy();
+        fs::write(&temporary, bytes).map_err(|error| ERRUranXError::io(&path_name, error))?;
+        fs::rename(&temporary, &path).map_err(|error| ERRUranXError::io(&path_name, error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::mesh_file::IndexWidth;
+
+    /// The vertices of the single object in the OBJ file at `path`.
+    fn load(path: &str) -> (VertexData, Vec<u32>) {
+        let (models, _) = tobj::load_obj(path, &OBJ_LOAD_OPTIONS).unwrap();
+        assert_eq!(models.len(), 1);
+        obj_vertices(&models[0].mesh)
+    }
+
+    #[test]
+    fn cube_with_split_uvs_has_a_vertex_per_face_corner() {
+        let (vertices, indices) = load("res/objects/cube.obj");
+
+        assert_eq!(vertices.len(), 24);
+        assert_eq!(vertices.normals.len(), 24);
+        assert_eq!(vertices.texture_coords[0].len(), 24);
+        assert_eq!(indices.len(), 36);
+        assert!(indices.iter().all(|index| (*index as usize) < vertices.len()));
+
+        // Each face's two triangles share a corner normal, the face's own.
+        for triangle in indices.chunks_exact(3) {
+            let normals: Vec<_> = triangle.iter().map(|index| vertices.normals[*index as usize]).collect();
+            assert!(normals.iter().all(|normal| *normal == normals[0]));
+        }
+    }
+
+    #[test]
+    fn quad_shares_its_diagonal() {
+        let (vertices, indices) = load("res/objects/quad.obj");
+
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices, [0, 1, 2, 0, 2, 3]);
+        assert_eq!(vertices.positions, [[-1.0, -1.0, 0.0], [1.0, -1.0, 0.0], [1.0, 1.0, 0.0], [-1.0, 1.0, 0.0]]);
+        assert_eq!(vertices.texture_coords, [vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]]);
+        assert_eq!(vertices.normals, [[0.0, 0.0, 1.0]; 4]);
+    }
+
+    #[test]
+    fn polygons_are_triangulated_as_fans() {
+        let (vertices, indices) = load("res/objects/hexagon.obj");
+
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(indices, [0, 1, 2, 0, 2, 3, 0, 3, 4, 0, 4, 5]);
+        assert!(vertices.normals.is_empty());
+        assert!(vertices.texture_coords.is_empty());
+    }
+
+    #[test]
+    fn import_generates_missing_normals() {
+        let imported = import_obj("res/objects/hexagon.obj").unwrap();
+
+        assert_eq!(imported.dependencies, ["res/objects/hexagon.obj"]);
+        assert_eq!(imported.data.submeshes.len(), 1);
+        assert_eq!(imported.data.indices.len(), 12);
+        assert!(imported.data.vertices.normals.iter().all(|normal| *normal == [0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn deduplicate_merges_identical_corners() {
+        let vertices = VertexData {
+            positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [-0.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            texture_coords: vec![vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [0.0, 0.0], [0.5, 1.0]]],
+            ..VertexData::default()
+        };
+        let (unique, indices) = deduplicate(&vertices, &[0, 1, 2, 3, 2, 4]);
+
+        // `-0.0` equals `0.0`; a corner with another UV stays apart.
+        assert_eq!(indices, [0, 1, 2, 0, 2, 3]);
+        assert_eq!(unique.positions, [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 1.0, 0.0]]);
+        assert_eq!(unique.texture_coords[0][3], [0.5, 1.0]);
+    }
+
+    #[test]
+    fn index_width_follows_the_largest_submesh() {
+        let submesh = |vertex_count: usize| {
+            let mut data = MeshData::default();
+            let vertices = VertexData { positions: vec![[0.0; 3]; vertex_count], ..VertexData::default() };
+            data.push_submesh(vertices, vec![0, 1, 2], None);
+            data
+        };
+
+        assert_eq!(submesh(3).index_width(), IndexWidth::U16);
+        // 16-bit indices reach vertex 65535, so 65536 vertices still fit.
+        assert_eq!(submesh(65536).index_width(), IndexWidth::U16);
+        assert_eq!(submesh(65537).index_width(), IndexWidth::U32);
+    }
+}
@@ -0,0 +1,359 @@
+use super::vertex_data::VertexData;
+use crate::math::Vec3;
+use std::collections::HashMap;
+
+/// Merges vertices whose attributes are all bit-for-bit equal and returns the remaining
+/// vertices with `indices` remapped to them. Vertices keep their first-use order; ones no
+/// index refers to are dropped.
+///
+/// `-0.0` and `0.0` count as equal, so mirrored exporters don't leave seams of duplicates.
+pub fn deduplicate(vertices: &VertexData, indices: &[u32]) -> (VertexData, Vec<u32>) {
+    let mut unique = Vec::new();
+    let mut lookup: HashMap<Vec<u32>, u32> = HashMap::with_capacity(vertices.len());
+    let mut key = Vec::new();
+
+    let remapped = indices
+        .iter()
+        .map(|index| {
+            vertex_key(vertices, *index as usize, &mut key);
+            if let Some(unique_index) = lookup.get(&key) {
+                return *unique_index;
+            }
+            unique.push(*index);
+            let unique_index = (unique.len() - 1) as u32;
+            lookup.insert(key.clone(), unique_index);
+            unique_index
+        })
+        .collect();
+
+    (vertices.gather(&unique), remapped)
+}
+
+/// The bits of every attribute of vertex `index`, in a fixed order.
+fn vertex_key(vertices: &VertexData, index: usize, key: &mut Vec<u32>) {
+    // Adding zero turns `-0.0` into `0.0` and leaves everything else alone.
+    fn push(key: &mut Vec<u32>, values: Option<&[f32]>) {
+        key.extend(values.unwrap_or(&[]).iter().map(|value| (value + 0.0).to_bits()));
+    }
+
+    key.clear();
+    push(key, vertices.positions.get(index).map(|value| &value[..]));
+    push(key, vertices.normals.get(index).map(|value| &value[..]));
+    push(key, vertices.tangents.get(index).map(|value| &value[..]));
+    push(key, vertices.bitangents.get(index).map(|value| &value[..]));
+    push(key, vertices.colors.get(index).map(|value| &value[..]));
+    for set in &vertices.texture_coords {
+        push(key, set.get(index).map(|value| &value[..]));
+    }
+    if let Some(joints) = vertices.joints.get(index) {
+        key.extend(joints.iter().map(|joint| *joint as u32));
+    }
+    push(key, vertices.weights.get(index).map(|value| &value[..]));
+}
+
+/// How [`generate_normals`] shades a mesh.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalMode {
+    /// Every triangle gets its own face normal.
+    Flat,
+    /// Normals are averaged over the triangles around a position, each weighted by its angle
+    /// there. Triangles meeting at more than `crease_angle` radians keep a hard edge.
+    Smooth { crease_angle: f32 },
+}
+
+/// Replaces the normals of an indexed triangle list. Vertices are split where flat shading
+/// or a crease needs several normals at one vertex, and merged again where they agree.
+///
+/// Smoothing goes by position rather than by index, so UV seams don't show in the shading.
+pub fn generate_normals(vertices: &VertexData, indices: &[u32], mode: NormalMode) -> (VertexData, Vec<u32>) {
+    let triangles = triangles(indices);
+    let position = |index: u32| Vec3::from(vertices.positions[index as usize]);
+    let face_normals: Vec<Vec3> = triangles
+        .iter()
+        .map(|[a, b, c]| (position(*b) - position(*a)).cross(position(*c) - position(*a)).normalize())
+        .collect();
+
+    let mut corner_normals = Vec::with_capacity(triangles.len() * 3);
+    match mode {
+        NormalMode::Flat => {
+            for normal in &face_normals {
+                corner_normals.extend([*normal; 3]);
+            }
+        }
+        NormalMode::Smooth { crease_angle } => {
+            let min_cosine = crease_angle.cos() - 1e-6;
+            let mut around_position: HashMap<[u32; 3], Vec<(usize, f32)>> = HashMap::new();
+            for (face, triangle) in triangles.iter().enumerate() {
+                for corner in 0..3 {
+                    let weight = corner_angle(vertices, triangle, corner);
+                    around_position.entry(position_key(vertices, triangle[corner])).or_default().push((face, weight));
+                }
+            }
+
+            for (face, triangle) in triangles.iter().enumerate() {
+                for index in triangle {
+                    let normal = around_position[&position_key(vertices, *index)]
+                        .iter()
+                        .filter(|(other, _)| face_normals[*other].dot(face_normals[face]) >= min_cosine)
+                        .fold(Vec3::ZERO, |sum, (other, weight)| sum + face_normals[*other] * *weight);
+                    corner_normals.push(normal.normalize());
+                }
+            }
+        }
+    }
+
+    // One vertex per corner with its new normal, then merge the ones that came out equal.
+    let corner_indices: Vec<u32> = triangles.iter().flatten().copied().collect();
+    let mut corners = vertices.gather(&corner_indices);
+    corners.normals = corner_normals
+        .into_iter()
+        .map(|normal| if normal == Vec3::ZERO { Vec3::Y } else { normal }.to_array())
+        .collect();
+
+    let corner_indices: Vec<u32> = (0..corners.len() as u32).collect();
+    deduplicate(&corners, &corner_indices)
+}
+
+/// Adds tangents for UV set 0, replacing any there are.
+///
+/// Follows the MikkTSpace conventions, so normal maps baked by common tools come out right:
+/// per-triangle UV directions are weighted by corner angle and made orthogonal to the vertex
+/// normal, `w` is the handedness with `bitangent = w * cross(normal, tangent)`, and vertices
+/// are split where mirrored UVs meet. It is not a bit-exact port of the reference code.
+///
+/// Needs normals and UVs; without them the mesh is returned unchanged.
+pub fn generate_tangents(vertices: &VertexData, indices: &[u32]) -> (VertexData, Vec<u32>) {
+    let texture_coords = vertices.texture_coords(0);
+    if vertices.normals.len() != vertices.len() || texture_coords.len() != vertices.len() {
+        return (vertices.clone(), indices.to_vec());
+    }
+
+    // Output vertices are (input vertex, UV orientation) pairs.
+    let mut outputs: HashMap<(u32, bool), usize> = HashMap::new();
+    let mut sources = Vec::new();
+    let mut sums: Vec<(Vec3, Vec3)> = Vec::new();
+    let mut new_indices = Vec::with_capacity(indices.len());
+
+    for triangle in triangles(indices) {
+        let [p0, p1, p2] = triangle.map(|index| Vec3::from(vertices.positions[index as usize]));
+        let [uv0, uv1, uv2] = triangle.map(|index| texture_coords[index as usize]);
+        let (edge1, edge2) = (p1 - p0, p2 - p0);
+        let (du1, dv1, du2, dv2) = (uv1[0] - uv0[0], uv1[1] - uv0[1], uv2[0] - uv0[0], uv2[1] - uv0[1]);
+        let area = du1 * dv2 - du2 * dv1;
+
+        // Degenerate UVs give no direction; the corners still get a vertex.
+        let orientation = area >= 0.0;
+        let sign = if orientation { 1.0 } else { -1.0 };
+        let (tangent, bitangent) = if area.abs() > f32::EPSILON {
+            ((edge1 * dv2 - edge2 * dv1) * sign, (edge2 * du1 - edge1 * du2) * sign)
+        } else {
+            (Vec3::ZERO, Vec3::ZERO)
+        };
+
+        for (corner, index) in triangle.iter().enumerate() {
+            let output = *outputs.entry((*index, orientation)).or_insert_with(|| {
+                sources.push(*index);
+                sums.push((Vec3::ZERO, Vec3::ZERO));
+                sums.len() - 1
+            });
+            new_indices.push(output as u32);
+
+            let normal = Vec3::from(vertices.normals[*index as usize]);
+            let weight = corner_angle(vertices, &triangle, corner);
+            sums[output].0 += (tangent - normal * normal.dot(tangent)).normalize() * weight;
+            sums[output].1 += (bitangent - normal * normal.dot(bitangent)).normalize() * weight;
+        }
+    }
+
+    let mut result = vertices.gather(&sources);
+    result.tangents = sums
+        .iter()
+        .zip(&result.normals)
+        .map(|((tangent, bitangent), normal)| {
+            let normal = Vec3::from(*normal);
+            let tangent = match tangent.normalize() {
+                Vec3::ZERO => perpendicular(normal),
+                tangent => tangent,
+            };
+            let handedness = if normal.cross(tangent).dot(*bitangent) < 0.0 { -1.0 } else { 1.0 };
+            [tangent.x, tangent.y, tangent.z, handedness]
+        })
+        .collect();
+
+    (result, new_indices)
+}
+
+fn triangles(indices: &[u32]) -> Vec<[u32; 3]> {
+    indices.chunks_exact(3).map(|triangle| [triangle[0], triangle[1], triangle[2]]).collect()
+}
+
+fn position_key(vertices: &VertexData, index: u32) -> [u32; 3] {
+    vertices.positions[index as usize].map(|value| (value + 0.0).to_bits())
+}
+
+/// The angle of `triangle` at its `corner`, in radians.
+fn corner_angle(vertices: &VertexData, triangle: &[u32; 3], corner: usize) -> f32 {
+    let position = |index: u32| Vec3::from(vertices.positions[index as usize]);
+    let origin = position(triangle[corner]);
+    let next = (position(triangle[(corner + 1) % 3]) - origin).normalize();
+    let previous = (position(triangle[(corner + 2) % 3]) - origin).normalize();
+    next.dot(previous).clamp(-1.0, 1.0).acos()
+}
+
+/// Some unit vector perpendicular to `normal`.
+fn perpendicular(normal: Vec3) -> Vec3 {
+    let axis = if normal.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
+    (axis - normal * normal.dot(axis)).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::primitives;
+    use std::f32::consts::{FRAC_PI_3, PI};
+
+    /// The positions of a primitive alone, with corners at the same place merged.
+    fn positions_only((vertices, indices): (VertexData, Vec<u32>)) -> (VertexData, Vec<u32>) {
+        let positions = VertexData { positions: vertices.positions, ..VertexData::default() };
+        deduplicate(&positions, &indices)
+    }
+
+    /// Every triangle of the mesh with the normal its winding gives.
+    fn winding_normals(vertices: &VertexData, indices: &[u32]) -> Vec<([u32; 3], Vec3)> {
+        triangles(indices)
+            .into_iter()
+            .map(|triangle| {
+                let [a, b, c] = triangle.map(|index| Vec3::from(vertices.positions[index as usize]));
+                (triangle, (b - a).cross(c - a).normalize())
+            })
+            .collect()
+    }
+
+    fn assert_tangent_frames(vertices: &VertexData) {
+        assert_eq!(vertices.tangents.len(), vertices.len());
+        for (tangent, normal) in vertices.tangents.iter().zip(&vertices.normals) {
+            let direction = Vec3::new(tangent[0], tangent[1], tangent[2]);
+            assert!((direction.length() - 1.0).abs() < 1e-4, "{tangent:?} is not unit length");
+            assert!(direction.dot(Vec3::from(*normal)).abs() < 1e-4, "{tangent:?} is not orthogonal to {normal:?}");
+            assert!(tangent[3] == 1.0 || tangent[3] == -1.0, "{tangent:?} has no handedness");
+        }
+    }
+
+    #[test]
+    fn flat_normals_split_a_cube_per_face() {
+        let (vertices, indices) = positions_only(primitives::cube(2.0));
+        assert_eq!(vertices.len(), 8);
+
+        let (vertices, indices) = generate_normals(&vertices, &indices, NormalMode::Flat);
+        assert_eq!(vertices.len(), 24);
+        assert_eq!(indices.len(), 36);
+        for (triangle, face_normal) in winding_normals(&vertices, &indices) {
+            for index in triangle {
+                assert_eq!(Vec3::from(vertices.normals[index as usize]), face_normal);
+            }
+            // Faces point away from the centre.
+            assert!(face_normal.dot(Vec3::from(vertices.positions[triangle[0] as usize])) > 0.0);
+        }
+    }
+
+    #[test]
+    fn smooth_normals_follow_a_sphere() {
+        let (vertices, indices) = positions_only(primitives::icosphere(1.0, 2));
+        let (smooth, smooth_indices) = generate_normals(&vertices, &indices, NormalMode::Smooth { crease_angle: FRAC_PI_3 });
+
+        // One normal per position, so nothing is split.
+        assert_eq!(smooth.len(), vertices.len());
+        assert_eq!(smooth_indices.len(), indices.len());
+        for (position, normal) in smooth.positions.iter().zip(&smooth.normals) {
+            let normal = Vec3::from(*normal);
+            assert!((normal.length() - 1.0).abs() < 1e-4);
+            assert!(normal.dot(Vec3::from(*position).normalize()) > 0.99, "{normal:?} at {position:?}");
+        }
+    }
+
+    #[test]
+    fn crease_angle_keeps_cube_edges_hard() {
+        let (vertices, indices) = positions_only(primitives::cube(2.0));
+
+        let (creased, _) = generate_normals(&vertices, &indices, NormalMode::Smooth { crease_angle: FRAC_PI_3 });
+        let (flat, _) = generate_normals(&vertices, &indices, NormalMode::Flat);
+        assert_eq!(creased.len(), 24);
+        let mut creased_normals = creased.normals.clone();
+        let mut flat_normals = flat.normals.clone();
+        creased_normals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        flat_normals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(creased_normals, flat_normals);
+
+        // Past 90° every face at a corner is averaged in.
+        let (rounded, _) = generate_normals(&vertices, &indices, NormalMode::Smooth { crease_angle: PI });
+        assert_eq!(rounded.len(), 8);
+        for (position, normal) in rounded.positions.iter().zip(&rounded.normals) {
+            let expected = Vec3::from(*position).normalize();
+            assert!(Vec3::from(*normal).abs_diff_eq(expected, 1e-5), "{normal:?} at {position:?}");
+        }
+    }
+
+    #[test]
+    fn tangents_are_unit_and_orthogonal() {
+        for (name, (vertices, indices)) in [
+            ("cube", primitives::cube(2.0)),
+            ("sphere", primitives::uv_sphere(1.0, 16, 8)),
+            ("torus", primitives::torus(1.0, 0.25, 16, 8)),
+        ] {
+            let vertices = VertexData { tangents: Vec::new(), ..vertices };
+            let (vertices, tangent_indices) = generate_tangents(&vertices, &indices);
+            assert_eq!(tangent_indices.len(), indices.len(), "{name}");
+            assert_tangent_frames(&vertices);
+        }
+    }
+
+    #[test]
+    fn tangents_follow_u() {
+        let vertices = VertexData {
+            positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+            normals: vec![[0.0, 0.0, 1.0]; 4],
+            texture_coords: vec![vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]],
+            ..VertexData::default()
+        };
+        let (vertices, indices) = generate_tangents(&vertices, &[0, 1, 2, 0, 2, 3]);
+
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices, [0, 1, 2, 0, 2, 3]);
+        assert_eq!(vertices.tangents, [[1.0, 0.0, 0.0, 1.0]; 4]);
+    }
+
+    #[test]
+    fn mirrored_uvs_split_the_seam() {
+        // A strip whose `u` runs from the middle out to both sides, as on a mirrored texture.
+        let vertices = VertexData {
+            positions: vec![
+                [-1.0, 0.0, 0.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [-1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [1.0, 1.0, 0.0],
+            ],
+            normals: vec![[0.0, 0.0, 1.0]; 6],
+            texture_coords: vec![vec![[1.0, 0.0], [0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0], [1.0, 1.0]]],
+            ..VertexData::default()
+        };
+        let (vertices, indices) = generate_tangents(&vertices, &[0, 1, 4, 0, 4, 3, 1, 2, 5, 1, 5, 4]);
+
+        // The two vertices on the seam get one copy per side.
+        assert_eq!(vertices.len(), 8);
+        assert_tangent_frames(&vertices);
+        for (corner, index) in indices.iter().enumerate() {
+            let expected = if corner < 6 { [-1.0, 0.0, 0.0, -1.0] } else { [1.0, 0.0, 0.0, 1.0] };
+            assert_eq!(vertices.tangents[*index as usize], expected, "corner {corner}");
+        }
+    }
+
+    #[test]
+    fn tangents_need_normals_and_uvs() {
+        let (vertices, indices) = positions_only(primitives::cube(2.0));
+        let (unchanged, unchanged_indices) = generate_tangents(&vertices, &indices);
+        assert_eq!(unchanged, vertices);
+        assert_eq!(unchanged_indices, indices);
+    }
+}
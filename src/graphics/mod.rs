@@ -1,5 +1,18 @@
 pub mod camera;
+pub mod gltf;
+pub mod material;
 pub mod mesh;
+pub mod mesh_file;
+pub mod mesh_import;
+pub mod mesh_processing;
 pub mod model;
+pub mod mtl;
+pub mod primitives;
+pub mod shader_preprocessor;
 pub mod shader_program;
+pub mod std140;
 pub mod texture;
+pub mod uniform;
+pub mod uniform_buffer;
+pub mod vertex_data;
+pub mod vertex_layout;
@@ -1,111 +1,188 @@
+use super::gltf::GltfDocument;
+use super::material::Material;
 use super::mesh::Mesh;
-use super::mesh::Vertex;
+use super::mesh_file::{Bounds, MeshData, MeshFile, MeshMaterial, MeshTexture, TextureSource};
+use super::mesh_import::{self, gltf_materials, gltf_primitive, MeshCache};
+use super::shader_program::ShaderProgram;
 use super::texture::Texture;
+use crate::math::Mat4;
+use crate::utils::errors::ERRUranXError;
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::string::String;
-use tobj;
 
 #[derive(Debug)]
 pub struct Model {
     meshes: Vec<Mesh>,
-    texture_loader: HashMap<String, Texture>,
-    directory: String,
+    texture_loader: HashMap<String, Rc<Texture>>,
+    materials: Vec<Rc<Material>>,
+    bounds: Bounds,
+    path: String,
 }
 
 impl Model {
-    pub fn new(path: &str) -> Model {
-        let mut model = Model {
-            meshes: Vec::new(),
-            texture_loader: HashMap::new(),
-            directory: String::new(),
-        };
+    /// Loads a Wavefront OBJ, or a glTF 2.0 file (`.gltf`, `.glb`) with its whole default
+    /// scene merged into one model, node transforms applied to the vertices.
+    pub fn new(path: &str) -> Result<Model, ERRUranXError> {
+        Self::from_mesh_data(path, &mesh_import::import(path)?.data)
+    }
 
-        if let Some(parent) = std::path::Path::new(path).parent() {
-            model.directory = parent.to_string_lossy().to_string();
-        };
+    /// Like [`Model::new`], but reads the model from `cache` when its source is unchanged, and
+    /// stores it there after importing it otherwise. A cache that can't be written only warns.
+    pub fn load(path: &str, cache: &MeshCache) -> Result<Model, ERRUranXError> {
+        if let Some(model) = cache.load(path, |mesh_file| Self::from_mesh_file(path, mesh_file)) {
+            return model;
+        }
 
-        model.load_model(path);
-        model
+        let imported = mesh_import::import(path)?;
+        if let Err(error) = cache.store(path, &imported) {
+            eprintln!("[WARN] Model `{}` is not cached: {}", path, error);
+        }
+        Self::from_mesh_data(path, &imported.data)
     }
 
-    fn load_model(&mut self, path: &str) {
-        let (models, materials) =
-            tobj::load_obj(path, &tobj::LoadOptions::default()).expect("Failed to load .obj");
+    /// Uploads imported data, one mesh per submesh.
+    pub fn from_mesh_data(path: &str, data: &MeshData) -> Result<Model, ERRUranXError> {
+        let mut model = Self::empty(path);
+        model.create_materials(&data.materials)?;
+        model.push_meshes(data);
+        Ok(model)
+    }
 
-        for model in models {
-            self.process_mesh(&model);
+    /// Uploads the vertex and index data of a mesh file directly from its bytes.
+    pub fn from_mesh_file(path: &str, file: &MeshFile) -> Result<Model, ERRUranXError> {
+        let mut model = Self::empty(path);
+        model.create_materials(&file.materials)?;
+        model.bounds = file.bounds;
+
+        for submesh in &file.submeshes {
+            let material = model.submesh_material(submesh.material);
+            model.meshes.push(Mesh::from_bytes(
+                &file.layout,
+                file.submesh_vertex_bytes(submesh),
+                file.submesh_index_bytes(submesh),
+                file.index_width,
+                material,
+            ));
         }
 
-        if let Ok(materials) = materials {
-            for material in materials {
-                if !material.diffuse_texture.is_empty() {
-                    let texture_path = format!("{}/{}", self.directory, material.diffuse_texture);
-                    println!("[DEBUG] Loading texture: {}", texture_path);
-                    let texture = Texture::load(&texture_path, "diffuse".to_string());
-                    self.texture_loader
-                        .insert(material.diffuse_texture.clone(), texture);
-                }
+        Ok(model)
+    }
+
+    /// One mesh of a glTF document in its own space, for placing it with the node hierarchy.
+    /// `materials` are the document's, from [`Model::load_gltf_materials`].
+    pub fn from_gltf_mesh(document: &GltfDocument, mesh: usize, materials: &[Rc<Material>]) -> Model {
+        let mut data = MeshData::default();
+        for primitive in &document.meshes[mesh].primitives {
+            if let Some((vertices, indices)) = gltf_primitive(document, primitive, Mat4::IDENTITY) {
+                data.push_submesh(vertices, indices, primitive.material.map(|material| material as u32));
             }
         }
+
+        let mut model = Self::empty(&document.path);
+        model.materials = materials.to_vec();
+        model.push_meshes(&data);
+        model
+    }
+
+    fn empty(path: &str) -> Model {
+        Model {
+            meshes: Vec::new(),
+            texture_loader: HashMap::new(),
+            materials: Vec::new(),
+            bounds: Bounds::from_points([]),
+            path: path.to_string(),
+        }
+    }
+
+    /// Creates every material of `document`, loading each texture once.
+    pub fn load_gltf_materials(document: &GltfDocument) -> Result<Vec<Rc<Material>>, ERRUranXError> {
+        let mut model = Self::empty(&document.path);
+        model.create_materials(&gltf_materials(document))?;
+        Ok(model.materials)
     }
 
-    fn process_mesh(&mut self, model: &tobj::Model) {
-        let mesh = &model.mesh;
-        let mut vertices = Vec::new();
-        let indices = mesh.indices.clone();
-        let mut texture = Vec::new();
-
-        for i in 0..mesh.positions.len() / 3 {
-            let position = [
-                mesh.positions[i * 3],
-                mesh.positions[i * 3 + 1],
-                mesh.positions[i * 3 + 2],
-            ];
-
-            let normal = if !mesh.normals.is_empty() && (i * 3 + 2) < mesh.normals.len() {
-                [
-                    mesh.normals[i * 3],
-                    mesh.normals[i * 3 + 1],
-                    mesh.normals[i * 3 + 2],
-                ]
-            } else {
-                [0.0, 0.0, 0.0]
-            };
-
-            let texture_coords = if !mesh.texcoords.is_empty() {
-                [mesh.texcoords[i * 2], mesh.texcoords[i * 2 + 1]]
-            } else {
-                [0.0, 0.0]
-            };
-
-            println!(
-                "[DEBUG] Vertex {}: Pos({:?}), Normal({:?}), UV({:?})",
-                i, position, normal, texture_coords
-            );
-
-            vertices.push(Vertex {
-                position,
-                normal,
-                texture_coords,
-            });
+    fn push_meshes(&mut self, data: &MeshData) {
+        self.bounds = data.bounds();
+        for submesh in &data.submeshes {
+            let vertices = submesh.vertex_offset as usize..(submesh.vertex_offset + submesh.vertex_count) as usize;
+            let indices = submesh.index_offset as usize..(submesh.index_offset + submesh.index_count) as usize;
+            let material = self.submesh_material(submesh.material);
+            self.meshes.push(Mesh::from_data(&data.vertices.slice(vertices), &data.indices[indices], material));
         }
+    }
+
+    fn submesh_material(&self, material: Option<u32>) -> Option<Rc<Material>> {
+        material.and_then(|material| self.materials.get(material as usize)).cloned()
+    }
 
-        if let Some(material_id) = mesh.material_id {
-            if let Some(material) = self
-                .texture_loader
-                .get(&format!("{}/{}", self.directory, material_id))
-            {
-                texture.push(material.clone());
+    fn create_materials(&mut self, materials: &[MeshMaterial]) -> Result<(), ERRUranXError> {
+        for mesh_material in materials {
+            let mut material = Material::new(&mesh_material.name);
+            for (name, value) in &mesh_material.parameters {
+                material.set_parameter(name, *value);
             }
+            for (slot, texture) in &mesh_material.textures {
+                let texture = self.load_texture(texture, slot.name())?;
+                material.set_texture(*slot, texture);
+            }
+            self.materials.push(Rc::new(material));
+        }
+
+        Ok(())
+    }
+
+    /// Loads a texture once, however many materials use it.
+    fn load_texture(&mut self, texture: &MeshTexture, texture_type: &str) -> Result<Rc<Texture>, ERRUranXError> {
+        let key = match &texture.source {
+            TextureSource::File(path) => path,
+            TextureSource::Embedded { name, .. } => name,
+        };
+        if let Some(texture) = self.texture_loader.get(key) {
+            return Ok(texture.clone());
         }
 
-        let mesh = Mesh::new(vertices, indices, texture);
-        self.meshes.push(mesh);
+        let image = match &texture.source {
+            TextureSource::File(path) => Texture::decode_file(path)?,
+            TextureSource::Embedded { name, data } => Texture::decode_memory(data, name)?,
+        };
+        let image = if texture.flip_vertically { image.flipv() } else { image };
+
+        let result = Texture::from_image(&image, texture_type.to_string());
+        result.set_sampling(texture.mag_filter, texture.min_filter, texture.wrap_s, texture.wrap_t);
+        let result = Rc::new(result);
+        self.texture_loader.insert(key.clone(), result.clone());
+        Ok(result)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn meshes(&self) -> &[Mesh] {
+        &self.meshes
     }
 
-    pub fn draw(&self, shader_program: u32) {
+    pub fn materials(&self) -> &[Rc<Material>] {
+        &self.materials
+    }
+
+    /// Bounding box of every mesh, in model space.
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    /// Draws every mesh with its own material, or with `material` when one is given. Materials
+    /// without a shader use `shader`. The model matrix comes from the `ObjectData` block.
+    pub fn draw(&self, shader: &ShaderProgram, material: Option<&Material>) {
+        let fallback = Material::default();
+
         for mesh in &self.meshes {
-            mesh.draw(shader_program);
+            let mesh_material = material
+                .or(mesh.material().map(|material| material.as_ref()))
+                .unwrap_or(&fallback);
+            mesh_material.bind(shader);
+            mesh.draw();
         }
     }
 }
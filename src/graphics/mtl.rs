@@ -0,0 +1,248 @@
+use super::material::{MaterialValue, TextureSlot};
+use crate::math::Vec3;
+use crate::utils::errors::ERRUranXError;
+
+/// A material from an MTL file, with the texture options stripped from its map names.
+///
+/// Reading it does not touch GL, so MTL files can be inspected without a context; textures
+/// are only loaded when the model turns it into a [`Material`](super::material::Material).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    /// `Ka`
+    pub ambient: Vec3,
+    /// `Kd`
+    pub diffuse: Vec3,
+    /// `Ks`
+    pub specular: Vec3,
+    /// `Ke`
+    pub emissive: Vec3,
+    /// `Ns`, the specular exponent.
+    pub shininess: f32,
+    /// `d`, or `1 - Tr`; `1` is opaque.
+    pub dissolve: f32,
+    /// `illum`
+    pub illumination_model: Option<u8>,
+    /// `map_Kd`
+    pub diffuse_texture: Option<String>,
+    /// `map_Ks`
+    pub specular_texture: Option<String>,
+    /// `norm`, or `map_Bump`/`bump` when there is no `norm`.
+    pub normal_texture: Option<String>,
+    /// `map_d`
+    pub alpha_texture: Option<String>,
+    /// `map_Ke`
+    pub emissive_texture: Option<String>,
+}
+
+impl From<&tobj::Material> for MtlMaterial {
+    fn from(material: &tobj::Material) -> Self {
+        let unknown = |key: &str| material.unknown_param.get(key).map(String::as_str);
+        let dissolve = match unknown("Tr").and_then(|value| value.trim().parse::<f32>().ok()) {
+            Some(transparency) if material.dissolve == 1.0 => 1.0 - transparency,
+            _ => material.dissolve,
+        };
+
+        Self {
+            name: material.name.clone(),
+            ambient: Vec3::from(material.ambient),
+            diffuse: Vec3::from(material.diffuse),
+            specular: Vec3::from(material.specular),
+            emissive: unknown("Ke").and_then(parse_color).unwrap_or(Vec3::ZERO),
+            shininess: material.shininess,
+            dissolve,
+            illumination_model: material.illumination_model,
+            diffuse_texture: texture_file_name(&material.diffuse_texture),
+            specular_texture: texture_file_name(&material.specular_texture),
+            normal_texture: unknown("norm")
+                .and_then(texture_file_name)
+                .or_else(|| texture_file_name(&material.normal_texture)),
+            alpha_texture: texture_file_name(&material.dissolve_texture),
+            emissive_texture: unknown("map_Ke").and_then(texture_file_name),
+        }
+    }
+}
+
+impl MtlMaterial {
+    /// Uniform values for the material, named as in [`DEFAULT_PARAMETERS`](super::material::DEFAULT_PARAMETERS).
+    ///
+    /// A black `Kd` next to a `map_Kd` is taken as "not set" and becomes white, since some
+    /// exporters leave `Kd` out and the texture would otherwise be multiplied away.
+    pub fn parameters(&self) -> Vec<(&'static str, MaterialValue)> {
+        let diffuse = if self.diffuse == Vec3::ZERO && self.diffuse_texture.is_some() {
+            Vec3::ONE
+        } else {
+            self.diffuse
+        };
+
+        let mut parameters = vec![
+            ("albedoColor", MaterialValue::Vec4([diffuse.x, diffuse.y, diffuse.z, self.dissolve])),
+            ("ambientColor", MaterialValue::Vec3(self.ambient.to_array())),
+            ("specularColor", MaterialValue::Vec3(self.specular.to_array())),
+            ("emissiveColor", MaterialValue::Vec3(self.emissive.to_array())),
+            ("shininess", MaterialValue::Float(self.shininess)),
+        ];
+        if let Some(illumination_model) = self.illumination_model {
+            parameters.push(("illuminationModel", MaterialValue::Int(illumination_model as i32)));
+        }
+
+        parameters
+    }
+
+    /// Texture file names per slot, relative to the MTL file.
+    pub fn textures(&self) -> Vec<(TextureSlot, &str)> {
+        [
+            (TextureSlot::Albedo, &self.diffuse_texture),
+            (TextureSlot::Specular, &self.specular_texture),
+            (TextureSlot::Normal, &self.normal_texture),
+            (TextureSlot::Opacity, &self.alpha_texture),
+            (TextureSlot::Emissive, &self.emissive_texture),
+        ]
+        .into_iter()
+        .filter_map(|(slot, name)| name.as_deref().map(|name| (slot, name)))
+        .collect()
+    }
+}
+
+/// Reads every material of an MTL file.
+pub fn load_mtl(path: &str) -> Result<Vec<MtlMaterial>, ERRUranXError> {
+    let (materials, _) = tobj::load_mtl(path)
+        .map_err(|source| ERRUranXError::OBJ_PARSE_ERROR { path: path.to_string(), source })?;
+    Ok(materials.iter().map(MtlMaterial::from).collect())
+}
+
+fn parse_color(value: &str) -> Option<Vec3> {
+    let components: Vec<f32> = value
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    match *components.as_slice() {
+        [x, y, z] => Some(Vec3::new(x, y, z)),
+        [x] => Some(Vec3::splat(x)),
+        _ => None,
+    }
+}
+
+/// The file name of a `map_*` statement without its options, e.g. `brick.png` for
+/// `-bm 0.5 -s 2 2 brick.png`. `None` for an empty statement.
+pub fn texture_file_name(statement: &str) -> Option<String> {
+    let mut words = statement.split_whitespace().peekable();
+
+    while let Some(option) = words.peek().copied().filter(|word| word.starts_with('-')) {
+        words.next();
+        let (min_arguments, max_arguments) = match option {
+            "-o" | "-s" | "-t" => (1, 3),
+            "-mm" => (2, 2),
+            _ => (1, 1),
+        };
+
+        for index in 0..max_arguments {
+            let is_number = words.peek().is_some_and(|word| word.parse::<f32>().is_ok());
+            // `-clamp on`, `-imfchan r` and `-type sphere` take a word rather than a number.
+            if index < min_arguments || is_number {
+                words.next();
+            }
+        }
+    }
+
+    let name = words.collect::<Vec<_>>().join(" ");
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(name: &str) -> MtlMaterial {
+        load_mtl("res/objects/materials.mtl")
+            .unwrap()
+            .into_iter()
+            .find(|material| material.name == name)
+            .unwrap()
+    }
+
+    fn parameter(material: &MtlMaterial, name: &str) -> Option<MaterialValue> {
+        material
+            .parameters()
+            .into_iter()
+            .find(|(parameter, _)| *parameter == name)
+            .map(|(_, value)| value)
+    }
+
+    #[test]
+    fn reads_colors_and_textures() {
+        let brick = sample("brick");
+
+        assert_eq!(brick.ambient, Vec3::splat(0.1));
+        assert_eq!(brick.diffuse, Vec3::new(0.6, 0.3, 0.2));
+        assert_eq!(brick.specular, Vec3::splat(0.5));
+        assert_eq!(brick.emissive, Vec3::new(0.0, 0.2, 0.4));
+        assert_eq!(brick.shininess, 32.0);
+        assert_eq!(brick.dissolve, 1.0);
+        assert_eq!(brick.illumination_model, Some(2));
+
+        assert_eq!(
+            brick.textures(),
+            [
+                (TextureSlot::Albedo, "brick.png"),
+                (TextureSlot::Specular, "brick_specular.png"),
+                // `norm` wins over `map_Bump`.
+                (TextureSlot::Normal, "brick_normal.png"),
+                (TextureSlot::Opacity, "brick_alpha.png"),
+                (TextureSlot::Emissive, "brick glow.png"),
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_for_missing_statements() {
+        let glass = sample("glass");
+        assert_eq!(glass.dissolve, 0.75);
+        assert_eq!(glass.emissive, Vec3::ZERO);
+        assert_eq!(glass.illumination_model, None);
+
+        let plain = sample("plain");
+        assert_eq!(plain.emissive, Vec3::splat(0.5));
+        assert_eq!(plain.normal_texture.as_deref(), Some("plain_bump.png"));
+        assert_eq!(plain.diffuse_texture, None);
+    }
+
+    #[test]
+    fn parameters_use_the_standard_names() {
+        let brick = sample("brick");
+        assert_eq!(parameter(&brick, "albedoColor"), Some(MaterialValue::Vec4([0.6, 0.3, 0.2, 1.0])));
+        assert_eq!(parameter(&brick, "ambientColor"), Some(MaterialValue::Vec3([0.1, 0.1, 0.1])));
+        assert_eq!(parameter(&brick, "specularColor"), Some(MaterialValue::Vec3([0.5, 0.5, 0.5])));
+        assert_eq!(parameter(&brick, "emissiveColor"), Some(MaterialValue::Vec3([0.0, 0.2, 0.4])));
+        assert_eq!(parameter(&brick, "shininess"), Some(MaterialValue::Float(32.0)));
+        assert_eq!(parameter(&brick, "illuminationModel"), Some(MaterialValue::Int(2)));
+
+        for (name, _) in brick.parameters() {
+            let standard = crate::graphics::material::DEFAULT_PARAMETERS
+                .iter()
+                .any(|(default, _)| *default == name);
+            assert!(standard || name == "illuminationModel", "`{name}` is not a standard parameter");
+        }
+    }
+
+    #[test]
+    fn black_diffuse_next_to_a_map_becomes_white() {
+        let glass = sample("glass");
+        assert_eq!(parameter(&glass, "albedoColor"), Some(MaterialValue::Vec4([1.0, 1.0, 1.0, 0.75])));
+        assert_eq!(parameter(&glass, "illuminationModel"), None);
+    }
+
+    #[test]
+    fn texture_options_are_stripped() {
+        assert_eq!(texture_file_name("brick.png").as_deref(), Some("brick.png"));
+        assert_eq!(texture_file_name("-bm 0.5 -s 2 2 brick.png").as_deref(), Some("brick.png"));
+        assert_eq!(texture_file_name("-s 2 brick.png").as_deref(), Some("brick.png"));
+        assert_eq!(texture_file_name("-o 0.1 0.2 0.3 -mm 0 1 brick.png").as_deref(), Some("brick.png"));
+        assert_eq!(texture_file_name("-clamp on -imfchan r -type sphere sky.png").as_deref(), Some("sky.png"));
+        assert_eq!(texture_file_name("-blendu off textures/old brick.png").as_deref(), Some("textures/old brick.png"));
+        assert_eq!(texture_file_name(""), None);
+        assert_eq!(texture_file_name("-bm 0.5"), None);
+    }
+}
@@ -0,0 +1,503 @@
+//! Built-in shapes as vertices and triangle indices, ready for [`Mesh::from_data`].
+//!
+//! Every shape is centred on the origin with `+Y` up, winds its front faces counter-clockwise
+//! and has normals, UV set 0 with `v` pointing up, and tangents. Round shapes put their UV
+//! seam at the back (`-Z`). Counts below their minimum are raised to it.
+//!
+//! [`Mesh::from_data`]: super::mesh::Mesh::from_data
+
+use super::mesh_processing::{deduplicate, generate_tangents};
+use super::vertex_data::VertexData;
+use crate::math::Vec3;
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// A cube with edges of `size` and one UV square per face.
+pub fn cube(size: f32) -> (VertexData, Vec<u32>) {
+    let half = size / 2.0;
+    let faces = [
+        (Vec3::X, -Vec3::Z, Vec3::Y),
+        (-Vec3::X, Vec3::Z, Vec3::Y),
+        (Vec3::Y, Vec3::X, -Vec3::Z),
+        (-Vec3::Y, Vec3::X, Vec3::Z),
+        (Vec3::Z, Vec3::X, Vec3::Y),
+        (-Vec3::Z, -Vec3::X, Vec3::Y),
+    ];
+
+    let mut builder = Builder::default();
+    for (normal, right, up) in faces {
+        let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y)| {
+            let position = (normal + right * x + up * y) * half;
+            builder.vertex(position, normal, [(x + 1.0) / 2.0, (y + 1.0) / 2.0])
+        });
+        builder.quad(corners);
+    }
+    builder.finish()
+}
+
+/// A sphere of `segments` around its axis and `rings` from pole to pole.
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> (VertexData, Vec<u32>) {
+    let rings = rings.max(2);
+    let profile = (0..=rings).map(|ring| {
+        let latitude = -FRAC_PI_2 + PI * ring as f32 / rings as f32;
+        ProfilePoint::on_circle(radius, 0.0, 0.0, latitude, ring as f32 / rings as f32)
+    });
+    lathe(profile.collect(), segments)
+}
+
+/// A sphere made by splitting each triangle of an icosahedron into four `subdivisions` times.
+/// Its triangles are more even than a [`uv_sphere`]'s.
+pub fn icosphere(radius: f32, subdivisions: u32) -> (VertexData, Vec<u32>) {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut points: Vec<Vec3> = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+    .into_iter()
+    .map(|point| Vec3::from(point).normalize())
+    .collect();
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                points.push(((points[a as usize] + points[b as usize]) / 2.0).normalize());
+                (points.len() - 1) as u32
+            })
+        };
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    // UVs are worked out per corner: triangles across the seam need `u` past 1 on one side,
+    // and a pole takes the `u` of the triangle it is in.
+    let mut builder = Builder::default();
+    for triangle in &triangles {
+        let corners = triangle.map(|index| points[index as usize]);
+        let mut texture_coords = corners.map(|point| {
+            let u = (-point.x).atan2(-point.z) / TAU;
+            [if u < 0.0 { u + 1.0 } else { u }, 0.5 + point.y.clamp(-1.0, 1.0).asin() / PI]
+        });
+        let is_pole = corners.map(|point| point.x * point.x + point.z * point.z < 1e-10);
+
+        let us = (0..3).filter(|corner| !is_pole[*corner]).map(|corner| texture_coords[corner][0]);
+        if us.clone().fold(f32::MIN, f32::max) - us.fold(f32::MAX, f32::min) > 0.5 {
+            for [u, _] in texture_coords.iter_mut().filter(|[u, _]| *u < 0.5) {
+                *u += 1.0;
+            }
+        }
+        for corner in (0..3).filter(|corner| is_pole[*corner]) {
+            let others: Vec<f32> = (0..3).filter(|other| !is_pole[*other]).map(|other| texture_coords[other][0]).collect();
+            texture_coords[corner][0] = others.iter().sum::<f32>() / others.len().max(1) as f32;
+        }
+
+        let corners = [0, 1, 2].map(|corner| builder.vertex(corners[corner] * radius, corners[corner], texture_coords[corner]));
+        builder.indices.extend(corners);
+    }
+
+    let (vertices, indices) = deduplicate(&builder.vertices, &builder.indices);
+    generate_tangents(&vertices, &indices)
+}
+
+/// A `width` by `depth` plane facing `+Y`, split into `subdivisions` quads along each side.
+pub fn plane(width: f32, depth: f32, subdivisions: u32) -> (VertexData, Vec<u32>) {
+    let subdivisions = subdivisions.max(1);
+    let mut builder = Builder::default();
+    for row in 0..=subdivisions {
+        for column in 0..=subdivisions {
+            let (u, v) = (column as f32 / subdivisions as f32, row as f32 / subdivisions as f32);
+            let position = Vec3::new((u - 0.5) * width, 0.0, (0.5 - v) * depth);
+            builder.vertex(position, Vec3::Y, [u, v]);
+        }
+    }
+
+    let row_length = subdivisions + 1;
+    for row in 0..subdivisions {
+        for column in 0..subdivisions {
+            let corner = row * row_length + column;
+            builder.quad([corner, corner + 1, corner + row_length + 1, corner + row_length]);
+        }
+    }
+    builder.finish()
+}
+
+/// A closed cylinder along the Y axis.
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> (VertexData, Vec<u32>) {
+    let half = height / 2.0;
+    let side = vec![
+        ProfilePoint { radius, y: -half, normal: [1.0, 0.0], v: 0.0 },
+        ProfilePoint { radius, y: half, normal: [1.0, 0.0], v: 1.0 },
+    ];
+
+    let mut builder = Builder::from(lathe_vertices(&side, segments));
+    builder.disk(radius, half, true, segments);
+    builder.disk(radius, -half, false, segments);
+    builder.finish()
+}
+
+/// A cone along the Y axis with its tip at `+height / 2` and a closed base.
+pub fn cone(radius: f32, height: f32, segments: u32) -> (VertexData, Vec<u32>) {
+    let half = height / 2.0;
+    let slope = Vec3::new(height, radius, 0.0).normalize();
+    let side = vec![
+        ProfilePoint { radius, y: -half, normal: [slope.x, slope.y], v: 0.0 },
+        ProfilePoint { radius: 0.0, y: half, normal: [slope.x, slope.y], v: 1.0 },
+    ];
+
+    let mut builder = Builder::from(lathe_vertices(&side, segments));
+    builder.disk(radius, -half, false, segments);
+    builder.finish()
+}
+
+/// A cylinder of `height` along the Y axis with a hemisphere of `rings` on each end. `v` runs
+/// along the whole outline, so textures don't stretch on the caps.
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> (VertexData, Vec<u32>) {
+    let rings = rings.max(1);
+    let half = height / 2.0;
+    let length = PI * radius + height;
+    let mut profile = Vec::new();
+    for ring in 0..=rings {
+        let latitude = -FRAC_PI_2 + FRAC_PI_2 * ring as f32 / rings as f32;
+        let v = radius * (latitude + FRAC_PI_2) / length;
+        profile.push(ProfilePoint::on_circle(radius, 0.0, -half, latitude, v));
+    }
+    for ring in 0..=rings {
+        let latitude = FRAC_PI_2 * ring as f32 / rings as f32;
+        let v = (radius * (latitude + FRAC_PI_2) + height) / length;
+        profile.push(ProfilePoint::on_circle(radius, 0.0, half, latitude, v));
+    }
+    lathe(profile, segments)
+}
+
+/// A torus around the Y axis: a tube of `minor_radius` and `sides` following a circle of
+/// `major_radius` and `segments`. `v` starts on the inside of the ring.
+pub fn torus(major_radius: f32, minor_radius: f32, segments: u32, sides: u32) -> (VertexData, Vec<u32>) {
+    let sides = sides.max(3);
+    let profile = (0..=sides).map(|side| {
+        let v = side as f32 / sides as f32;
+        let latitude = -PI + TAU * (side % sides) as f32 / sides as f32;
+        ProfilePoint::on_circle(minor_radius, major_radius, 0.0, latitude, v)
+    });
+    lathe(profile.collect(), segments)
+}
+
+/// Two triangles covering the screen, in clip space (`-1` to `1`) so they need no transform.
+pub fn fullscreen_quad() -> (VertexData, Vec<u32>) {
+    let mut builder = Builder::default();
+    let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+        .map(|(x, y)| builder.vertex(Vec3::new(x, y, 0.0), Vec3::Z, [(x + 1.0) / 2.0, (y + 1.0) / 2.0]));
+    builder.quad(corners);
+    builder.finish()
+}
+
+/// A point of the outline [`lathe`] turns around the Y axis.
+#[derive(Debug, Clone, Copy)]
+struct ProfilePoint {
+    /// Distance from the axis.
+    radius: f32,
+    y: f32,
+    /// Normal as (away from the axis, along Y).
+    normal: [f32; 2],
+    v: f32,
+}
+
+impl ProfilePoint {
+    /// The point at `latitude` on a circle of `radius` around (`center_radius`, `center_y`).
+    fn on_circle(radius: f32, center_radius: f32, center_y: f32, latitude: f32, v: f32) -> Self {
+        let normal = [latitude.cos(), latitude.sin()];
+        ProfilePoint {
+            radius: (center_radius + radius * normal[0]).max(0.0),
+            y: center_y + radius * normal[1],
+            normal,
+            v,
+        }
+    }
+}
+
+fn lathe(profile: Vec<ProfilePoint>, segments: u32) -> (VertexData, Vec<u32>) {
+    Builder::from(lathe_vertices(&profile, segments)).finish()
+}
+
+/// Turns `profile`, listed bottom to top, around the Y axis. Each ring has a doubled vertex
+/// on the seam. Points on the axis get their own vertex per segment, halfway along it, and
+/// the triangles that would collapse there are left out.
+fn lathe_vertices(profile: &[ProfilePoint], segments: u32) -> (VertexData, Vec<u32>) {
+    let segments = segments.max(3);
+    let mut builder = Builder::default();
+    for point in profile {
+        let offset = if point.radius == 0.0 { 0.5 } else { 0.0 };
+        for segment in 0..=segments {
+            let u = (segment as f32 + offset) / segments as f32;
+            // The seam copy turns by `segment % segments`, so it lands exactly on the first vertex.
+            let angle = ((segment % segments) as f32 + offset) / segments as f32 * TAU;
+            let outward = Vec3::new(-angle.sin(), 0.0, -angle.cos());
+            let position = outward * point.radius + Vec3::Y * point.y;
+            let normal = outward * point.normal[0] + Vec3::Y * point.normal[1];
+            builder.vertex(position, normal.normalize(), [u, point.v]);
+        }
+    }
+
+    let ring_length = segments + 1;
+    for (ring, points) in profile.windows(2).enumerate() {
+        for segment in 0..segments {
+            let a = ring as u32 * ring_length + segment;
+            let (b, c, d) = (a + 1, a + ring_length + 1, a + ring_length);
+            // On the axis `c` and `d` are the same point; `d` has this segment's `u`.
+            if points[0].radius != 0.0 {
+                builder.indices.extend([a, b, if points[1].radius == 0.0 { d } else { c }]);
+            }
+            if points[1].radius != 0.0 {
+                builder.indices.extend([a, c, d]);
+            }
+        }
+    }
+    (builder.vertices, builder.indices)
+}
+
+#[derive(Default)]
+struct Builder {
+    vertices: VertexData,
+    indices: Vec<u32>,
+}
+
+impl From<(VertexData, Vec<u32>)> for Builder {
+    fn from((vertices, indices): (VertexData, Vec<u32>)) -> Self {
+        Builder { vertices, indices }
+    }
+}
+
+impl Builder {
+    fn vertex(&mut self, position: Vec3, normal: Vec3, texture_coords: [f32; 2]) -> u32 {
+        if self.vertices.texture_coords.is_empty() {
+            self.vertices.texture_coords.push(Vec::new());
+        }
+        self.vertices.positions.push(position.to_array());
+        self.vertices.normals.push(normal.to_array());
+        self.vertices.texture_coords[0].push(texture_coords);
+        (self.vertices.len() - 1) as u32
+    }
+
+    /// Two triangles for corners in counter-clockwise order.
+    fn quad(&mut self, [a, b, c, d]: [u32; 4]) {
+        self.indices.extend([a, b, c, a, c, d]);
+    }
+
+    /// A flat disk at height `y` facing up or down, with UVs mapped from above or below.
+    fn disk(&mut self, radius: f32, y: f32, up: bool, segments: u32) {
+        let segments = segments.max(3);
+        let (normal, mirror) = if up { (Vec3::Y, 1.0) } else { (-Vec3::Y, -1.0) };
+        let center = self.vertex(Vec3::new(0.0, y, 0.0), normal, [0.5, 0.5]);
+        for segment in 0..segments {
+            let angle = segment as f32 / segments as f32 * TAU;
+            let (x, z) = (-angle.sin(), -angle.cos());
+            self.vertex(Vec3::new(x * radius, y, z * radius), normal, [0.5 + x / 2.0, 0.5 - mirror * z / 2.0]);
+        }
+
+        for segment in 0..segments {
+            let (first, second) = (center + 1 + segment, center + 1 + (segment + 1) % segments);
+            if up {
+                self.indices.extend([center, first, second]);
+            } else {
+                self.indices.extend([center, second, first]);
+            }
+        }
+    }
+
+    fn finish(self) -> (VertexData, Vec<u32>) {
+        generate_tangents(&self.vertices, &self.indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks what the module promises of every shape and returns it for shape-specific checks.
+    /// `max_u` is `1` except for shapes whose seam triangles run past it.
+    fn checked(name: &str, (vertices, indices): (VertexData, Vec<u32>), max_u: f32) -> (VertexData, Vec<u32>) {
+        assert!(!indices.is_empty() && indices.len() % 3 == 0, "{name}: {} indices", indices.len());
+        assert!(indices.iter().all(|index| (*index as usize) < vertices.len()), "{name}: index out of bounds");
+        assert_eq!(vertices.normals.len(), vertices.len(), "{name}");
+        assert_eq!(vertices.tangents.len(), vertices.len(), "{name}");
+        assert_eq!(vertices.texture_coords(0).len(), vertices.len(), "{name}");
+
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| Vec3::from(vertices.positions[triangle[corner] as usize]));
+            let winding = (b - a).cross(c - a);
+            assert!(winding.length() > 1e-8, "{name}: degenerate triangle {triangle:?}");
+            for index in triangle {
+                let normal = Vec3::from(vertices.normals[*index as usize]);
+                assert!((normal.length() - 1.0).abs() < 1e-5, "{name}: normal {normal:?} is not unit length");
+                assert!(normal.dot(winding) > 0.0, "{name}: triangle {triangle:?} winds against its normals");
+            }
+        }
+
+        for (tangent, normal) in vertices.tangents.iter().zip(&vertices.normals) {
+            let direction = Vec3::new(tangent[0], tangent[1], tangent[2]);
+            assert!((direction.length() - 1.0).abs() < 1e-4, "{name}: tangent {tangent:?} is not unit length");
+            assert!(direction.dot(Vec3::from(*normal)).abs() < 1e-4, "{name}: tangent {tangent:?} is not orthogonal");
+            assert!(tangent[3] == 1.0 || tangent[3] == -1.0, "{name}: tangent {tangent:?} has no handedness");
+        }
+
+        for [u, v] in vertices.texture_coords(0) {
+            assert!((0.0..=max_u).contains(u) && (0.0..=1.0).contains(v), "{name}: UV {:?} out of range", [u, v]);
+        }
+
+        (vertices, indices)
+    }
+
+    /// Vertices whose position is on the Y axis.
+    fn on_axis(vertices: &VertexData) -> usize {
+        vertices.positions.iter().filter(|[x, _, z]| *x == 0.0 && *z == 0.0).count()
+    }
+
+    /// Vertices on the `u = 1` side of the seam, each sitting exactly on a `u = 0` vertex.
+    fn seam_copies(vertices: &VertexData) -> usize {
+        let texture_coords = vertices.texture_coords(0);
+        let at_u = |u: f32| -> Vec<[f32; 3]> {
+            (0..vertices.len())
+                .filter(|index| texture_coords[*index][0] == u)
+                .map(|index| vertices.positions[index])
+                .collect()
+        };
+        let starts = at_u(0.0);
+        let copies = at_u(1.0);
+        assert!(copies.iter().all(|copy| starts.contains(copy)), "a seam copy is not on the seam");
+        copies.len()
+    }
+
+    #[test]
+    fn cube() {
+        let (vertices, indices) = checked("cube", super::cube(2.0), 1.0);
+        assert_eq!((vertices.len(), indices.len()), (24, 36));
+        assert!(vertices.positions.iter().flatten().all(|value| value.abs() == 1.0));
+    }
+
+    #[test]
+    fn uv_sphere() {
+        let (segments, rings) = (12, 6);
+        let (vertices, indices) = checked("uv_sphere", super::uv_sphere(2.0, segments, rings), 1.0);
+
+        // Inner rings have a seam copy, each pole a vertex per segment.
+        assert_eq!(vertices.len(), ((rings - 1) * (segments + 1) + 2 * segments) as usize);
+        assert_eq!(indices.len(), (6 * segments * (rings - 1)) as usize);
+        assert_eq!(seam_copies(&vertices), (rings - 1) as usize);
+        assert_eq!(on_axis(&vertices), 2 * segments as usize);
+
+        for (position, normal) in vertices.positions.iter().zip(&vertices.normals) {
+            assert!((Vec3::from(*position) / 2.0).abs_diff_eq(Vec3::from(*normal), 1e-5));
+        }
+    }
+
+    #[test]
+    fn icosphere() {
+        for subdivisions in 0..3 {
+            let (vertices, indices) = checked("icosphere", super::icosphere(1.0, subdivisions), 1.5);
+            assert_eq!(indices.len(), 60 * 4usize.pow(subdivisions));
+
+            // Points are only split at the poles, and into a pair one turn of `u` apart where
+            // triangles cross the seam.
+            let mut points: HashMap<[u32; 3], Vec<f32>> = HashMap::new();
+            for (position, [u, _]) in vertices.positions.iter().zip(vertices.texture_coords(0)) {
+                points.entry(position.map(|value| (value + 0.0).to_bits())).or_default().push(*u);
+            }
+            assert_eq!(points.len(), 10 * 4usize.pow(subdivisions) + 2);
+            for (point, us) in &points {
+                let is_pole = point[0] == 0 && point[2] == 0;
+                let is_seam_pair = us.len() == 2 && ((us[0] - us[1]).abs() - 1.0).abs() < 1e-6;
+                assert!(us.len() == 1 || is_pole || is_seam_pair, "{point:?} is split into u {us:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn plane() {
+        let (vertices, indices) = checked("plane", super::plane(4.0, 2.0, 3), 1.0);
+        assert_eq!((vertices.len(), indices.len()), (16, 54));
+        assert!(vertices.normals.iter().all(|normal| *normal == [0.0, 1.0, 0.0]));
+        assert!(vertices.positions.iter().all(|[x, y, z]| x.abs() <= 2.0 && *y == 0.0 && z.abs() <= 1.0));
+    }
+
+    #[test]
+    fn cylinder() {
+        let segments = 10;
+        let (vertices, indices) = checked("cylinder", super::cylinder(1.0, 2.0, segments), 1.0);
+
+        // Two side rings with seam copies, and a centre plus a ring on each cap.
+        assert_eq!(vertices.len(), (4 * (segments + 1)) as usize);
+        assert_eq!(indices.len(), (12 * segments) as usize);
+        assert_eq!(seam_copies(&vertices), 2);
+        assert_eq!(on_axis(&vertices), 2);
+    }
+
+    #[test]
+    fn cone() {
+        let segments = 10;
+        let (vertices, indices) = checked("cone", super::cone(1.0, 2.0, segments), 1.0);
+
+        // A side ring with a seam copy, a tip vertex per segment, and the base cap.
+        assert_eq!(vertices.len(), (3 * segments + 2) as usize);
+        assert_eq!(indices.len(), (6 * segments) as usize);
+        assert_eq!(seam_copies(&vertices), 1);
+        assert_eq!(on_axis(&vertices), segments as usize + 1);
+    }
+
+    #[test]
+    fn capsule() {
+        let (segments, rings) = (8, 4);
+        let (vertices, indices) = checked("capsule", super::capsule(0.5, 1.0, segments, rings), 1.0);
+
+        assert_eq!(vertices.len(), (2 * rings * (segments + 1) + 2 * segments) as usize);
+        assert_eq!(indices.len(), (12 * segments * rings) as usize);
+        assert_eq!(seam_copies(&vertices), (2 * rings) as usize);
+        assert_eq!(on_axis(&vertices), 2 * segments as usize);
+        assert!(vertices.positions.iter().all(|[_, y, _]| y.abs() <= 1.0 + 1e-6));
+    }
+
+    #[test]
+    fn torus() {
+        let (segments, sides) = (12, 6);
+        let (vertices, indices) = checked("torus", super::torus(1.0, 0.25, segments, sides), 1.0);
+
+        // Both the segment and the side seams have copies.
+        assert_eq!(vertices.len(), ((segments + 1) * (sides + 1)) as usize);
+        assert_eq!(indices.len(), (6 * segments * sides) as usize);
+        assert_eq!(seam_copies(&vertices), (sides + 1) as usize);
+        assert_eq!(on_axis(&vertices), 0);
+    }
+
+    #[test]
+    fn fullscreen_quad() {
+        let (vertices, indices) = checked("fullscreen_quad", super::fullscreen_quad(), 1.0);
+        assert_eq!((vertices.len(), indices.len()), (4, 6));
+        assert!(vertices.positions.iter().flatten().all(|value| value.abs() == 1.0 || *value == 0.0));
+    }
+
+    #[test]
+    fn counts_below_the_minimum_are_raised() {
+        assert_eq!(super::uv_sphere(1.0, 0, 0), super::uv_sphere(1.0, 3, 2));
+        assert_eq!(super::plane(1.0, 1.0, 0), super::plane(1.0, 1.0, 1));
+        assert_eq!(super::torus(1.0, 0.25, 1, 1), super::torus(1.0, 0.25, 3, 3));
+    }
+}
@@ -0,0 +1,409 @@
+use crate::utils::errors::ERRUranXError;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `#define`s injected into a shader, kept sorted so equal sets give equal variant keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ShaderDefines {
+    defines: BTreeMap<String, String>,
+}
+
+impl ShaderDefines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `#define name 1`.
+    pub fn with(self, name: &str) -> Self {
+        self.with_value(name, "1")
+    }
+
+    pub fn with_value(mut self, name: &str, value: &str) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.defines.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.defines.remove(name);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.defines.contains_key(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.defines.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defines.is_empty()
+    }
+}
+
+/// `NAME=value` pairs joined by `;`, e.g. `HAS_NORMAL_MAP=1;SKINNED=1`.
+impl Display for ShaderDefines {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let pairs: Vec<String> = self
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        write!(f, "{}", pairs.join(";"))
+    }
+}
+
+/// A shader with includes expanded and defines injected, ready for `glShaderSource`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderSource {
+    pub code: String,
+    /// Every file that went into `code`; the index is the source string number used in the
+    /// `#line` directives, so `files[0]` is the root file.
+    pub files: Vec<String>,
+}
+
+/// Expands `#include "file.glsl"` and injects `#define`s into GLSL sources.
+///
+/// Included paths are looked up next to the including file first, then in the shader
+/// directory. Each inclusion is wrapped in `#line` directives so the driver reports errors
+/// against the original file and line. Files containing `#pragma once` are only expanded
+/// the first time; any other repeated include is expanded again, but an include cycle is an
+/// error. Includes are expanded unconditionally, even inside `#ifdef` blocks.
+#[derive(Debug, Clone)]
+pub struct ShaderPreprocessor {
+    directory: PathBuf,
+}
+
+struct Expansion<'a, F> {
+    defines: &'a ShaderDefines,
+    read: F,
+    code: String,
+    files: Vec<String>,
+    stack: Vec<PathBuf>,
+    included_once: Vec<PathBuf>,
+}
+
+impl ShaderPreprocessor {
+    pub fn new(directory: &str) -> Self {
+        Self {
+            directory: PathBuf::from(directory),
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn process(&self, path: &str, defines: &ShaderDefines) -> Result<ShaderSource, ERRUranXError> {
+        self.process_with(path, defines, |path| std::fs::read_to_string(path))
+    }
+
+    /// Like [`ShaderPreprocessor::process`], reading files through `read` instead of the disk.
+    pub fn process_with(
+        &self,
+        path: &str,
+        defines: &ShaderDefines,
+        read: impl FnMut(&Path) -> io::Result<String>,
+    ) -> Result<ShaderSource, ERRUranXError> {
+        let mut expansion = Expansion {
+            defines,
+            read,
+            code: String::new(),
+            files: Vec::new(),
+            stack: Vec::new(),
+            included_once: Vec::new(),
+        };
+
+        expansion.expand(self, Path::new(path), true)?;
+
+        Ok(ShaderSource {
+            code: expansion.code,
+            files: expansion.files,
+        })
+    }
+
+    fn resolve(&self, including_file: &Path, include: &str) -> PathBuf {
+        let beside = including_file
+            .parent()
+            .map(|parent| parent.join(include))
+            .unwrap_or_else(|| PathBuf::from(include));
+
+        if beside.exists() || !self.directory.join(include).exists() {
+            beside
+        } else {
+            self.directory.join(include)
+        }
+    }
+}
+
+impl<F: FnMut(&Path) -> io::Result<String>> Expansion<'_, F> {
+    fn expand(&mut self, preprocessor: &ShaderPreprocessor, path: &Path, is_root: bool) -> Result<(), ERRUranXError> {
+        let path_name = path.to_string_lossy().to_string();
+        let key = normalize(path);
+
+        if self.included_once.contains(&key) {
+            return Ok(());
+        }
+
+        let source = (self.read)(path).map_err(|source| ERRUranXError::io(&path_name, source))?;
+        let index = self.files.len();
+        self.files.push(path_name.clone());
+        self.stack.push(key.clone());
+
+        // `#version` has to come first, so it is hoisted above the defines and its own line
+        // is left blank to keep the numbering.
+        let has_version = is_root && find_version(&source).is_some();
+        if is_root {
+            if let Some(version) = find_version(&source) {
+                self.code.push_str(version.trim());
+                self.code.push('\n');
+            }
+            self.inject_defines();
+            self.code.push_str(&format!("#line 1 {}\n", index));
+        }
+
+        for (line_index, line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
+            match directive(line) {
+                Some(("version", _)) if has_version => {
+                    self.code.push('\n');
+                }
+                Some(("version", _)) => {
+                    return Err(ERRUranXError::SHADER_PREPROCESS_ERROR {
+                        path: path_name,
+                        line: line_number,
+                        message: "`#version` is only allowed at the top of the root shader".to_string(),
+                    });
+                }
+                Some(("pragma", "once")) => {
+                    self.included_once.push(key.clone());
+                    self.code.push('\n');
+                }
+                Some(("include", argument)) => {
+                    let include = parse_include(argument).ok_or_else(|| ERRUranXError::SHADER_PREPROCESS_ERROR {
+                        path: path_name.clone(),
+                        line: line_number,
+                        message: format!("expected `#include \"file\"`, found `{}`", line.trim()),
+                    })?;
+
+                    let include_path = preprocessor.resolve(path, include);
+                    if self.stack.contains(&normalize(&include_path)) {
+                        let chain: Vec<String> = self
+                            .stack
+                            .iter()
+                            .map(|path| path.to_string_lossy().to_string())
+                            .chain(std::iter::once(include_path.to_string_lossy().to_string()))
+                            .collect();
+                        return Err(ERRUranXError::SHADER_PREPROCESS_ERROR {
+                            path: path_name,
+                            line: line_number,
+                            message: format!("include cycle {}", chain.join(" -> ")),
+                        });
+                    }
+
+                    self.code.push_str(&format!("#line 1 {}\n", self.files.len()));
+                    self.expand(preprocessor, &include_path, false)?;
+                    self.code.push_str(&format!("#line {} {}\n", line_number + 1, index));
+                }
+                _ => {
+                    self.code.push_str(line);
+                    self.code.push('\n');
+                }
+            }
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn inject_defines(&mut self) {
+        for (name, value) in self.defines.iter() {
+            self.code.push_str(&format!("#define {} {}\n", name, value));
+        }
+    }
+}
+
+/// Splits a preprocessor line into its directive name and the rest, e.g. `("include", "\"a\"")`.
+fn directive(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start();
+    let name_length = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(rest.len());
+    let (name, argument) = rest.split_at(name_length);
+
+    let argument = argument.split("//").next().unwrap_or("").trim();
+    Some((name, argument))
+}
+
+fn parse_include(argument: &str) -> Option<&str> {
+    let argument = argument.trim();
+    let inner = argument
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .or_else(|| argument.strip_prefix('<').and_then(|rest| rest.strip_suffix('>')))?;
+
+    (!inner.is_empty()).then_some(inner)
+}
+
+fn find_version(source: &str) -> Option<&str> {
+    source
+        .lines()
+        .find(|line| matches!(directive(line), Some(("version", _))))
+}
+
+/// Lexically resolves `.` and `..` so the same file reached by two routes compares equal.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(files: &[(&str, &str)], root: &str, defines: &ShaderDefines) -> Result<ShaderSource, ERRUranXError> {
+        ShaderPreprocessor::new("shaders").process_with(root, defines, |path| {
+            files
+                .iter()
+                .find(|(name, _)| Path::new(name) == path)
+                .map(|(_, code)| code.to_string())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.to_string_lossy()))
+        })
+    }
+
+    #[test]
+    fn expands_nested_includes() {
+        let files = [
+            ("shaders/main.frag", "#include \"lighting.glsl\"\nvoid main() {}\n"),
+            ("shaders/lighting.glsl", "#include \"common/math.glsl\"\nvec3 light();\n"),
+            ("shaders/common/math.glsl", "float saturate(float x);\n"),
+        ];
+        let source = process(&files, "shaders/main.frag", &ShaderDefines::new()).unwrap();
+
+        assert_eq!(source.files, ["shaders/main.frag", "shaders/lighting.glsl", "shaders/common/math.glsl"]);
+        assert_eq!(
+            source.code,
+            "#line 1 0\n\
+             #line 1 1\n\
+             #line 1 2\n\
+             float saturate(float x);\n\
+             #line 2 1\n\
+             vec3 light();\n\
+             #line 2 0\n\
+             void main() {}\n"
+        );
+    }
+
+    #[test]
+    fn include_cycles_show_the_chain() {
+        let files = [
+            ("shaders/main.frag", "#include \"a.glsl\"\n"),
+            ("shaders/a.glsl", "#include \"b.glsl\"\n"),
+            ("shaders/b.glsl", "\n#include \"a.glsl\"\n"),
+        ];
+        match process(&files, "shaders/main.frag", &ShaderDefines::new()) {
+            Err(ERRUranXError::SHADER_PREPROCESS_ERROR { path, line, message }) => {
+                assert_eq!(path, "shaders/b.glsl");
+                assert_eq!(line, 2);
+                assert_eq!(message, "include cycle shaders/main.frag -> shaders/a.glsl -> shaders/b.glsl -> shaders/a.glsl");
+            }
+            other => panic!("expected an include cycle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pragma_once_expands_a_file_once() {
+        let files = [
+            ("shaders/main.frag", "#include \"common.glsl\"\n#include \"common.glsl\"\n"),
+            ("shaders/common.glsl", "#pragma once\nconst float PI = 3.14159;\n"),
+        ];
+        let source = process(&files, "shaders/main.frag", &ShaderDefines::new()).unwrap();
+        assert_eq!(source.code.matches("const float PI").count(), 1);
+
+        let files = [
+            ("shaders/main.frag", "#include \"common.glsl\"\n#include \"common.glsl\"\n"),
+            ("shaders/common.glsl", "const float PI = 3.14159;\n"),
+        ];
+        let source = process(&files, "shaders/main.frag", &ShaderDefines::new()).unwrap();
+        assert_eq!(source.code.matches("const float PI").count(), 2);
+    }
+
+    #[test]
+    fn version_is_hoisted_above_defines() {
+        let files = [("shaders/main.frag", "// header\n#version 330 core\nvoid main() {}\n")];
+        let defines = ShaderDefines::new().with("SKINNED").with_value("MAX_LIGHTS", "4");
+        let source = process(&files, "shaders/main.frag", &defines).unwrap();
+
+        assert_eq!(
+            source.code,
+            "#version 330 core\n\
+             #define MAX_LIGHTS 4\n\
+             #define SKINNED 1\n\
+             #line 1 0\n\
+             // header\n\
+             \n\
+             void main() {}\n"
+        );
+    }
+
+    #[test]
+    fn line_numbers_resume_after_an_include() {
+        let files = [
+            ("shaders/main.frag", "#version 330 core\n#include \"a.glsl\"\nint x = oops;\n"),
+            ("shaders/a.glsl", "int a;\nint b;\n"),
+        ];
+        let source = process(&files, "shaders/main.frag", &ShaderDefines::new()).unwrap();
+
+        // GLSL numbers the line after `#line n s` as `n`, so `oops` is line 3 of string 0.
+        let lines: Vec<&str> = source.code.lines().collect();
+        let oops = lines.iter().position(|line| line.contains("oops")).unwrap();
+        assert_eq!(lines[oops - 1], "#line 3 0");
+        let a = lines.iter().position(|line| *line == "int a;").unwrap();
+        assert_eq!(lines[a - 1], "#line 1 1");
+    }
+
+    #[test]
+    fn rejects_version_in_includes_and_bad_includes() {
+        let files = [
+            ("shaders/main.frag", "#include \"a.glsl\"\n"),
+            ("shaders/a.glsl", "#version 330 core\n"),
+        ];
+        assert!(matches!(
+            process(&files, "shaders/main.frag", &ShaderDefines::new()),
+            Err(ERRUranXError::SHADER_PREPROCESS_ERROR { line: 1, .. })
+        ));
+
+        let files = [("shaders/main.frag", "void f();\n#include a.glsl\n")];
+        assert!(matches!(
+            process(&files, "shaders/main.frag", &ShaderDefines::new()),
+            Err(ERRUranXError::SHADER_PREPROCESS_ERROR { line: 2, .. })
+        ));
+
+        let files = [("shaders/main.frag", "#include \"missing.glsl\"\n")];
+        assert!(matches!(
+            process(&files, "shaders/main.frag", &ShaderDefines::new()),
+            Err(ERRUranXError::IO_ERROR { .. })
+        ));
+    }
+
+    #[test]
+    fn equal_define_sets_are_equal_keys() {
+        let a = ShaderDefines::new().with("A").with("B");
+        let b = ShaderDefines::new().with("B").with("A");
+        assert_eq!(a, b);
+        assert_eq!(a.to_string(), "A=1;B=1");
+    }
+}
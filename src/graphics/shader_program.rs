@@ -1,64 +1,240 @@
-use gl;
+use super::shader_preprocessor::{ShaderDefines, ShaderPreprocessor, ShaderSource};
+use crate::utils::errors::ERRUranXError;
+use super::uniform::{Uniform, UniformBlockInfo, UniformInfo};
+use super::uniform_buffer::ENGINE_UNIFORM_BLOCKS;
+use super::vertex_layout::{AttributeInfo, VertexAttribute};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::rc::Rc;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+impl ShaderStage {
+    pub fn gl_enum(self) -> u32 {
+        match self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+        }
+    }
+}
+
+impl std::fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+        }
+    }
+}
+
+/// A linked GL program; the GL object is deleted when the value is dropped.
+#[derive(Debug)]
 pub struct ShaderProgram {
     pub gl_shader_program: u32,
     gl_vertex_shader: u32,
     gl_fragment_shader: u32,
+    vertex_path: String,
+    fragment_path: String,
+    defines: ShaderDefines,
+    preprocessor: ShaderPreprocessor,
+    files: Vec<String>,
+    uniforms: HashMap<String, UniformInfo>,
+    uniform_blocks: HashMap<String, UniformBlockInfo>,
+    attributes: HashMap<String, AttributeInfo>,
+    warned_uniforms: RefCell<HashSet<String>>,
 }
 
 impl ShaderProgram {
-    pub fn new(vertex_shader_path: &str, fradment_shader_path: &str) -> ShaderProgram {
-        let gl_vertex_shader = unsafe { gl::CreateShader(gl::VERTEX_SHADER) };
-        let gl_fragment_shader = unsafe { gl::CreateShader(gl::FRAGMENT_SHADER) };
+    /// Builds a program from two files, resolving includes next to the vertex shader.
+    pub fn new(vertex_shader_path: &str, fragment_shader_path: &str) -> Result<ShaderProgram, ERRUranXError> {
+        Self::with_defines(
+            &ShaderPreprocessor::new(&parent_directory(vertex_shader_path)),
+            vertex_shader_path,
+            fragment_shader_path,
+            &ShaderDefines::new(),
+        )
+    }
+
+    /// Builds the variant of a program selected by `defines`.
+    pub fn with_defines(
+        preprocessor: &ShaderPreprocessor,
+        vertex_shader_path: &str,
+        fragment_shader_path: &str,
+        defines: &ShaderDefines,
+    ) -> Result<ShaderProgram, ERRUranXError> {
+        let vertex = preprocessor.process(vertex_shader_path, defines)?;
+        let fragment = preprocessor.process(fragment_shader_path, defines)?;
+
+        let mut program = Self::from_sources(&vertex, &fragment)?;
+        program.defines = defines.clone();
+        program.preprocessor = preprocessor.clone();
+        Ok(program)
+    }
+
+    /// Builds a program from already preprocessed sources.
+    pub fn from_sources(vertex: &ShaderSource, fragment: &ShaderSource) -> Result<ShaderProgram, ERRUranXError> {
+        let (gl_shader_program, gl_vertex_shader, gl_fragment_shader) = unsafe {
+            (
+                gl::CreateProgram(),
+                gl::CreateShader(ShaderStage::Vertex.gl_enum()),
+                gl::CreateShader(ShaderStage::Fragment.gl_enum()),
+            )
+        };
+
+        let mut files = vertex.files.clone();
+        for file in &fragment.files {
+            if !files.contains(file) {
+                files.push(file.clone());
+            }
+        }
 
         let program = ShaderProgram {
-            gl_shader_program: 0,
+            gl_shader_program,
             gl_vertex_shader,
             gl_fragment_shader,
+            vertex_path: vertex.files[0].clone(),
+            fragment_path: fragment.files[0].clone(),
+            defines: ShaderDefines::new(),
+            preprocessor: ShaderPreprocessor::new(&parent_directory(&vertex.files[0])),
+            files,
+            uniforms: HashMap::new(),
+            uniform_blocks: HashMap::new(),
+            attributes: HashMap::new(),
+            warned_uniforms: RefCell::new(HashSet::new()),
         };
 
-        program.compile_shader(vertex_shader_path, fradment_shader_path);
-        program.create_shader_program();
+        let built = program
+            .compile_shader(vertex, fragment)
+            .and_then(|_| program.create_shader_program());
         program.clear_shader();
 
-        program
+        built?;
+
+        let mut program = program;
+        program.uniforms = program.introspect_uniforms();
+        program.uniform_blocks = program.introspect_uniform_blocks();
+        program.attributes = program.introspect_attributes();
+        for (name, binding, size) in ENGINE_UNIFORM_BLOCKS {
+            if program.uniform_blocks.contains_key(*name) {
+                program.bind_uniform_block(name, *binding, *size);
+            }
+        }
+        Ok(program)
+    }
+
+    pub fn vertex_path(&self) -> &str {
+        &self.vertex_path
+    }
+
+    pub fn fragment_path(&self) -> &str {
+        &self.fragment_path
+    }
+
+    pub fn defines(&self) -> &ShaderDefines {
+        &self.defines
+    }
+
+    /// Every source file the program was built from, includes too.
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// Rebuilds the program from its files. On success the new program replaces this one and
+    /// the old GL program is deleted; on failure this one is left untouched.
+    pub fn reload(&mut self) -> Result<(), ERRUranXError> {
+        let mut program = Self::with_defines(&self.preprocessor, &self.vertex_path, &self.fragment_path, &self.defines)?;
+        std::mem::swap(self, &mut program);
+        Ok(())
+    }
+
+    pub fn compile_shader(&self, vertex: &ShaderSource, fragment: &ShaderSource) -> Result<(), ERRUranXError> {
+        Self::compile_stage(self.gl_vertex_shader, ShaderStage::Vertex, vertex)?;
+        Self::compile_stage(self.gl_fragment_shader, ShaderStage::Fragment, fragment)
     }
 
-    pub fn compile_shader(&self, vertex_shader_path: &str, fragment_shader_path: &str) {
-        let vert_scr =
-            std::fs::read_to_string(vertex_shader_path).expect("Failed to load vertex shader");
-        let frag_scr =
-            std::fs::read_to_string(fragment_shader_path).expect("Failed to load fragment shader");
+    fn compile_stage(gl_shader: u32, stage: ShaderStage, source: &ShaderSource) -> Result<(), ERRUranXError> {
+        let path = &source.files[0];
+        let code = std::ffi::CString::new(source.code.as_str()).map_err(|_| ERRUranXError::SHADER_COMPILE_ERROR {
+            stage,
+            path: path.clone(),
+            log: "the source contains a NUL byte".to_string(),
+        })?;
 
+        let mut status = 0;
         unsafe {
-            let vert_cstr = std::ffi::CString::new(vert_scr).unwrap();
-            let frag_cstr = std::ffi::CString::new(frag_scr).unwrap();
-
-            gl::ShaderSource(
-                self.gl_vertex_shader,
-                1,
-                &vert_cstr.as_ptr(),
-                std::ptr::null(),
-            );
-            gl::CompileShader(self.gl_vertex_shader);
+            gl::ShaderSource(gl_shader, 1, &code.as_ptr(), std::ptr::null());
+            gl::CompileShader(gl_shader);
+            gl::GetShaderiv(gl_shader, gl::COMPILE_STATUS, &mut status);
+        }
 
-            gl::ShaderSource(
-                self.gl_fragment_shader,
-                1,
-                &frag_cstr.as_ptr(),
-                std::ptr::null(),
-            );
-            gl::CompileShader(self.gl_fragment_shader);
+        if status == gl::TRUE as i32 {
+            return Ok(());
         }
+
+        let log = Self::info_log(gl_shader, gl::GetShaderiv, gl::GetShaderInfoLog);
+        let files: Vec<&str> = source.files.iter().map(String::as_str).collect();
+        Err(ERRUranXError::SHADER_COMPILE_ERROR {
+            stage,
+            path: path.clone(),
+            log: map_info_log(&log, &files),
+        })
     }
 
-    pub fn create_shader_program(&self) {
+    /// Links the program. Vertex inputs without a `layout (location = …)` that use a
+    /// [`VertexAttribute::shader_name`] get that attribute's location.
+    pub fn create_shader_program(&self) -> Result<(), ERRUranXError> {
+        for attribute in VertexAttribute::all() {
+            let name = std::ffi::CString::new(attribute.shader_name()).unwrap_or_default();
+            unsafe {
+                gl::BindAttribLocation(self.gl_shader_program, attribute.location(), name.as_ptr());
+            }
+        }
+
+        let mut status = 0;
         unsafe {
             gl::AttachShader(self.gl_shader_program, self.gl_vertex_shader);
             gl::AttachShader(self.gl_shader_program, self.gl_fragment_shader);
             gl::LinkProgram(self.gl_shader_program);
+            gl::GetProgramiv(self.gl_shader_program, gl::LINK_STATUS, &mut status);
+            gl::DetachShader(self.gl_shader_program, self.gl_vertex_shader);
+            gl::DetachShader(self.gl_shader_program, self.gl_fragment_shader);
+        }
+
+        if status == gl::TRUE as i32 {
+            return Ok(());
         }
+
+        Err(ERRUranXError::SHADER_LINK_ERROR {
+            vertex_path: self.vertex_path.clone(),
+            fragment_path: self.fragment_path.clone(),
+            log: Self::info_log(self.gl_shader_program, gl::GetProgramiv, gl::GetProgramInfoLog),
+        })
+    }
+
+    /// Reads the info log of a shader or a program with the matching pair of GL functions.
+    fn info_log(
+        object: u32,
+        get_parameter: unsafe fn(u32, u32, *mut i32),
+        get_info_log: unsafe fn(u32, i32, *mut i32, *mut gl::types::GLchar),
+    ) -> String {
+        let mut length = 0;
+        unsafe {
+            get_parameter(object, gl::INFO_LOG_LENGTH, &mut length);
+        }
+
+        let mut log = vec![0u8; length.max(1) as usize];
+        let mut written = 0;
+        unsafe {
+            get_info_log(object, length.max(1), &mut written, log.as_mut_ptr() as *mut _);
+        }
+        log.truncate(written.max(0) as usize);
+
+        String::from_utf8_lossy(&log).into_owned()
     }
 
     pub fn use_program(&self) {
@@ -74,25 +250,508 @@ impl ShaderProgram {
         }
     }
 
-    pub fn gl_bind_uniform_mat4_fv(&self, name: &str, target: &[f32; 16]) {
+    /// Active uniforms read once after linking. Array uniforms are listed both as `name`
+    /// and as `name[0]`.
+    fn introspect_uniforms(&self) -> HashMap<String, UniformInfo> {
+        let mut count = 0;
+        let mut max_name_length = 0;
         unsafe {
-            let loc = gl::GetUniformLocation(
-                self.gl_shader_program,
-                std::ffi::CString::new(name).unwrap().as_ptr(),
-            );
+            gl::GetProgramiv(self.gl_shader_program, gl::ACTIVE_UNIFORMS, &mut count);
+            gl::GetProgramiv(self.gl_shader_program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_name_length);
+        }
+
+        let mut uniforms = HashMap::new();
+        let mut name = vec![0u8; max_name_length.max(1) as usize];
+        for index in 0..count.max(0) as u32 {
+            let mut length = 0;
+            let mut size = 0;
+            let mut gl_type = 0;
+            unsafe {
+                gl::GetActiveUniform(
+                    self.gl_shader_program,
+                    index,
+                    name.len() as i32,
+                    &mut length,
+                    &mut size,
+                    &mut gl_type,
+                    name.as_mut_ptr() as *mut _,
+                );
+            }
+
+            let uniform_name = String::from_utf8_lossy(&name[..length.max(0) as usize]).into_owned();
+            let c_name = std::ffi::CString::new(uniform_name.as_str()).unwrap_or_default();
+            let location = unsafe { gl::GetUniformLocation(self.gl_shader_program, c_name.as_ptr()) };
+            if location < 0 {
+                // Members of uniform blocks have no location of their own.
+                continue;
+            }
 
-            gl::UniformMatrix4fv(loc, 1, gl::FALSE, target.as_ptr());
+            let info = UniformInfo { location, gl_type, size };
+            if let Some(base_name) = uniform_name.strip_suffix("[0]") {
+                uniforms.insert(base_name.to_string(), info);
+            }
+            uniforms.insert(uniform_name, info);
         }
+
+        uniforms
+    }
+
+    fn introspect_uniform_blocks(&self) -> HashMap<String, UniformBlockInfo> {
+        let mut count = 0;
+        let mut max_name_length = 0;
+        unsafe {
+            gl::GetProgramiv(self.gl_shader_program, gl::ACTIVE_UNIFORM_BLOCKS, &mut count);
+            gl::GetProgramiv(self.gl_shader_program, gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH, &mut max_name_length);
+        }
+
+        let mut blocks = HashMap::new();
+        let mut name = vec![0u8; max_name_length.max(1) as usize];
+        for index in 0..count.max(0) as u32 {
+            let mut length = 0;
+            let mut size = 0;
+            unsafe {
+                gl::GetActiveUniformBlockName(
+                    self.gl_shader_program,
+                    index,
+                    name.len() as i32,
+                    &mut length,
+                    name.as_mut_ptr() as *mut _,
+                );
+                gl::GetActiveUniformBlockiv(self.gl_shader_program, index, gl::UNIFORM_BLOCK_DATA_SIZE, &mut size);
+            }
+
+            let block_name = String::from_utf8_lossy(&name[..length.max(0) as usize]).into_owned();
+            blocks.insert(block_name, UniformBlockInfo { index, size: size.max(0) as usize });
+        }
+
+        blocks
     }
 
-    pub fn set_int(shader_program: u32, name: &str, value: f32) {
+    /// Active vertex inputs, without built-ins like `gl_VertexID`.
+    fn introspect_attributes(&self) -> HashMap<String, AttributeInfo> {
+        let mut count = 0;
+        let mut max_name_length = 0;
         unsafe {
-            let loc = gl::GetUniformLocation(
-                shader_program,
-                std::ffi::CString::new(name).unwrap().as_ptr(),
+            gl::GetProgramiv(self.gl_shader_program, gl::ACTIVE_ATTRIBUTES, &mut count);
+            gl::GetProgramiv(self.gl_shader_program, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH, &mut max_name_length);
+        }
+
+        let mut attributes = HashMap::new();
+        let mut name = vec![0u8; max_name_length.max(1) as usize];
+        for index in 0..count.max(0) as u32 {
+            let mut length = 0;
+            let mut size = 0;
+            let mut gl_type = 0;
+            unsafe {
+                gl::GetActiveAttrib(
+                    self.gl_shader_program,
+                    index,
+                    name.len() as i32,
+                    &mut length,
+                    &mut size,
+                    &mut gl_type,
+                    name.as_mut_ptr() as *mut _,
+                );
+            }
+
+            let attribute_name = String::from_utf8_lossy(&name[..length.max(0) as usize]).into_owned();
+            let c_name = std::ffi::CString::new(attribute_name.as_str()).unwrap_or_default();
+            let location = unsafe { gl::GetAttribLocation(self.gl_shader_program, c_name.as_ptr()) };
+            if location < 0 {
+                continue;
+            }
+
+            attributes.insert(attribute_name, AttributeInfo { location, gl_type, size });
+        }
+
+        attributes
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&AttributeInfo> {
+        self.attributes.get(name)
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, &AttributeInfo)> {
+        self.attributes.iter().map(|(name, info)| (name.as_str(), info))
+    }
+
+    pub fn uniform_block(&self, name: &str) -> Option<&UniformBlockInfo> {
+        self.uniform_blocks.get(name)
+    }
+
+    /// Attaches the uniform block `name` to `binding`, where a
+    /// [`UniformBuffer`](super::uniform_buffer::UniformBuffer) of `size` bytes is bound.
+    ///
+    /// The buffer may be larger than the block, e.g. when the shader only declares the first
+    /// members of the struct. A block the program doesn't use, or one larger than `size`
+    /// (usually a GLSL declaration out of sync with the Rust struct), is skipped with a
+    /// warning. Returns whether the block was bound.
+    pub fn bind_uniform_block(&self, name: &str, binding: u32, size: usize) -> bool {
+        let Some(block) = self.uniform_blocks.get(name) else {
+            self.warn_once(name, || format!("[WARN] Shader `{}` has no active uniform block `{}`", self.fragment_path, name));
+            return false;
+        };
+
+        if block.size > size {
+            self.warn_once(name, || {
+                format!(
+                    "[WARN] Uniform block `{}` of shader `{}` is {} bytes, expected at most {}",
+                    name, self.fragment_path, block.size, size
+                )
+            });
+            return false;
+        }
+
+        unsafe {
+            gl::UniformBlockBinding(self.gl_shader_program, block.index, binding);
+        }
+        true
+    }
+
+    pub fn uniform(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms.get(name)
+    }
+
+    pub fn uniforms(&self) -> impl Iterator<Item = (&str, &UniformInfo)> {
+        self.uniforms.iter().map(|(name, info)| (name.as_str(), info))
+    }
+
+    /// Writes `value` to the uniform `name` of this program, which must be in use.
+    ///
+    /// Unknown names (including uniforms the driver optimised away) and values whose type
+    /// does not match the declaration are skipped with a warning, printed once per name.
+    /// Returns whether the value was written.
+    pub fn set_uniform<T: Uniform + ?Sized>(&self, name: &str, value: &T) -> bool {
+        let Some(info) = self.uniforms.get(name) else {
+            self.warn_once(name, || format!("[WARN] Shader `{}` has no active uniform `{}`", self.fragment_path, name));
+            return false;
+        };
+
+        if !T::accepts(info.gl_type) {
+            self.warn_once(name, || {
+                format!(
+                    "[WARN] Uniform `{}` of shader `{}` is GL type 0x{:04X} and can't be set from `{}`",
+                    name,
+                    self.fragment_path,
+                    info.gl_type,
+                    T::type_name()
+                )
+            });
+            return false;
+        }
+
+        if value.element_count() > info.size as usize {
+            self.warn_once(name, || {
+                format!(
+                    "[WARN] Uniform `{}` of shader `{}` holds {} elements, got {}",
+                    name,
+                    self.fragment_path,
+                    info.size,
+                    value.element_count()
+                )
+            });
+            return false;
+        }
+
+        value.upload(info.location);
+        true
+    }
+
+    fn warn_once(&self, name: &str, message: impl FnOnce() -> String) {
+        if self.warned_uniforms.borrow_mut().insert(name.to_string()) {
+            eprintln!("{}", message());
+        }
+    }
+
+    pub fn set_int(&self, name: &str, value: i32) -> bool {
+        self.set_uniform(name, &value)
+    }
+
+    pub fn set_float(&self, name: &str, value: f32) -> bool {
+        self.set_uniform(name, &value)
+    }
+}
+
+impl Drop for ShaderProgram {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteProgram(self.gl_shader_program) };
+    }
+}
+
+fn parent_directory(path: &str) -> String {
+    Path::new(path)
+        .parent()
+        .map(|parent| parent.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Compiled variants of shader programs, keyed by their files and defines.
+#[derive(Debug)]
+pub struct ShaderVariants {
+    preprocessor: ShaderPreprocessor,
+    programs: HashMap<(String, String, ShaderDefines), Rc<ShaderProgram>>,
+}
+
+impl ShaderVariants {
+    pub fn new(preprocessor: ShaderPreprocessor) -> Self {
+        Self {
+            preprocessor,
+            programs: HashMap::new(),
+        }
+    }
+
+    pub fn preprocessor(&self) -> &ShaderPreprocessor {
+        &self.preprocessor
+    }
+
+    /// Returns the variant for `defines`, compiling it on first use.
+    pub fn get(
+        &mut self,
+        vertex_shader_path: &str,
+        fragment_shader_path: &str,
+        defines: &ShaderDefines,
+    ) -> Result<Rc<ShaderProgram>, ERRUranXError> {
+        let key = (vertex_shader_path.to_string(), fragment_shader_path.to_string(), defines.clone());
+        if let Some(program) = self.programs.get(&key) {
+            return Ok(program.clone());
+        }
+
+        let program = Rc::new(ShaderProgram::with_defines(
+            &self.preprocessor,
+            vertex_shader_path,
+            fragment_shader_path,
+            defines,
+        )?);
+        self.programs.insert(key, program.clone());
+        Ok(program)
+    }
+
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+
+    /// Every source file of every cached variant.
+    pub fn files(&self) -> Vec<String> {
+        let mut files: Vec<String> = self
+            .programs
+            .values()
+            .flat_map(|program| program.files().iter().cloned())
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Rebuilds the variants that use any of `changed_files`. Variants that fail keep their
+    /// previous program; the errors are returned. Programs handed out before keep running
+    /// the old code, so callers should fetch variants again after a reload.
+    pub fn reload(&mut self, changed_files: &[String]) -> Vec<ERRUranXError> {
+        let mut errors = Vec::new();
+        for program in self.programs.values_mut() {
+            if !program.files().iter().any(|file| changed_files.contains(file)) {
+                continue;
+            }
+
+            let reloaded = ShaderProgram::with_defines(
+                &program.preprocessor,
+                &program.vertex_path,
+                &program.fragment_path,
+                &program.defines,
             );
+            match reloaded {
+                Ok(reloaded) => *program = Rc::new(reloaded),
+                Err(error) => errors.push(error),
+            }
+        }
+        errors
+    }
+
+    /// Drops every cached variant; programs still referenced elsewhere stay alive.
+    pub fn clear(&mut self) {
+        self.programs.clear();
+    }
+}
+
+/// Rewrites the `source:line` locations of a driver info log to `path:line`.
+///
+/// Drivers name the source string by index rather than by file, in one of a few formats:
+/// `0:12(5): error: ...` (Mesa), `0(12) : error C0000: ...` (NVIDIA) or
+/// `ERROR: 0:12: ...` (AMD, Intel, Apple). `paths[index]` replaces the index; lines whose
+/// index is out of range, or which carry no location, are kept as they are.
+pub fn map_info_log(log: &str, paths: &[&str]) -> String {
+    log.lines()
+        .map(|line| map_info_log_line(line, paths).unwrap_or_else(|| line.to_string()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn map_info_log_line(line: &str, paths: &[&str]) -> Option<String> {
+    let (prefix, rest) = ["ERROR: ", "WARNING: "]
+        .iter()
+        .find_map(|prefix| Some((*prefix, line.strip_prefix(prefix)?)))
+        .unwrap_or(("", line));
 
-            gl::Uniform1f(loc, value)
+    let (index, rest) = split_number(rest)?;
+    let path = paths.get(index)?;
+
+    let (line_number, column, rest) = if let Some(rest) = rest.strip_prefix(':') {
+        let (line_number, rest) = split_number(rest)?;
+        match rest.strip_prefix('(').and_then(split_number) {
+            Some((column, rest)) => (line_number, Some(column), rest.strip_prefix(')')?),
+            None => (line_number, None, rest),
         }
+    } else {
+        let (line_number, rest) = split_number(rest.strip_prefix('(')?)?;
+        (line_number, None, rest.strip_prefix(')')?)
+    };
+
+    let rest = rest.trim_start_matches([' ', ':']);
+    Some(match column {
+        Some(column) => format!("{}{}:{}:{}: {}", prefix, path, line_number, column, rest),
+        None => format!("{}{}:{}: {}", prefix, path, line_number, rest),
+    })
+}
+
+fn split_number(text: &str) -> Option<(usize, &str)> {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    Some((text[..digits].parse().ok()?, &text[digits..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATHS: [&str; 2] = ["res/shaders/shd.frag", "res/shaders/lighting.glsl"];
+
+    #[test]
+    fn maps_mesa_locations() {
+        let log = "0:12(3): error: `colour' undeclared\n1:4(10): warning: unused variable";
+        assert_eq!(
+            map_info_log(log, &PATHS),
+            "res/shaders/shd.frag:12:3: error: `colour' undeclared\nres/shaders/lighting.glsl:4:10: warning: unused variable"
+        );
+    }
+
+    #[test]
+    fn maps_nvidia_locations() {
+        let log = "0(12) : error C1008: undefined variable \"colour\"";
+        assert_eq!(map_info_log(log, &PATHS), "res/shaders/shd.frag:12: error C1008: undefined variable \"colour\"");
+    }
+
+    #[test]
+    fn maps_amd_locations() {
+        let log = "ERROR: 0:12: 'colour' : undeclared identifier\nWARNING: 1:7: extension not supported\nERROR: 2 compilation errors.  No code generated.";
+        assert_eq!(
+            map_info_log(log, &PATHS),
+            "ERROR: res/shaders/shd.frag:12: 'colour' : undeclared identifier\n\
+             WARNING: res/shaders/lighting.glsl:7: extension not supported\n\
+             ERROR: 2 compilation errors.  No code generated."
+        );
+    }
+
+    #[test]
+    fn keeps_lines_it_cannot_map() {
+        let log = "5:1(1): error: unknown source\nlink failed\n";
+        assert_eq!(map_info_log(log, &PATHS), "5:1(1): error: unknown source\nlink failed");
+    }
+
+    /// A broken include reported by a Mesa-style driver against the preprocessed source
+    /// points at the included file and its own line.
+    #[test]
+    fn maps_errors_in_included_files() {
+        let files = [
+            ("shaders/broken.frag", "#version 330 core\n#include \"lighting.glsl\"\nvoid main() {}\n"),
+            ("shaders/lighting.glsl", "vec3 light() {\n    return colour;\n}\n"),
+        ];
+        let source = ShaderPreprocessor::new("shaders")
+            .process_with("shaders/broken.frag", &ShaderDefines::new(), |path| {
+                let (_, code) = files.iter().find(|(name, _)| Path::new(name) == path).unwrap();
+                Ok(code.to_string())
+            })
+            .unwrap();
+
+        // The driver numbers lines from the `#line` directive, so `colour` is line 2 of string 1.
+        assert!(source.code.contains("#line 1 1\nvec3 light() {\n    return colour;"));
+        let log = "1:2(12): error: `colour' undeclared";
+        let files: Vec<&str> = source.files.iter().map(String::as_str).collect();
+        assert_eq!(map_info_log(log, &files), "shaders/lighting.glsl:2:12: error: `colour' undeclared");
+    }
+
+    const VERTEX: &str = "#version 330 core\nlayout (location = 0) in vec3 aPos;\nvoid main() {\n    gl_Position = vec4(aPos, 1.0);\n}\n";
+
+    /// Preprocesses in-memory `files`, the first of which is the one compiled.
+    fn source(files: &[(&str, &str)]) -> ShaderSource {
+        ShaderPreprocessor::new("shaders")
+            .process_with(files[0].0, &ShaderDefines::new(), |path| {
+                let (_, code) = files.iter().find(|(name, _)| Path::new(name) == path).unwrap();
+                Ok(code.to_string())
+            })
+            .unwrap()
+    }
+
+    /// Runs `test` with a current GL 3.3 core context on a hidden window. Contexts are made one
+    /// at a time, since GLFW must not be used from several threads at once.
+    fn with_gl_context(test: impl FnOnce()) {
+        static GLFW_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let _guard = GLFW_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        use glfw::Context;
+        let mut glfw = glfw::init(glfw::log_errors).expect("GLFW can't be initialised");
+        glfw.window_hint(glfw::WindowHint::Visible(false));
+        glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
+        glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+        glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+        let (mut window, _events) = glfw
+            .create_window(64, 64, "UranX test", glfw::WindowMode::Windowed)
+            .expect("no GL 3.3 context");
+        window.make_current();
+        gl::load_with(|name| window.get_proc_address(name) as *const _);
+
+        test();
+    }
+
+    #[test]
+    #[ignore = "needs a display and a GL 3.3 driver"]
+    fn reports_compile_errors_with_stage_path_and_line() {
+        with_gl_context(|| {
+            let vertex = source(&[("shaders/plain.vert", VERTEX)]);
+            let fragment = source(&[
+                ("shaders/broken.frag", "#version 330 core\n#include \"lighting.glsl\"\nout vec4 fragColor;\nvoid main() {\n    fragColor = vec4(light(), 1.0);\n}\n"),
+                ("shaders/lighting.glsl", "vec3 light() {\n    return colour;\n}\n"),
+            ]);
+
+            match ShaderProgram::from_sources(&vertex, &fragment) {
+                Err(ERRUranXError::SHADER_COMPILE_ERROR { stage, path, log }) => {
+                    assert_eq!(stage, ShaderStage::Fragment);
+                    assert_eq!(path, "shaders/broken.frag");
+                    // `colour` is on line 2 of the include, whatever format the driver logs in.
+                    assert!(log.contains("shaders/lighting.glsl:2"), "unmapped log:\n{log}");
+                }
+                other => panic!("expected a compile error, got {:?}", other.map(|program| program.gl_shader_program)),
+            }
+        });
+    }
+
+    #[test]
+    #[ignore = "needs a display and a GL 3.3 driver"]
+    fn reports_link_errors_with_both_paths() {
+        with_gl_context(|| {
+            let vertex = source(&[("shaders/plain.vert", VERTEX)]);
+            // Compiles on its own, but a program needs a `main` in every stage.
+            let fragment = source(&[("shaders/no_main.frag", "#version 330 core\nout vec4 fragColor;\nvoid shade() {\n    fragColor = vec4(1.0);\n}\n")]);
+
+            match ShaderProgram::from_sources(&vertex, &fragment) {
+                Err(ERRUranXError::SHADER_LINK_ERROR { vertex_path, fragment_path, log }) => {
+                    assert_eq!(vertex_path, "shaders/plain.vert");
+                    assert_eq!(fragment_path, "shaders/no_main.frag");
+                    assert!(!log.trim().is_empty());
+                }
+                other => panic!("expected a link error, got {:?}", other.map(|program| program.gl_shader_program)),
+            }
+        });
     }
 }
@@ -0,0 +1,304 @@
+use crate::math::{Mat3, Mat4, Vec2, Vec3, Vec4};
+
+/// A value with a std140 memory layout, the layout of `layout(std140) uniform` blocks.
+///
+/// Scalars are 4-byte aligned, `vec2` 8, `vec3` and `vec4` 16. Array elements and matrix
+/// columns are padded to 16 bytes, and structs are aligned to 16 with their size rounded up
+/// to that. Structs get their impl from [`std140_struct!`](crate::std140_struct).
+pub trait Std140 {
+    const ALIGNMENT: usize;
+    const SIZE: usize;
+
+    /// Writes the value into `buffer`, which is at least [`Std140::SIZE`] bytes long.
+    /// Padding bytes are left untouched.
+    fn write_std140(&self, buffer: &mut [u8]);
+
+    /// Named members with their offsets, for structs; empty for everything else.
+    fn std140_fields() -> Vec<Std140Field> {
+        Vec::new()
+    }
+
+    fn to_std140_bytes(&self) -> Vec<u8>
+    where
+        Self: Sized,
+    {
+        let mut buffer = vec![0; Self::SIZE];
+        self.write_std140(&mut buffer);
+        buffer
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Std140Field {
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+}
+
+pub const fn align_to(offset: usize, alignment: usize) -> usize {
+    offset.div_ceil(alignment) * alignment
+}
+
+fn write_f32s(buffer: &mut [u8], values: &[f32]) {
+    for (chunk, value) in buffer.chunks_exact_mut(4).zip(values) {
+        chunk.copy_from_slice(&value.to_ne_bytes());
+    }
+}
+
+impl Std140 for f32 {
+    const ALIGNMENT: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write_std140(&self, buffer: &mut [u8]) {
+        buffer[..4].copy_from_slice(&self.to_ne_bytes());
+    }
+}
+
+impl Std140 for i32 {
+    const ALIGNMENT: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write_std140(&self, buffer: &mut [u8]) {
+        buffer[..4].copy_from_slice(&self.to_ne_bytes());
+    }
+}
+
+impl Std140 for u32 {
+    const ALIGNMENT: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write_std140(&self, buffer: &mut [u8]) {
+        buffer[..4].copy_from_slice(&self.to_ne_bytes());
+    }
+}
+
+/// GLSL `bool` takes a full 4-byte word.
+impl Std140 for bool {
+    const ALIGNMENT: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write_std140(&self, buffer: &mut [u8]) {
+        (*self as u32).write_std140(buffer);
+    }
+}
+
+impl Std140 for Vec2 {
+    const ALIGNMENT: usize = 8;
+    const SIZE: usize = 8;
+
+    fn write_std140(&self, buffer: &mut [u8]) {
+        write_f32s(buffer, &self.to_array());
+    }
+}
+
+impl Std140 for Vec3 {
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 12;
+
+    fn write_std140(&self, buffer: &mut [u8]) {
+        write_f32s(buffer, &self.to_array());
+    }
+}
+
+impl Std140 for Vec4 {
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 16;
+
+    fn write_std140(&self, buffer: &mut [u8]) {
+        write_f32s(buffer, &self.to_array());
+    }
+}
+
+/// Three `vec3` columns, each padded to 16 bytes.
+impl Std140 for Mat3 {
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 48;
+
+    fn write_std140(&self, buffer: &mut [u8]) {
+        for (index, column) in self.cols.iter().enumerate() {
+            column.write_std140(&mut buffer[index * 16..]);
+        }
+    }
+}
+
+impl Std140 for Mat4 {
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 64;
+
+    fn write_std140(&self, buffer: &mut [u8]) {
+        write_f32s(buffer, &self.to_cols_array());
+    }
+}
+
+/// Elements are padded to a 16-byte stride, whatever their own size.
+impl<T: Std140, const N: usize> Std140 for [T; N] {
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = align_to(T::SIZE, 16) * N;
+
+    fn write_std140(&self, buffer: &mut [u8]) {
+        let stride = align_to(T::SIZE, 16);
+        for (index, element) in self.iter().enumerate() {
+            element.write_std140(&mut buffer[index * stride..]);
+        }
+    }
+}
+
+/// Declares a struct together with its [`Std140`] layout.
+///
+/// ```ignore
+/// std140_struct! {
+///     #[derive(Debug, Clone, Copy, Default)]
+///     pub struct FrameData {
+///         pub view: Mat4,
+///         pub camera_position: Vec3,
+///         pub time: f32,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! std140_struct {
+    (
+        $(#[$attribute:meta])*
+        $visibility:vis struct $name:ident {
+            $($(#[$field_attribute:meta])* $field_visibility:vis $field:ident: $type:ty),* $(,)?
+        }
+    ) => {
+        $(#[$attribute])*
+        $visibility struct $name {
+            $($(#[$field_attribute])* $field_visibility $field: $type),*
+        }
+
+        impl $crate::graphics::std140::Std140 for $name {
+            // No member is aligned to more than 16, which structs are rounded up to anyway.
+            const ALIGNMENT: usize = 16;
+            const SIZE: usize = {
+                let offset = 0;
+                $(let offset = $crate::graphics::std140::align_to(
+                    offset,
+                    <$type as $crate::graphics::std140::Std140>::ALIGNMENT,
+                ) + <$type as $crate::graphics::std140::Std140>::SIZE;)*
+                $crate::graphics::std140::align_to(offset, Self::ALIGNMENT)
+            };
+
+            fn write_std140(&self, buffer: &mut [u8]) {
+                let offset = 0;
+                $(
+                    let offset = $crate::graphics::std140::align_to(
+                        offset,
+                        <$type as $crate::graphics::std140::Std140>::ALIGNMENT,
+                    );
+                    $crate::graphics::std140::Std140::write_std140(&self.$field, &mut buffer[offset..]);
+                    let offset = offset + <$type as $crate::graphics::std140::Std140>::SIZE;
+                )*
+                let _ = offset;
+            }
+
+            fn std140_fields() -> Vec<$crate::graphics::std140::Std140Field> {
+                let mut fields = Vec::new();
+                let offset = 0;
+                $(
+                    let offset = $crate::graphics::std140::align_to(
+                        offset,
+                        <$type as $crate::graphics::std140::Std140>::ALIGNMENT,
+                    );
+                    fields.push($crate::graphics::std140::Std140Field {
+                        name: stringify!($field),
+                        offset,
+                        size: <$type as $crate::graphics::std140::Std140>::SIZE,
+                    });
+                    let offset = offset + <$type as $crate::graphics::std140::Std140>::SIZE;
+                )*
+                let _ = offset;
+                fields
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::uniform_buffer::{FrameData, ObjectData};
+
+    fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+        f32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn frame_data_matches_the_glsl_block() {
+        assert_eq!(FrameData::SIZE, 176);
+
+        let offsets: Vec<(&str, usize)> = FrameData::std140_fields()
+            .iter()
+            .map(|field| (field.name, field.offset))
+            .collect();
+        assert_eq!(
+            offsets,
+            [
+                ("view", 0),
+                ("projection", 64),
+                ("camera_position", 128),
+                // Packs into the last word of the vec3 before it.
+                ("time", 140),
+                ("light_position", 144),
+                ("light_color", 160),
+            ]
+        );
+    }
+
+    #[test]
+    fn frame_data_writes_at_its_offsets() {
+        let frame = FrameData {
+            camera_position: Vec3::new(1.0, 2.0, 3.0),
+            time: 4.0,
+            light_color: Vec3::new(5.0, 6.0, 7.0),
+            ..FrameData::default()
+        };
+        let bytes = frame.to_std140_bytes();
+
+        assert_eq!(bytes.len(), 176);
+        assert_eq!(f32_at(&bytes, 128), 1.0);
+        assert_eq!(f32_at(&bytes, 136), 3.0);
+        assert_eq!(f32_at(&bytes, 140), 4.0);
+        assert_eq!(f32_at(&bytes, 168), 7.0);
+    }
+
+    #[test]
+    fn mat3_columns_have_a_16_byte_stride() {
+        let matrix = Mat3::from_cols(Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0), Vec3::new(7.0, 8.0, 9.0));
+        let bytes = matrix.to_std140_bytes();
+
+        assert_eq!(bytes.len(), 48);
+        for column in 0..3 {
+            for row in 0..3 {
+                assert_eq!(f32_at(&bytes, column * 16 + row * 4), (column * 3 + row + 1) as f32);
+            }
+            assert_eq!(f32_at(&bytes, column * 16 + 12), 0.0, "padding of column {column}");
+        }
+
+        let fields = ObjectData::std140_fields();
+        assert_eq!(fields[1].offset, 64);
+        assert_eq!(ObjectData::SIZE, 112);
+    }
+
+    #[test]
+    fn array_elements_have_a_16_byte_stride() {
+        assert_eq!(<[f32; 3]>::SIZE, 48);
+        assert_eq!(<[Vec2; 2]>::SIZE, 32);
+        assert_eq!(<[Vec4; 2]>::SIZE, 32);
+        assert_eq!(<[Mat3; 2]>::SIZE, 96);
+
+        let bytes = [1.0f32, 2.0, 3.0].to_std140_bytes();
+        assert_eq!(f32_at(&bytes, 0), 1.0);
+        assert_eq!(f32_at(&bytes, 16), 2.0);
+        assert_eq!(f32_at(&bytes, 32), 3.0);
+    }
+
+    #[test]
+    fn align_to_rounds_up() {
+        assert_eq!(align_to(0, 16), 0);
+        assert_eq!(align_to(12, 16), 16);
+        assert_eq!(align_to(16, 16), 16);
+        assert_eq!(align_to(140, 4), 140);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::utils::errors::ERRUranXError;
 use image;
 use std::path::Path;
 
@@ -36,14 +37,31 @@ impl Texture {
         }
     }
 
-    pub fn load(texture_path: &str, texture_type: String) -> Self {
+    /// Loads an image file flipped vertically, for UVs with the origin at the bottom (OBJ).
+    pub fn load(texture_path: &str, texture_type: String) -> Result<Self, ERRUranXError> {
+        let img = Self::decode_file(texture_path)?.flipv();
+        Ok(Texture::from_image(&img, texture_type))
+    }
+
+    pub fn decode_file(texture_path: &str) -> Result<image::DynamicImage, ERRUranXError> {
+        image::open(Path::new(texture_path)).map_err(|source| match source {
+            image::ImageError::IoError(source) => ERRUranXError::io(texture_path, source),
+            source => ERRUranXError::IMAGE_DECODE_ERROR { path: texture_path.to_string(), source },
+        })
+    }
+
+    /// Decodes an encoded image held in memory. `name` is only used in errors.
+    pub fn decode_memory(data: &[u8], name: &str) -> Result<image::DynamicImage, ERRUranXError> {
+        image::load_from_memory(data)
+            .map_err(|source| ERRUranXError::IMAGE_DECODE_ERROR { path: name.to_string(), source })
+    }
+
+    /// Uploads `img` row by row as given, with the first row at `v = 0`, as glTF expects.
+    pub fn from_image(img: &image::DynamicImage, texture_type: String) -> Self {
         let texture = Texture::new(texture_type);
         texture.bind();
 
-        let img = image::open(&Path::new(texture_path)).expect("Failed to load texture");
-        let img = img.flipv();
-
-        let data = img.to_rgb8();
+        let data = img.to_rgba8();
         let width = img.width();
         let height = img.height();
 
@@ -51,11 +69,11 @@ impl Texture {
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
-                gl::RGB as i32,
+                gl::RGBA as i32,
                 width as i32,
                 height as i32,
                 0,
-                gl::RGB,
+                gl::RGBA,
                 gl::UNSIGNED_BYTE,
                 data.as_ptr() as *const _,
             );
@@ -65,4 +83,21 @@ impl Texture {
 
         texture
     }
+
+    /// Overrides the filters and wrap modes set by [`Texture::bind`]; `None` keeps the default.
+    pub fn set_sampling(&self, mag_filter: Option<u32>, min_filter: Option<u32>, wrap_s: Option<u32>, wrap_t: Option<u32>) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.texture_id);
+            for (parameter, value) in [
+                (gl::TEXTURE_MAG_FILTER, mag_filter),
+                (gl::TEXTURE_MIN_FILTER, min_filter),
+                (gl::TEXTURE_WRAP_S, wrap_s),
+                (gl::TEXTURE_WRAP_T, wrap_t),
+            ] {
+                if let Some(value) = value {
+                    gl::TexParameteri(gl::TEXTURE_2D, parameter, value as i32);
+                }
+            }
+        }
+    }
 }
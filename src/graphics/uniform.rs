@@ -0,0 +1,245 @@
+use crate::math::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use gl::types::GLenum;
+
+/// An active uniform of a linked program, as reported by `glGetActiveUniform`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniformInfo {
+    pub location: i32,
+    /// GL type enum, e.g. `gl::FLOAT_VEC3` or `gl::SAMPLER_2D`.
+    pub gl_type: GLenum,
+    /// Number of array elements; `1` for a plain uniform.
+    pub size: i32,
+}
+
+/// An active uniform block of a linked program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniformBlockInfo {
+    pub index: u32,
+    /// `GL_UNIFORM_BLOCK_DATA_SIZE`, in bytes.
+    pub size: usize,
+}
+
+/// A value that can be written to a uniform with
+/// [`ShaderProgram::set_uniform`](super::shader_program::ShaderProgram::set_uniform).
+pub trait Uniform {
+    /// Whether a uniform declared with `gl_type` can take this value.
+    fn accepts(gl_type: GLenum) -> bool;
+
+    /// Name of the Rust type, for mismatch warnings.
+    fn type_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Number of array elements written.
+    fn element_count(&self) -> usize {
+        1
+    }
+
+    /// Uploads the value to `location` of the bound program.
+    fn upload(&self, location: i32);
+}
+
+/// A uniform type that can also be uploaded as an array.
+pub trait UniformElement: Sized {
+    fn accepts(gl_type: GLenum) -> bool;
+
+    fn upload_slice(location: i32, values: &[Self]);
+}
+
+/// Texture unit index for a sampler uniform.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampler(pub i32);
+
+impl<T: UniformElement> Uniform for T {
+    fn accepts(gl_type: GLenum) -> bool {
+        <T as UniformElement>::accepts(gl_type)
+    }
+
+    fn upload(&self, location: i32) {
+        T::upload_slice(location, std::slice::from_ref(self));
+    }
+}
+
+impl<T: UniformElement> Uniform for [T] {
+    fn accepts(gl_type: GLenum) -> bool {
+        <T as UniformElement>::accepts(gl_type)
+    }
+
+    fn element_count(&self) -> usize {
+        self.len()
+    }
+
+    fn upload(&self, location: i32) {
+        T::upload_slice(location, self);
+    }
+}
+
+impl<T: UniformElement> Uniform for Vec<T> {
+    fn accepts(gl_type: GLenum) -> bool {
+        <T as UniformElement>::accepts(gl_type)
+    }
+
+    fn element_count(&self) -> usize {
+        self.len()
+    }
+
+    fn upload(&self, location: i32) {
+        T::upload_slice(location, self);
+    }
+}
+
+pub fn is_sampler(gl_type: GLenum) -> bool {
+    matches!(
+        gl_type,
+        gl::SAMPLER_1D
+            | gl::SAMPLER_2D
+            | gl::SAMPLER_3D
+            | gl::SAMPLER_CUBE
+            | gl::SAMPLER_1D_SHADOW
+            | gl::SAMPLER_2D_SHADOW
+            | gl::SAMPLER_1D_ARRAY
+            | gl::SAMPLER_2D_ARRAY
+            | gl::SAMPLER_2D_ARRAY_SHADOW
+            | gl::SAMPLER_CUBE_SHADOW
+            | gl::SAMPLER_2D_MULTISAMPLE
+            | gl::SAMPLER_2D_RECT
+            | gl::SAMPLER_BUFFER
+            | gl::INT_SAMPLER_2D
+            | gl::INT_SAMPLER_3D
+            | gl::INT_SAMPLER_CUBE
+            | gl::INT_SAMPLER_2D_ARRAY
+            | gl::UNSIGNED_INT_SAMPLER_2D
+            | gl::UNSIGNED_INT_SAMPLER_3D
+            | gl::UNSIGNED_INT_SAMPLER_CUBE
+            | gl::UNSIGNED_INT_SAMPLER_2D_ARRAY
+    )
+}
+
+impl UniformElement for f32 {
+    fn accepts(gl_type: GLenum) -> bool {
+        gl_type == gl::FLOAT
+    }
+
+    fn upload_slice(location: i32, values: &[Self]) {
+        unsafe { gl::Uniform1fv(location, values.len() as i32, values.as_ptr()) }
+    }
+}
+
+/// Also accepted by sampler uniforms, as the texture unit.
+impl UniformElement for i32 {
+    fn accepts(gl_type: GLenum) -> bool {
+        gl_type == gl::INT || is_sampler(gl_type)
+    }
+
+    fn upload_slice(location: i32, values: &[Self]) {
+        unsafe { gl::Uniform1iv(location, values.len() as i32, values.as_ptr()) }
+    }
+}
+
+impl UniformElement for u32 {
+    fn accepts(gl_type: GLenum) -> bool {
+        gl_type == gl::UNSIGNED_INT
+    }
+
+    fn upload_slice(location: i32, values: &[Self]) {
+        unsafe { gl::Uniform1uiv(location, values.len() as i32, values.as_ptr()) }
+    }
+}
+
+impl UniformElement for bool {
+    fn accepts(gl_type: GLenum) -> bool {
+        gl_type == gl::BOOL
+    }
+
+    /// GL takes bools as ints, so they are converted through a stack buffer a chunk at a time.
+    /// Array elements have consecutive locations, so each chunk starts at `location + offset`.
+    fn upload_slice(location: i32, values: &[Self]) {
+        const CHUNK: usize = 32;
+        let mut buffer = [0i32; CHUNK];
+        for (chunk_index, chunk) in values.chunks(CHUNK).enumerate() {
+            for (slot, value) in buffer.iter_mut().zip(chunk) {
+                *slot = *value as i32;
+            }
+            let chunk_location = location + (chunk_index * CHUNK) as i32;
+            unsafe { gl::Uniform1iv(chunk_location, chunk.len() as i32, buffer.as_ptr()) }
+        }
+    }
+}
+
+impl UniformElement for Sampler {
+    fn accepts(gl_type: GLenum) -> bool {
+        is_sampler(gl_type)
+    }
+
+    fn upload_slice(location: i32, values: &[Self]) {
+        // `Sampler` is a transparent `i32`.
+        unsafe { gl::Uniform1iv(location, values.len() as i32, values.as_ptr() as *const i32) }
+    }
+}
+
+// The vector and matrix types are `#[repr(C)]` runs of `f32`, so slices of them are passed to GL as is.
+const _: () = {
+    assert!(std::mem::size_of::<Vec2>() == 2 * 4);
+    assert!(std::mem::size_of::<Vec3>() == 3 * 4);
+    assert!(std::mem::size_of::<Vec4>() == 4 * 4);
+    assert!(std::mem::size_of::<Mat3>() == 9 * 4);
+    assert!(std::mem::size_of::<Mat4>() == 16 * 4);
+};
+
+macro_rules! impl_float_vector_uniform {
+    ($type:ty, $gl_type:expr, $function:ident) => {
+        impl UniformElement for $type {
+            fn accepts(gl_type: GLenum) -> bool {
+                gl_type == $gl_type
+            }
+
+            fn upload_slice(location: i32, values: &[Self]) {
+                unsafe { gl::$function(location, values.len() as i32, values.as_ptr() as *const f32) }
+            }
+        }
+    };
+}
+
+impl_float_vector_uniform!(Vec2, gl::FLOAT_VEC2, Uniform2fv);
+impl_float_vector_uniform!(Vec3, gl::FLOAT_VEC3, Uniform3fv);
+impl_float_vector_uniform!(Vec4, gl::FLOAT_VEC4, Uniform4fv);
+
+macro_rules! impl_matrix_uniform {
+    ($type:ty, $gl_type:expr, $function:ident) => {
+        impl UniformElement for $type {
+            fn accepts(gl_type: GLenum) -> bool {
+                gl_type == $gl_type
+            }
+
+            fn upload_slice(location: i32, values: &[Self]) {
+                unsafe { gl::$function(location, values.len() as i32, gl::FALSE, values.as_ptr() as *const f32) }
+            }
+        }
+    };
+}
+
+impl_matrix_uniform!(Mat3, gl::FLOAT_MAT3, UniformMatrix3fv);
+impl_matrix_uniform!(Mat4, gl::FLOAT_MAT4, UniformMatrix4fv);
+
+/// Plain arrays stand for vectors, so `[f32; 3]` is a `vec3`; pass a slice for `float[3]`.
+macro_rules! impl_array_uniform {
+    ($type:ty, $gl_type:expr, $function:ident) => {
+        impl UniformElement for $type {
+            fn accepts(gl_type: GLenum) -> bool {
+                gl_type == $gl_type
+            }
+
+            fn upload_slice(location: i32, values: &[Self]) {
+                unsafe { gl::$function(location, values.len() as i32, values.as_ptr() as *const _) }
+            }
+        }
+    };
+}
+
+impl_array_uniform!([f32; 2], gl::FLOAT_VEC2, Uniform2fv);
+impl_array_uniform!([f32; 3], gl::FLOAT_VEC3, Uniform3fv);
+impl_array_uniform!([f32; 4], gl::FLOAT_VEC4, Uniform4fv);
+impl_array_uniform!([i32; 2], gl::INT_VEC2, Uniform2iv);
+impl_array_uniform!([i32; 3], gl::INT_VEC3, Uniform3iv);
+impl_array_uniform!([i32; 4], gl::INT_VEC4, Uniform4iv);
@@ -0,0 +1,133 @@
+use super::std140::Std140;
+use crate::math::{Mat3, Mat4, Vec3};
+use crate::std140_struct;
+use std::marker::PhantomData;
+
+/// Binding point of the `FrameData` block.
+pub const FRAME_DATA_BINDING: u32 = 0;
+/// Binding point of the `ObjectData` block.
+pub const OBJECT_DATA_BINDING: u32 = 1;
+
+/// Uniform blocks every program is bound to after linking: name, binding point and the
+/// std140 size the engine uploads.
+pub const ENGINE_UNIFORM_BLOCKS: &[(&str, u32, usize)] = &[
+    ("FrameData", FRAME_DATA_BINDING, FrameData::SIZE),
+    ("ObjectData", OBJECT_DATA_BINDING, ObjectData::SIZE),
+];
+
+std140_struct! {
+    /// Per-frame values shared by every program:
+    ///
+    /// ```glsl
+    /// layout (std140) uniform FrameData {
+    ///     mat4 view;
+    ///     mat4 projection;
+    ///     vec3 cameraPosition;
+    ///     float time;
+    ///     vec3 lightPosition;
+    ///     vec3 lightColor;
+    /// };
+    /// ```
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub struct FrameData {
+        pub view: Mat4,
+        pub projection: Mat4,
+        pub camera_position: Vec3,
+        /// Seconds since start, scaled and paused with the game clock.
+        pub time: f32,
+        /// World position of the first light in the scene.
+        pub light_position: Vec3,
+        /// Radiance of that light; black when the scene has none.
+        pub light_color: Vec3,
+    }
+}
+
+std140_struct! {
+    /// Per-object values, updated before each draw:
+    ///
+    /// ```glsl
+    /// layout (std140) uniform ObjectData {
+    ///     mat4 model;
+    ///     mat3 normalMatrix;
+    /// };
+    /// ```
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub struct ObjectData {
+        pub model: Mat4,
+        pub normal_matrix: Mat3,
+    }
+}
+
+impl ObjectData {
+    pub fn new(model: Mat4) -> Self {
+        let normal_matrix = Mat3::from_mat4(model)
+            .inverse()
+            .map(Mat3::transpose)
+            .unwrap_or(Mat3::IDENTITY);
+
+        Self { model, normal_matrix }
+    }
+}
+
+/// A GL uniform buffer holding one `T` in std140 layout, attached to a fixed binding point.
+///
+/// The GL buffer is deleted when the value is dropped.
+#[derive(Debug)]
+pub struct UniformBuffer<T: Std140> {
+    pub gl_buffer: u32,
+    binding: u32,
+    data: Vec<u8>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Std140> UniformBuffer<T> {
+    pub fn new(binding: u32) -> Self {
+        let data = vec![0; T::SIZE];
+        let mut gl_buffer = 0;
+
+        unsafe {
+            gl::GenBuffers(1, &mut gl_buffer);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, gl_buffer);
+            gl::BufferData(gl::UNIFORM_BUFFER, T::SIZE as isize, data.as_ptr() as *const _, gl::DYNAMIC_DRAW);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, binding, gl_buffer);
+        }
+
+        Self {
+            gl_buffer,
+            binding,
+            data,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    /// Uploads `value`; every program whose block uses this binding sees it from the next draw.
+    pub fn update(&mut self, value: &T) {
+        value.write_std140(&mut self.data);
+
+        unsafe {
+            gl::BindBuffer(gl::UNIFORM_BUFFER, self.gl_buffer);
+            gl::BufferSubData(gl::UNIFORM_BUFFER, 0, self.data.len() as isize, self.data.as_ptr() as *const _);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+        }
+    }
+
+    /// Re-attaches the buffer to its binding point, e.g. after something else used it.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, self.binding, self.gl_buffer);
+        }
+    }
+}
+
+impl<T: Std140> Drop for UniformBuffer<T> {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.gl_buffer);
+        }
+    }
+}
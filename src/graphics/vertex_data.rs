@@ -0,0 +1,193 @@
+use super::mesh::Vertex;
+use super::vertex_layout::{VertexAttribute, VertexLayout};
+use std::ops::Range;
+
+/// Vertices on the CPU, one array per attribute.
+///
+/// An empty array means the mesh doesn't have that attribute; a present one has an entry per
+/// position. Keeping attributes apart makes it cheap to add one, e.g. generated tangents,
+/// before the data is interleaved for upload.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VertexData {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub tangents: Vec<[f32; 4]>,
+    pub bitangents: Vec<[f32; 3]>,
+    pub colors: Vec<[f32; 4]>,
+    /// One array per UV set.
+    pub texture_coords: Vec<Vec<[f32; 2]>>,
+    pub joints: Vec<[u16; 4]>,
+    pub weights: Vec<[f32; 4]>,
+}
+
+/// Used for vertices a present attribute has no value for. Colors default to white so they
+/// can always be multiplied in.
+const DEFAULT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+impl VertexData {
+    pub fn from_vertices(vertices: &[Vertex]) -> Self {
+        Self {
+            positions: vertices.iter().map(|vertex| vertex.position).collect(),
+            normals: vertices.iter().map(|vertex| vertex.normal).collect(),
+            texture_coords: vec![vertices.iter().map(|vertex| vertex.texture_coords).collect()],
+            ..Self::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// The attributes present, in location order.
+    pub fn layout(&self) -> VertexLayout {
+        VertexLayout::new(VertexAttribute::all().filter(|attribute| match attribute {
+            VertexAttribute::Position => true,
+            VertexAttribute::Normal => !self.normals.is_empty(),
+            VertexAttribute::Tangent => !self.tangents.is_empty(),
+            VertexAttribute::Bitangent => !self.bitangents.is_empty(),
+            VertexAttribute::Color => !self.colors.is_empty(),
+            VertexAttribute::TextureCoords(set) => self.texture_coords.get(*set as usize).is_some_and(|set| !set.is_empty()),
+            VertexAttribute::Joints => !self.joints.is_empty(),
+            VertexAttribute::Weights => !self.weights.is_empty(),
+        }))
+    }
+
+    /// UV set `set`, or an empty slice.
+    pub fn texture_coords(&self, set: usize) -> &[[f32; 2]] {
+        self.texture_coords.get(set).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// The vertices laid out as `layout` says, little-endian. Attributes the data doesn't have
+    /// are zero, or white for colors.
+    pub fn interleave(&self, layout: &VertexLayout) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len() * layout.stride());
+        for index in 0..self.len() {
+            for layout_attribute in layout.attributes() {
+                self.write_attribute(layout_attribute.attribute, index, &mut bytes);
+            }
+        }
+        bytes
+    }
+
+    fn write_attribute(&self, attribute: VertexAttribute, index: usize, bytes: &mut Vec<u8>) {
+        fn floats<const N: usize>(bytes: &mut Vec<u8>, values: Option<&[f32; N]>, default: [f32; N]) {
+            for value in values.copied().unwrap_or(default) {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        match attribute {
+            VertexAttribute::Position => floats(bytes, self.positions.get(index), [0.0; 3]),
+            VertexAttribute::Normal => floats(bytes, self.normals.get(index), [0.0; 3]),
+            VertexAttribute::Tangent => floats(bytes, self.tangents.get(index), [0.0; 4]),
+            VertexAttribute::Bitangent => floats(bytes, self.bitangents.get(index), [0.0; 3]),
+            VertexAttribute::Color => floats(bytes, self.colors.get(index), DEFAULT_COLOR),
+            VertexAttribute::TextureCoords(set) => floats(bytes, self.texture_coords(set as usize).get(index), [0.0; 2]),
+            VertexAttribute::Joints => {
+                for value in self.joints.get(index).copied().unwrap_or([0; 4]) {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            VertexAttribute::Weights => floats(bytes, self.weights.get(index), [0.0; 4]),
+        }
+    }
+
+    /// Reads vertices interleaved as `layout` says, the inverse of [`VertexData::interleave`].
+    /// A trailing partial vertex is ignored.
+    pub fn from_interleaved(layout: &VertexLayout, bytes: &[u8]) -> Self {
+        fn floats<const N: usize>(bytes: &[u8]) -> [f32; N] {
+            std::array::from_fn(|i| f32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap()))
+        }
+
+        let mut data = Self::default();
+        if layout.stride() == 0 {
+            return data;
+        }
+
+        for vertex in bytes.chunks_exact(layout.stride()) {
+            for layout_attribute in layout.attributes() {
+                let bytes = &vertex[layout_attribute.offset..];
+                match layout_attribute.attribute {
+                    VertexAttribute::Position => data.positions.push(floats(bytes)),
+                    VertexAttribute::Normal => data.normals.push(floats(bytes)),
+                    VertexAttribute::Tangent => data.tangents.push(floats(bytes)),
+                    VertexAttribute::Bitangent => data.bitangents.push(floats(bytes)),
+                    VertexAttribute::Color => data.colors.push(floats(bytes)),
+                    VertexAttribute::TextureCoords(set) => {
+                        let set = set as usize;
+                        if data.texture_coords.len() <= set {
+                            data.texture_coords.resize(set + 1, Vec::new());
+                        }
+                        data.texture_coords[set].push(floats(bytes));
+                    }
+                    VertexAttribute::Joints => {
+                        data.joints.push(std::array::from_fn(|i| u16::from_le_bytes([bytes[i * 2], bytes[i * 2 + 1]])))
+                    }
+                    VertexAttribute::Weights => data.weights.push(floats(bytes)),
+                }
+            }
+        }
+
+        data
+    }
+
+    /// Appends `other`. An attribute only one side has is filled with defaults on the other.
+    pub fn append(&mut self, other: &VertexData) {
+        let (length, other_length) = (self.len(), other.len());
+        append_stream(&mut self.normals, length, &other.normals, other_length, [0.0; 3]);
+        append_stream(&mut self.tangents, length, &other.tangents, other_length, [0.0; 4]);
+        append_stream(&mut self.bitangents, length, &other.bitangents, other_length, [0.0; 3]);
+        append_stream(&mut self.colors, length, &other.colors, other_length, DEFAULT_COLOR);
+        append_stream(&mut self.joints, length, &other.joints, other_length, [0; 4]);
+        append_stream(&mut self.weights, length, &other.weights, other_length, [0.0; 4]);
+
+        if self.texture_coords.len() < other.texture_coords.len() {
+            self.texture_coords.resize(other.texture_coords.len(), Vec::new());
+        }
+        for (set, texture_coords) in self.texture_coords.iter_mut().enumerate() {
+            append_stream(texture_coords, length, other.texture_coords(set), other_length, [0.0; 2]);
+        }
+
+        self.positions.extend_from_slice(&other.positions);
+    }
+
+    /// The vertices at `indices`, in that order.
+    pub fn gather(&self, indices: &[u32]) -> VertexData {
+        fn pick<T: Copy>(stream: &[T], indices: &[u32]) -> Vec<T> {
+            if stream.is_empty() {
+                return Vec::new();
+            }
+            indices.iter().map(|index| stream[*index as usize]).collect()
+        }
+
+        VertexData {
+            positions: pick(&self.positions, indices),
+            normals: pick(&self.normals, indices),
+            tangents: pick(&self.tangents, indices),
+            bitangents: pick(&self.bitangents, indices),
+            colors: pick(&self.colors, indices),
+            texture_coords: self.texture_coords.iter().map(|set| pick(set, indices)).collect(),
+            joints: pick(&self.joints, indices),
+            weights: pick(&self.weights, indices),
+        }
+    }
+
+    /// The vertices in `range`.
+    pub fn slice(&self, range: Range<usize>) -> VertexData {
+        let indices: Vec<u32> = (range.start as u32..range.end as u32).collect();
+        self.gather(&indices)
+    }
+}
+
+fn append_stream<T: Copy>(stream: &mut Vec<T>, length: usize, other: &[T], other_length: usize, default: T) {
+    if stream.is_empty() && other.is_empty() {
+        return;
+    }
+    stream.resize(length, default);
+    stream.extend_from_slice(other);
+    stream.resize(length + other_length, default);
+}
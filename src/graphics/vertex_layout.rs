@@ -0,0 +1,297 @@
+use super::shader_program::ShaderProgram;
+use gl::types::GLenum;
+use std::fmt::{Display, Formatter};
+
+/// UV sets a layout can hold, `TextureCoords(0)` to `TextureCoords(3)`.
+pub const MAX_TEXTURE_COORD_SETS: u8 = 4;
+
+/// A per-vertex input. Each one has a fixed attribute location, so any mesh can be drawn with
+/// any program that declares the inputs it needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum VertexAttribute {
+    Position,
+    Normal,
+    /// `xyz` is the tangent, `w` the handedness (`±1`) of the bitangent, as in glTF.
+    Tangent,
+    Bitangent,
+    Color,
+    /// UV set `n`, below [`MAX_TEXTURE_COORD_SETS`].
+    TextureCoords(u8),
+    /// Four joint indices for skinning, read as integers.
+    Joints,
+    Weights,
+}
+
+/// How the components of an attribute are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentType {
+    Float,
+    /// Unsigned 16-bit integers, passed to the shader unconverted.
+    UnsignedShort,
+}
+
+impl ComponentType {
+    pub fn size(self) -> usize {
+        match self {
+            ComponentType::Float => 4,
+            ComponentType::UnsignedShort => 2,
+        }
+    }
+
+    pub fn gl_type(self) -> GLenum {
+        match self {
+            ComponentType::Float => gl::FLOAT,
+            ComponentType::UnsignedShort => gl::UNSIGNED_SHORT,
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        self == ComponentType::UnsignedShort
+    }
+}
+
+impl VertexAttribute {
+    /// Every attribute, in location order.
+    pub fn all() -> impl Iterator<Item = VertexAttribute> {
+        [VertexAttribute::Position, VertexAttribute::Normal, VertexAttribute::TextureCoords(0)]
+            .into_iter()
+            .chain([VertexAttribute::Tangent, VertexAttribute::Bitangent, VertexAttribute::Color])
+            .chain((1..MAX_TEXTURE_COORD_SETS).map(VertexAttribute::TextureCoords))
+            .chain([VertexAttribute::Joints, VertexAttribute::Weights])
+    }
+
+    /// The shader input location. The first three match the original `shd.vert`.
+    pub fn location(self) -> u32 {
+        match self {
+            VertexAttribute::Position => 0,
+            VertexAttribute::Normal => 1,
+            VertexAttribute::TextureCoords(0) => 2,
+            VertexAttribute::Tangent => 3,
+            VertexAttribute::Bitangent => 4,
+            VertexAttribute::Color => 5,
+            VertexAttribute::TextureCoords(set) => 5 + set as u32,
+            VertexAttribute::Joints => 5 + MAX_TEXTURE_COORD_SETS as u32,
+            VertexAttribute::Weights => 6 + MAX_TEXTURE_COORD_SETS as u32,
+        }
+    }
+
+    /// Input name given this attribute's location when the shader doesn't set one with
+    /// `layout (location = …)`, e.g. `aPos` or `aTexCoords1`.
+    pub fn shader_name(self) -> String {
+        match self {
+            VertexAttribute::Position => "aPos".to_string(),
+            VertexAttribute::Normal => "aNormal".to_string(),
+            VertexAttribute::Tangent => "aTangent".to_string(),
+            VertexAttribute::Bitangent => "aBitangent".to_string(),
+            VertexAttribute::Color => "aColor".to_string(),
+            VertexAttribute::TextureCoords(0) => "aTexCoords".to_string(),
+            VertexAttribute::TextureCoords(set) => format!("aTexCoords{}", set),
+            VertexAttribute::Joints => "aJoints".to_string(),
+            VertexAttribute::Weights => "aWeights".to_string(),
+        }
+    }
+
+    pub fn components(self) -> usize {
+        match self {
+            VertexAttribute::Position | VertexAttribute::Normal | VertexAttribute::Bitangent => 3,
+            VertexAttribute::TextureCoords(_) => 2,
+            VertexAttribute::Tangent | VertexAttribute::Color | VertexAttribute::Joints | VertexAttribute::Weights => 4,
+        }
+    }
+
+    pub fn component_type(self) -> ComponentType {
+        match self {
+            VertexAttribute::Joints => ComponentType::UnsignedShort,
+            _ => ComponentType::Float,
+        }
+    }
+
+    /// Bytes per vertex.
+    pub fn size(self) -> usize {
+        self.components() * self.component_type().size()
+    }
+
+    /// `(kind, UV set)`, as stored in mesh files.
+    pub fn code(self) -> (u8, u8) {
+        match self {
+            VertexAttribute::Position => (0, 0),
+            VertexAttribute::Normal => (1, 0),
+            VertexAttribute::Tangent => (2, 0),
+            VertexAttribute::Bitangent => (3, 0),
+            VertexAttribute::Color => (4, 0),
+            VertexAttribute::TextureCoords(set) => (5, set),
+            VertexAttribute::Joints => (6, 0),
+            VertexAttribute::Weights => (7, 0),
+        }
+    }
+
+    pub fn from_code(kind: u8, set: u8) -> Option<Self> {
+        match kind {
+            0 => Some(VertexAttribute::Position),
+            1 => Some(VertexAttribute::Normal),
+            2 => Some(VertexAttribute::Tangent),
+            3 => Some(VertexAttribute::Bitangent),
+            4 => Some(VertexAttribute::Color),
+            5 if set < MAX_TEXTURE_COORD_SETS => Some(VertexAttribute::TextureCoords(set)),
+            6 => Some(VertexAttribute::Joints),
+            7 => Some(VertexAttribute::Weights),
+            _ => None,
+        }
+    }
+}
+
+impl Display for VertexAttribute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VertexAttribute::Position => write!(f, "position"),
+            VertexAttribute::Normal => write!(f, "normal"),
+            VertexAttribute::Tangent => write!(f, "tangent"),
+            VertexAttribute::Bitangent => write!(f, "bitangent"),
+            VertexAttribute::Color => write!(f, "color"),
+            VertexAttribute::TextureCoords(set) => write!(f, "texture_coords{}", set),
+            VertexAttribute::Joints => write!(f, "joints"),
+            VertexAttribute::Weights => write!(f, "weights"),
+        }
+    }
+}
+
+/// An attribute and where it starts inside a vertex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutAttribute {
+    pub attribute: VertexAttribute,
+    pub offset: usize,
+}
+
+/// Which attributes a vertex buffer holds and how they are interleaved.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VertexLayout {
+    attributes: Vec<LayoutAttribute>,
+    stride: usize,
+}
+
+impl VertexLayout {
+    /// Packs `attributes` tightly in the given order; repeats are dropped.
+    pub fn new(attributes: impl IntoIterator<Item = VertexAttribute>) -> Self {
+        let mut layout = Self::default();
+        for attribute in attributes {
+            if layout.contains(attribute) {
+                continue;
+            }
+            layout.attributes.push(LayoutAttribute { attribute, offset: layout.stride });
+            layout.stride += attribute.size();
+        }
+        layout
+    }
+
+    /// Position, normal and one UV set: the layout of [`Vertex`](super::mesh::Vertex).
+    pub fn standard() -> Self {
+        Self::new([VertexAttribute::Position, VertexAttribute::Normal, VertexAttribute::TextureCoords(0)])
+    }
+
+    /// Bytes per vertex.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    pub fn attributes(&self) -> &[LayoutAttribute] {
+        &self.attributes
+    }
+
+    pub fn contains(&self, attribute: VertexAttribute) -> bool {
+        self.offset(attribute).is_some()
+    }
+
+    pub fn offset(&self, attribute: VertexAttribute) -> Option<usize> {
+        self.attributes
+            .iter()
+            .find(|layout_attribute| layout_attribute.attribute == attribute)
+            .map(|layout_attribute| layout_attribute.offset)
+    }
+
+    /// Points each attribute's location at the bound `GL_ARRAY_BUFFER`. A vertex array has to
+    /// be bound; locations the layout doesn't use are disabled.
+    pub fn enable(&self) {
+        for attribute in VertexAttribute::all() {
+            if !self.contains(attribute) {
+                unsafe {
+                    gl::DisableVertexAttribArray(attribute.location());
+                }
+            }
+        }
+
+        for LayoutAttribute { attribute, offset } in &self.attributes {
+            let location = attribute.location();
+            let components = attribute.components() as i32;
+            let gl_type = attribute.component_type().gl_type();
+            let offset = *offset as *const std::ffi::c_void;
+            unsafe {
+                gl::EnableVertexAttribArray(location);
+                if attribute.component_type().is_integer() {
+                    gl::VertexAttribIPointer(location, components, gl_type, self.stride as i32, offset);
+                } else {
+                    gl::VertexAttribPointer(location, components, gl_type, gl::FALSE, self.stride as i32, offset);
+                }
+            }
+        }
+    }
+
+    /// Checks the layout against the vertex inputs `shader` declares. Returns one message per
+    /// input the layout doesn't provide or provides with the wrong kind of component; an empty
+    /// list means the program can draw meshes of this layout. Attributes the program doesn't
+    /// read are fine.
+    pub fn validate(&self, shader: &ShaderProgram) -> Vec<String> {
+        let mut inputs: Vec<(&str, &AttributeInfo)> = shader.attributes().collect();
+        inputs.sort_by_key(|(_, info)| info.location);
+
+        let mut problems = Vec::new();
+        for (name, info) in inputs {
+            let attribute = self
+                .attributes
+                .iter()
+                .map(|layout_attribute| layout_attribute.attribute)
+                .find(|attribute| attribute.location() as i32 == info.location);
+
+            match attribute {
+                None => problems.push(format!(
+                    "input `{}` at location {} has no vertex attribute and reads a constant",
+                    name, info.location
+                )),
+                Some(attribute) if attribute.component_type().is_integer() != is_integer_type(info.gl_type) => {
+                    problems.push(format!(
+                        "input `{}` is GL type 0x{:04X} but the {} attribute is {:?}",
+                        name,
+                        info.gl_type,
+                        attribute,
+                        attribute.component_type()
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+
+        problems
+    }
+}
+
+/// An active vertex input of a linked program, as reported by `glGetActiveAttrib`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttributeInfo {
+    pub location: i32,
+    /// GL type enum, e.g. `gl::FLOAT_VEC3`.
+    pub gl_type: GLenum,
+    pub size: i32,
+}
+
+fn is_integer_type(gl_type: GLenum) -> bool {
+    matches!(
+        gl_type,
+        gl::INT
+            | gl::INT_VEC2
+            | gl::INT_VEC3
+            | gl::INT_VEC4
+            | gl::UNSIGNED_INT
+            | gl::UNSIGNED_INT_VEC2
+            | gl::UNSIGNED_INT_VEC3
+            | gl::UNSIGNED_INT_VEC4
+    )
+}
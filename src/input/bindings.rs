@@ -0,0 +1,651 @@
+use crate::utils::errors::ERRUranXError;
+use glfw::{GamepadAxis, GamepadButton, Key, MouseButton};
+use serde_derive::Deserialize;
+use std::collections::HashMap;
+
+/// A single digital input: a keyboard key, a mouse button or a gamepad button.
+///
+/// Names follow GLFW's: `"W"`, `"Space"`, `"LeftShift"`, `"Num1"`, `"F5"`,
+/// `"MouseLeft"`, `"MouseRight"`, `"MouseMiddle"` or `"Mouse4"`..`"Mouse8"`, and
+/// `"PadA"`, `"PadStart"`, `"PadDpadUp"`, ... for gamepads. A gamepad axis pushed past the
+/// trigger threshold also counts as a button: `"PadLeftTrigger"`, `"PadLeftX+"`, `"PadLeftY-"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputBinding {
+    Key(Key),
+    MouseButton(MouseButton),
+    /// The button on any connected gamepad.
+    GamepadButton(GamepadButton),
+    /// A gamepad axis past the threshold in the positive (`true`) or negative direction.
+    GamepadAxis(GamepadAxis, bool),
+}
+
+/// Continuous inputs that can drive an axis directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnalogSource {
+    MouseX,
+    MouseY,
+    ScrollX,
+    ScrollY,
+    /// Dead-zoned stick in `[-1, 1]` or trigger in `[0, 1]`, from whichever gamepad pushes
+    /// it furthest.
+    Gamepad(GamepadAxis),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AxisBinding {
+    /// `+1` while `positive` is held, `-1` while `negative` is held.
+    Buttons {
+        positive: Option<InputBinding>,
+        negative: Option<InputBinding>,
+    },
+    /// Value of `source` multiplied by `scale`: a per-frame delta for the mouse and scroll
+    /// wheel, a position for gamepad axes.
+    Analog { source: AnalogSource, scale: f32 },
+}
+
+/// The `[input]` section of settings.toml.
+///
+/// ```toml
+/// [input.actions]
+/// jump = ["Space"]
+///
+/// [input.axes]
+/// move_x = [{ positive = "D", negative = "A" }]
+/// look_x = [{ source = "MouseX", scale = 0.1 }]
+/// ```
+#[derive(Deserialize, Default)]
+pub struct InputSettings {
+    #[serde(default)]
+    pub actions: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub axes: HashMap<String, Vec<AxisBindingSettings>>,
+    #[serde(default)]
+    pub gamepad: GamepadSettings,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct GamepadSettings {
+    /// Stick deflection below which input is ignored, in `[0, 1)`.
+    #[serde(default = "default_dead_zone")]
+    pub dead_zone: f32,
+    /// Trigger travel below which input is ignored, in `[0, 1)`.
+    #[serde(default = "default_trigger_dead_zone")]
+    pub trigger_dead_zone: f32,
+    /// Axis value past which an axis counts as a pressed button.
+    #[serde(default = "default_button_threshold")]
+    pub button_threshold: f32,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            dead_zone: default_dead_zone(),
+            trigger_dead_zone: default_trigger_dead_zone(),
+            button_threshold: default_button_threshold(),
+        }
+    }
+}
+
+fn default_dead_zone() -> f32 {
+    0.15
+}
+
+fn default_trigger_dead_zone() -> f32 {
+    0.05
+}
+
+fn default_button_threshold() -> f32 {
+    0.5
+}
+
+#[derive(Deserialize)]
+pub struct AxisBindingSettings {
+    #[serde(default)]
+    pub positive: Option<String>,
+    #[serde(default)]
+    pub negative: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+const MOUSE_BUTTONS: &[(&str, MouseButton)] = &[
+    ("MouseLeft", MouseButton::Button1),
+    ("MouseRight", MouseButton::Button2),
+    ("MouseMiddle", MouseButton::Button3),
+    ("Mouse1", MouseButton::Button1),
+    ("Mouse2", MouseButton::Button2),
+    ("Mouse3", MouseButton::Button3),
+    ("Mouse4", MouseButton::Button4),
+    ("Mouse5", MouseButton::Button5),
+    ("Mouse6", MouseButton::Button6),
+    ("Mouse7", MouseButton::Button7),
+    ("Mouse8", MouseButton::Button8),
+];
+
+const GAMEPAD_BUTTONS: &[(&str, GamepadButton)] = &[
+    ("PadA", GamepadButton::ButtonA),
+    ("PadB", GamepadButton::ButtonB),
+    ("PadX", GamepadButton::ButtonX),
+    ("PadY", GamepadButton::ButtonY),
+    ("PadLeftBumper", GamepadButton::ButtonLeftBumper),
+    ("PadRightBumper", GamepadButton::ButtonRightBumper),
+    ("PadBack", GamepadButton::ButtonBack),
+    ("PadStart", GamepadButton::ButtonStart),
+    ("PadGuide", GamepadButton::ButtonGuide),
+    ("PadLeftThumb", GamepadButton::ButtonLeftThumb),
+    ("PadRightThumb", GamepadButton::ButtonRightThumb),
+    ("PadDpadUp", GamepadButton::ButtonDpadUp),
+    ("PadDpadRight", GamepadButton::ButtonDpadRight),
+    ("PadDpadDown", GamepadButton::ButtonDpadDown),
+    ("PadDpadLeft", GamepadButton::ButtonDpadLeft),
+];
+
+const GAMEPAD_AXES: &[(&str, GamepadAxis)] = &[
+    ("PadLeftX", GamepadAxis::AxisLeftX),
+    ("PadLeftY", GamepadAxis::AxisLeftY),
+    ("PadRightX", GamepadAxis::AxisRightX),
+    ("PadRightY", GamepadAxis::AxisRightY),
+    ("PadLeftTrigger", GamepadAxis::AxisLeftTrigger),
+    ("PadRightTrigger", GamepadAxis::AxisRightTrigger),
+];
+
+const ANALOG_SOURCES: &[(&str, AnalogSource)] = &[
+    ("MouseX", AnalogSource::MouseX),
+    ("MouseY", AnalogSource::MouseY),
+    ("ScrollX", AnalogSource::ScrollX),
+    ("ScrollY", AnalogSource::ScrollY),
+];
+
+const KEYS: &[(&str, Key)] = &[
+    ("Space", Key::Space),
+    ("Apostrophe", Key::Apostrophe),
+    ("Comma", Key::Comma),
+    ("Minus", Key::Minus),
+    ("Period", Key::Period),
+    ("Slash", Key::Slash),
+    ("Num0", Key::Num0),
+    ("Num1", Key::Num1),
+    ("Num2", Key::Num2),
+    ("Num3", Key::Num3),
+    ("Num4", Key::Num4),
+    ("Num5", Key::Num5),
+    ("Num6", Key::Num6),
+    ("Num7", Key::Num7),
+    ("Num8", Key::Num8),
+    ("Num9", Key::Num9),
+    ("Semicolon", Key::Semicolon),
+    ("Equal", Key::Equal),
+    ("A", Key::A),
+    ("B", Key::B),
+    ("C", Key::C),
+    ("D", Key::D),
+    ("E", Key::E),
+    ("F", Key::F),
+    ("G", Key::G),
+    ("H", Key::H),
+    ("I", Key::I),
+    ("J", Key::J),
+    ("K", Key::K),
+    ("L", Key::L),
+    ("M", Key::M),
+    ("N", Key::N),
+    ("O", Key::O),
+    ("P", Key::P),
+    ("Q", Key::Q),
+    ("R", Key::R),
+    ("S", Key::S),
+    ("T", Key::T),
+    ("U", Key::U),
+    ("V", Key::V),
+    ("W", Key::W),
+    ("X", Key::X),
+    ("Y", Key::Y),
+    ("Z", Key::Z),
+    ("LeftBracket", Key::LeftBracket),
+    ("Backslash", Key::Backslash),
+    ("RightBracket", Key::RightBracket),
+    ("GraveAccent", Key::GraveAccent),
+    ("World1", Key::World1),
+    ("World2", Key::World2),
+    ("Escape", Key::Escape),
+    ("Enter", Key::Enter),
+    ("Tab", Key::Tab),
+    ("Backspace", Key::Backspace),
+    ("Insert", Key::Insert),
+    ("Delete", Key::Delete),
+    ("Right", Key::Right),
+    ("Left", Key::Left),
+    ("Down", Key::Down),
+    ("Up", Key::Up),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("CapsLock", Key::CapsLock),
+    ("ScrollLock", Key::ScrollLock),
+    ("NumLock", Key::NumLock),
+    ("PrintScreen", Key::PrintScreen),
+    ("Pause", Key::Pause),
+    ("F1", Key::F1),
+    ("F2", Key::F2),
+    ("F3", Key::F3),
+    ("F4", Key::F4),
+    ("F5", Key::F5),
+    ("F6", Key::F6),
+    ("F7", Key::F7),
+    ("F8", Key::F8),
+    ("F9", Key::F9),
+    ("F10", Key::F10),
+    ("F11", Key::F11),
+    ("F12", Key::F12),
+    ("F13", Key::F13),
+    ("F14", Key::F14),
+    ("F15", Key::F15),
+    ("F16", Key::F16),
+    ("F17", Key::F17),
+    ("F18", Key::F18),
+    ("F19", Key::F19),
+    ("F20", Key::F20),
+    ("F21", Key::F21),
+    ("F22", Key::F22),
+    ("F23", Key::F23),
+    ("F24", Key::F24),
+    ("F25", Key::F25),
+    ("Kp0", Key::Kp0),
+    ("Kp1", Key::Kp1),
+    ("Kp2", Key::Kp2),
+    ("Kp3", Key::Kp3),
+    ("Kp4", Key::Kp4),
+    ("Kp5", Key::Kp5),
+    ("Kp6", Key::Kp6),
+    ("Kp7", Key::Kp7),
+    ("Kp8", Key::Kp8),
+    ("Kp9", Key::Kp9),
+    ("KpDecimal", Key::KpDecimal),
+    ("KpDivide", Key::KpDivide),
+    ("KpMultiply", Key::KpMultiply),
+    ("KpSubtract", Key::KpSubtract),
+    ("KpAdd", Key::KpAdd),
+    ("KpEnter", Key::KpEnter),
+    ("KpEqual", Key::KpEqual),
+    ("LeftShift", Key::LeftShift),
+    ("LeftControl", Key::LeftControl),
+    ("LeftAlt", Key::LeftAlt),
+    ("LeftSuper", Key::LeftSuper),
+    ("RightShift", Key::RightShift),
+    ("RightControl", Key::RightControl),
+    ("RightAlt", Key::RightAlt),
+    ("RightSuper", Key::RightSuper),
+    ("Menu", Key::Menu),
+];
+
+fn find_by_name<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    table
+        .iter()
+        .find(|(entry, _)| entry.eq_ignore_ascii_case(name))
+        .map(|(_, value)| *value)
+}
+
+fn find_name<T: PartialEq>(table: &[(&'static str, T)], value: &T) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(_, entry)| entry == value)
+        .map(|(name, _)| *name)
+}
+
+/// Triggers rest at `0` and only have a positive direction.
+pub fn is_trigger(axis: GamepadAxis) -> bool {
+    matches!(axis, GamepadAxis::AxisLeftTrigger | GamepadAxis::AxisRightTrigger)
+}
+
+impl InputBinding {
+    pub fn parse(name: &str) -> Option<InputBinding> {
+        let name = name.trim();
+
+        if let Some(axis) = find_by_name(GAMEPAD_AXES, name).filter(|axis| is_trigger(*axis)) {
+            return Some(InputBinding::GamepadAxis(axis, true));
+        }
+        if let Some(axis) = name.strip_suffix('+').and_then(|name| find_by_name(GAMEPAD_AXES, name)) {
+            return Some(InputBinding::GamepadAxis(axis, true));
+        }
+        if let Some(axis) = name.strip_suffix('-').and_then(|name| find_by_name(GAMEPAD_AXES, name)) {
+            return Some(InputBinding::GamepadAxis(axis, false)).filter(|_| !is_trigger(axis));
+        }
+
+        find_by_name(MOUSE_BUTTONS, name)
+            .map(InputBinding::MouseButton)
+            .or_else(|| find_by_name(GAMEPAD_BUTTONS, name).map(InputBinding::GamepadButton))
+            .or_else(|| find_by_name(KEYS, name).map(InputBinding::Key))
+    }
+
+    pub fn name(&self) -> String {
+        let name = match self {
+            InputBinding::Key(key) => find_name(KEYS, key),
+            InputBinding::MouseButton(button) => find_name(MOUSE_BUTTONS, button),
+            InputBinding::GamepadButton(button) => find_name(GAMEPAD_BUTTONS, button),
+            InputBinding::GamepadAxis(axis, is_positive) => {
+                let name = find_name(GAMEPAD_AXES, axis).unwrap_or("Unknown");
+                return match (is_trigger(*axis), is_positive) {
+                    (true, _) => name.to_string(),
+                    (false, true) => format!("{}+", name),
+                    (false, false) => format!("{}-", name),
+                };
+            }
+        };
+
+        name.unwrap_or("Unknown").to_string()
+    }
+}
+
+impl AnalogSource {
+    pub fn parse(name: &str) -> Option<AnalogSource> {
+        let name = name.trim();
+        find_by_name(ANALOG_SOURCES, name)
+            .or_else(|| find_by_name(GAMEPAD_AXES, name).map(AnalogSource::Gamepad))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnalogSource::Gamepad(axis) => find_name(GAMEPAD_AXES, axis),
+            source => find_name(ANALOG_SOURCES, source),
+        }
+        .unwrap_or("Unknown")
+    }
+}
+
+impl AxisBinding {
+    pub fn keys(positive: Key, negative: Key) -> Self {
+        AxisBinding::Buttons {
+            positive: Some(InputBinding::Key(positive)),
+            negative: Some(InputBinding::Key(negative)),
+        }
+    }
+
+    pub fn analog(source: AnalogSource, scale: f32) -> Self {
+        AxisBinding::Analog { source, scale }
+    }
+
+    fn from_settings(axis: &str, settings: &AxisBindingSettings) -> Result<Self, ERRUranXError> {
+        let parse = |name: &Option<String>| -> Result<Option<InputBinding>, ERRUranXError> {
+            match name {
+                Some(name) => InputBinding::parse(name)
+                    .map(Some)
+                    .ok_or_else(|| ERRUranXError::INVALID_INPUT_BINDING(format!("{}: {}", axis, name))),
+                None => Ok(None),
+            }
+        };
+
+        if let Some(source) = &settings.source {
+            let source = AnalogSource::parse(source)
+                .ok_or_else(|| ERRUranXError::INVALID_INPUT_BINDING(format!("{}: {}", axis, source)))?;
+            return Ok(AxisBinding::Analog {
+                source,
+                scale: settings.scale,
+            });
+        }
+
+        let positive = parse(&settings.positive)?;
+        let negative = parse(&settings.negative)?;
+        if positive.is_none() && negative.is_none() {
+            return Err(ERRUranXError::INVALID_INPUT_BINDING(axis.to_string()));
+        }
+
+        Ok(AxisBinding::Buttons { positive, negative })
+    }
+}
+
+/// Named actions and axes together with the inputs bound to them.
+#[derive(Debug, Clone, Default)]
+pub struct InputMap {
+    actions: HashMap<String, Vec<InputBinding>>,
+    axes: HashMap<String, Vec<AxisBinding>>,
+    pub gamepad: GamepadSettings,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_settings(settings: &InputSettings) -> Result<Self, ERRUranXError> {
+        let mut map = Self::new();
+        map.gamepad = settings.gamepad;
+
+        for (action, names) in &settings.actions {
+            map.actions.entry(action.clone()).or_default();
+            for name in names {
+                let binding = InputBinding::parse(name)
+                    .ok_or_else(|| ERRUranXError::INVALID_INPUT_BINDING(format!("{}: {}", action, name)))?;
+                map.bind_action(action, binding);
+            }
+        }
+
+        for (axis, bindings) in &settings.axes {
+            map.axes.entry(axis.clone()).or_default();
+            for binding in bindings {
+                map.bind_axis(axis, AxisBinding::from_settings(axis, binding)?);
+            }
+        }
+
+        Ok(map)
+    }
+
+    pub fn bind_action(&mut self, action: &str, binding: InputBinding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind_action(&mut self, action: &str, binding: InputBinding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|bound| *bound != binding);
+        }
+    }
+
+    /// Replaces `old` with `new` on `action`. Returns `false` if `old` was not bound.
+    pub fn rebind_action(&mut self, action: &str, old: InputBinding, new: InputBinding) -> bool {
+        let Some(bindings) = self.actions.get_mut(action) else {
+            return false;
+        };
+
+        match bindings.iter().position(|bound| *bound == old) {
+            Some(index) => {
+                if bindings.contains(&new) {
+                    bindings.remove(index);
+                } else {
+                    bindings[index] = new;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear_action(&mut self, action: &str) {
+        self.actions.entry(action.to_string()).or_default().clear();
+    }
+
+    pub fn action_bindings(&self, action: &str) -> &[InputBinding] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        self.axes.entry(axis.to_string()).or_default().push(binding);
+    }
+
+    pub fn clear_axis(&mut self, axis: &str) {
+        self.axes.entry(axis.to_string()).or_default().clear();
+    }
+
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn axes(&self) -> impl Iterator<Item = &str> {
+        self.axes.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keys_and_mouse_buttons() {
+        assert_eq!(InputBinding::parse("W"), Some(InputBinding::Key(Key::W)));
+        assert_eq!(InputBinding::parse("space"), Some(InputBinding::Key(Key::Space)));
+        assert_eq!(InputBinding::parse(" LeftShift "), Some(InputBinding::Key(Key::LeftShift)));
+        assert_eq!(InputBinding::parse("F5"), Some(InputBinding::Key(Key::F5)));
+        assert_eq!(InputBinding::parse("MouseLeft"), Some(InputBinding::MouseButton(MouseButton::Button1)));
+        assert_eq!(InputBinding::parse("mouse8"), Some(InputBinding::MouseButton(MouseButton::Button8)));
+    }
+
+    #[test]
+    fn parses_gamepad_buttons_and_axes() {
+        assert_eq!(
+            InputBinding::parse("PadStart"),
+            Some(InputBinding::GamepadButton(GamepadButton::ButtonStart))
+        );
+        assert_eq!(
+            InputBinding::parse("PadLeftTrigger"),
+            Some(InputBinding::GamepadAxis(GamepadAxis::AxisLeftTrigger, true))
+        );
+        assert_eq!(
+            InputBinding::parse("PadRightTrigger+"),
+            Some(InputBinding::GamepadAxis(GamepadAxis::AxisRightTrigger, true))
+        );
+        assert_eq!(
+            InputBinding::parse("PadLeftX+"),
+            Some(InputBinding::GamepadAxis(GamepadAxis::AxisLeftX, true))
+        );
+        assert_eq!(
+            InputBinding::parse("padlefty-"),
+            Some(InputBinding::GamepadAxis(GamepadAxis::AxisLeftY, false))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        for name in ["", "Jump", "Mouse9", "PadZ", "PadLeftX", "PadLeftTrigger-", "W+", "Num10"] {
+            assert_eq!(InputBinding::parse(name), None, "{:?}", name);
+        }
+        assert_eq!(AnalogSource::parse("MouseZ"), None);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        let tables = KEYS
+            .iter()
+            .map(|(_, key)| InputBinding::Key(*key))
+            .chain(GAMEPAD_BUTTONS.iter().map(|(_, button)| InputBinding::GamepadButton(*button)))
+            .chain(MOUSE_BUTTONS.iter().map(|(_, button)| InputBinding::MouseButton(*button)))
+            .chain(GAMEPAD_AXES.iter().flat_map(|(_, axis)| {
+                [InputBinding::GamepadAxis(*axis, true), InputBinding::GamepadAxis(*axis, false)]
+                    .into_iter()
+                    .filter(|binding| !matches!(binding, InputBinding::GamepadAxis(axis, false) if is_trigger(*axis)))
+            }));
+
+        for binding in tables {
+            assert_eq!(InputBinding::parse(&binding.name()), Some(binding), "{}", binding.name());
+        }
+        assert_eq!(InputBinding::MouseButton(MouseButton::Button1).name(), "MouseLeft");
+        assert_eq!(InputBinding::GamepadAxis(GamepadAxis::AxisLeftX, false).name(), "PadLeftX-");
+    }
+
+    #[test]
+    fn parses_analog_sources() {
+        assert_eq!(AnalogSource::parse("MouseX"), Some(AnalogSource::MouseX));
+        assert_eq!(AnalogSource::parse("scrolly"), Some(AnalogSource::ScrollY));
+        assert_eq!(
+            AnalogSource::parse("PadRightY"),
+            Some(AnalogSource::Gamepad(GamepadAxis::AxisRightY))
+        );
+        assert_eq!(AnalogSource::Gamepad(GamepadAxis::AxisLeftTrigger).name(), "PadLeftTrigger");
+    }
+
+    #[test]
+    fn builds_map_from_settings() {
+        let settings: InputSettings = toml::from_str(
+            r#"
+            [actions]
+            jump = ["Space", "PadA"]
+
+            [axes]
+            move_x = [{ positive = "D", negative = "A" }, { source = "PadLeftX" }]
+            look_x = [{ source = "MouseX", scale = 0.1 }]
+
+            [gamepad]
+            dead_zone = 0.2
+            "#,
+        )
+        .unwrap();
+        let map = InputMap::from_settings(&settings).unwrap();
+
+        assert_eq!(
+            map.action_bindings("jump"),
+            [InputBinding::Key(Key::Space), InputBinding::GamepadButton(GamepadButton::ButtonA)]
+        );
+        assert_eq!(
+            map.axis_bindings("move_x"),
+            [
+                AxisBinding::keys(Key::D, Key::A),
+                AxisBinding::analog(AnalogSource::Gamepad(GamepadAxis::AxisLeftX), 1.0),
+            ]
+        );
+        assert_eq!(map.axis_bindings("look_x"), [AxisBinding::analog(AnalogSource::MouseX, 0.1)]);
+        assert_eq!(map.gamepad.dead_zone, 0.2);
+        assert_eq!(map.gamepad.button_threshold, 0.5);
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        for source in [
+            r#"actions = { jump = ["Spacebar"] }"#,
+            r#"axes = { move_x = [{ positive = "D", negative = "PadLeftTrigger-" }] }"#,
+            r#"axes = { look_x = [{ source = "MouseZ" }] }"#,
+            r#"axes = { move_x = [{ scale = 2.0 }] }"#,
+        ] {
+            let settings: InputSettings = toml::from_str(source).unwrap();
+            assert!(
+                matches!(InputMap::from_settings(&settings), Err(ERRUranXError::INVALID_INPUT_BINDING(_))),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn rebinds_an_action() {
+        let (space, j, pad_a) = (
+            InputBinding::Key(Key::Space),
+            InputBinding::Key(Key::J),
+            InputBinding::GamepadButton(GamepadButton::ButtonA),
+        );
+        let mut map = InputMap::new();
+        map.bind_action("jump", space);
+        map.bind_action("jump", pad_a);
+
+        assert!(map.rebind_action("jump", space, j));
+        assert_eq!(map.action_bindings("jump"), [j, pad_a]);
+
+        assert!(!map.rebind_action("jump", space, j));
+        assert!(!map.rebind_action("crouch", space, j));
+
+        assert!(map.rebind_action("jump", j, pad_a));
+        assert_eq!(map.action_bindings("jump"), [pad_a]);
+
+        map.unbind_action("jump", pad_a);
+        assert!(map.action_bindings("jump").is_empty());
+    }
+}
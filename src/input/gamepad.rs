@@ -0,0 +1,264 @@
+use super::bindings::{is_trigger, GamepadSettings};
+use glfw::{Action, GamepadAxis, GamepadButton, Glfw, JoystickId};
+
+pub const GAMEPAD_BUTTON_COUNT: usize = 15;
+pub const GAMEPAD_AXIS_COUNT: usize = 6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected(JoystickId, String),
+    Disconnected(JoystickId),
+}
+
+/// Raw state of a gamepad in GLFW's standard layout.
+///
+/// Sticks range over `[-1, 1]` with `+Y` pointing down; triggers rest at `-1` and go to `1`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GamepadSnapshot {
+    pub buttons: [bool; GAMEPAD_BUTTON_COUNT],
+    pub axes: [f32; GAMEPAD_AXIS_COUNT],
+}
+
+impl Default for GamepadSnapshot {
+    fn default() -> Self {
+        let mut axes = [0.0; GAMEPAD_AXIS_COUNT];
+        axes[GamepadAxis::AxisLeftTrigger as usize] = -1.0;
+        axes[GamepadAxis::AxisRightTrigger as usize] = -1.0;
+
+        Self {
+            buttons: [false; GAMEPAD_BUTTON_COUNT],
+            axes,
+        }
+    }
+}
+
+impl From<glfw::GamepadState> for GamepadSnapshot {
+    fn from(state: glfw::GamepadState) -> Self {
+        let mut snapshot = Self::default();
+        for (index, is_down) in snapshot.buttons.iter_mut().enumerate() {
+            let button = GamepadButton::from_i32(index as i32).unwrap();
+            *is_down = state.get_button_state(button) == Action::Press;
+        }
+        for (index, value) in snapshot.axes.iter_mut().enumerate() {
+            *value = state.get_axis(GamepadAxis::from_i32(index as i32).unwrap());
+        }
+        snapshot
+    }
+}
+
+/// A connected gamepad with dead zones applied to its axes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gamepad {
+    pub id: JoystickId,
+    pub name: String,
+    buttons: [bool; GAMEPAD_BUTTON_COUNT],
+    axes: [f32; GAMEPAD_AXIS_COUNT],
+}
+
+impl Gamepad {
+    pub fn new(id: JoystickId, name: &str) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            buttons: [false; GAMEPAD_BUTTON_COUNT],
+            axes: [0.0; GAMEPAD_AXIS_COUNT],
+        }
+    }
+
+    pub fn update(&mut self, snapshot: &GamepadSnapshot, settings: &GamepadSettings) {
+        self.buttons = snapshot.buttons;
+
+        for (x, y) in [
+            (GamepadAxis::AxisLeftX, GamepadAxis::AxisLeftY),
+            (GamepadAxis::AxisRightX, GamepadAxis::AxisRightY),
+        ] {
+            let (x, y) = (x as usize, y as usize);
+            let (value_x, value_y) =
+                apply_radial_dead_zone(snapshot.axes[x], snapshot.axes[y], settings.dead_zone);
+            self.axes[x] = value_x;
+            self.axes[y] = value_y;
+        }
+
+        for trigger in [GamepadAxis::AxisLeftTrigger, GamepadAxis::AxisRightTrigger] {
+            let index = trigger as usize;
+            let travel = (snapshot.axes[index] + 1.0) * 0.5;
+            self.axes[index] = apply_dead_zone(travel, settings.trigger_dead_zone);
+        }
+    }
+
+    pub fn is_button_down(&self, button: GamepadButton) -> bool {
+        self.buttons[button as usize]
+    }
+
+    /// Stick value in `[-1, 1]` or trigger value in `[0, 1]`, after dead zones.
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+
+    /// Whether the axis is pushed past `threshold` in the given direction.
+    pub fn is_axis_past(&self, axis: GamepadAxis, is_positive: bool, threshold: f32) -> bool {
+        let value = self.axis(axis);
+        if is_positive || is_trigger(axis) {
+            value >= threshold
+        } else {
+            value <= -threshold
+        }
+    }
+}
+
+/// Rescales `value` so that the range past the dead zone maps onto `[0, 1]`.
+pub fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    let magnitude = value.abs();
+    if magnitude <= dead_zone || dead_zone >= 1.0 {
+        return 0.0;
+    }
+
+    value.signum() * ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0)
+}
+
+/// Dead zone on the length of a stick, so diagonals are not snapped to the axes.
+pub fn apply_radial_dead_zone(x: f32, y: f32, dead_zone: f32) -> (f32, f32) {
+    let length = (x * x + y * y).sqrt();
+    if length <= dead_zone || dead_zone >= 1.0 {
+        return (0.0, 0.0);
+    }
+
+    let scale = ((length - dead_zone) / (1.0 - dead_zone)).min(1.0) / length;
+    (x * scale, y * scale)
+}
+
+/// Reads every joystick slot that GLFW recognizes as a gamepad.
+pub fn poll_gamepads(glfw: &Glfw) -> Vec<(JoystickId, String, GamepadSnapshot)> {
+    (0..16)
+        .filter_map(JoystickId::from_i32)
+        .filter_map(|id| {
+            let joystick = glfw.get_joystick(id);
+            if !joystick.is_present() || !joystick.is_gamepad() {
+                return None;
+            }
+
+            let state = joystick.get_gamepad_state()?;
+            let name = joystick
+                .get_gamepad_name()
+                .or_else(|| joystick.get_name())
+                .unwrap_or_default();
+            Some((id, name, GamepadSnapshot::from(state)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::EPSILON;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < EPSILON, "{} != {}", actual, expected);
+    }
+
+    fn snapshot(axes: &[(GamepadAxis, f32)]) -> GamepadSnapshot {
+        let mut snapshot = GamepadSnapshot::default();
+        for (axis, value) in axes {
+            snapshot.axes[*axis as usize] = *value;
+        }
+        snapshot
+    }
+
+    fn gamepad(axes: &[(GamepadAxis, f32)]) -> Gamepad {
+        let mut gamepad = Gamepad::new(JoystickId::Joystick1, "Pad");
+        gamepad.update(&snapshot(axes), &GamepadSettings::default());
+        gamepad
+    }
+
+    #[test]
+    fn dead_zone_ignores_values_up_to_its_edge() {
+        assert_eq!(apply_dead_zone(0.0, 0.2), 0.0);
+        assert_eq!(apply_dead_zone(0.1, 0.2), 0.0);
+        assert_eq!(apply_dead_zone(-0.2, 0.2), 0.0);
+        assert_eq!(apply_dead_zone(0.5, 1.0), 0.0);
+    }
+
+    #[test]
+    fn dead_zone_rescales_the_rest_onto_the_full_range() {
+        assert_close(apply_dead_zone(0.6, 0.2), 0.5);
+        assert_close(apply_dead_zone(-0.6, 0.2), -0.5);
+        assert_close(apply_dead_zone(1.0, 0.2), 1.0);
+        assert_close(apply_dead_zone(-1.5, 0.2), -1.0);
+        assert_close(apply_dead_zone(0.3, 0.0), 0.3);
+    }
+
+    #[test]
+    fn radial_dead_zone_keeps_the_stick_direction() {
+        assert_eq!(apply_radial_dead_zone(0.1, -0.1, 0.2), (0.0, 0.0));
+        assert_eq!(apply_radial_dead_zone(0.12, 0.16, 0.2), (0.0, 0.0));
+
+        let (x, y) = apply_radial_dead_zone(0.36, 0.48, 0.2);
+        assert_close(x, 0.3);
+        assert_close(y, 0.4);
+
+        let (x, y) = apply_radial_dead_zone(1.0, 1.0, 0.2);
+        assert_close(x, 0.5f32.sqrt());
+        assert_close(y, 0.5f32.sqrt());
+    }
+
+    #[test]
+    fn update_applies_dead_zones_to_sticks() {
+        let pad = gamepad(&[
+            (GamepadAxis::AxisLeftX, 0.1),
+            (GamepadAxis::AxisLeftY, -0.1),
+            (GamepadAxis::AxisRightX, -1.0),
+        ]);
+
+        assert_eq!(pad.axis(GamepadAxis::AxisLeftX), 0.0);
+        assert_eq!(pad.axis(GamepadAxis::AxisLeftY), 0.0);
+        assert_close(pad.axis(GamepadAxis::AxisRightX), -1.0);
+        assert_eq!(pad.axis(GamepadAxis::AxisRightY), 0.0);
+    }
+
+    #[test]
+    fn triggers_are_remapped_from_rest_at_minus_one() {
+        assert_eq!(gamepad(&[]).axis(GamepadAxis::AxisLeftTrigger), 0.0);
+
+        // 4% travel, inside the default 5% trigger dead zone.
+        let pad = gamepad(&[
+            (GamepadAxis::AxisLeftTrigger, -0.92),
+            (GamepadAxis::AxisRightTrigger, 1.0),
+        ]);
+        assert_eq!(pad.axis(GamepadAxis::AxisLeftTrigger), 0.0);
+        assert_close(pad.axis(GamepadAxis::AxisRightTrigger), 1.0);
+
+        let pad = gamepad(&[(GamepadAxis::AxisLeftTrigger, 0.0)]);
+        assert_close(pad.axis(GamepadAxis::AxisLeftTrigger), (0.5 - 0.05) / 0.95);
+    }
+
+    #[test]
+    fn axis_counts_as_pressed_past_the_threshold() {
+        let pad = gamepad(&[
+            (GamepadAxis::AxisLeftX, -0.9),
+            (GamepadAxis::AxisRightTrigger, 1.0),
+        ]);
+
+        assert!(pad.is_axis_past(GamepadAxis::AxisLeftX, false, 0.5));
+        assert!(!pad.is_axis_past(GamepadAxis::AxisLeftX, true, 0.5));
+        assert!(!pad.is_axis_past(GamepadAxis::AxisLeftX, false, 0.95));
+        assert!(pad.is_axis_past(GamepadAxis::AxisRightTrigger, true, 0.5));
+        assert!(!pad.is_axis_past(GamepadAxis::AxisLeftTrigger, true, 0.5));
+
+        let threshold = pad.axis(GamepadAxis::AxisLeftX).abs();
+        assert!(pad.is_axis_past(GamepadAxis::AxisLeftX, false, threshold));
+    }
+
+    #[test]
+    fn update_copies_buttons() {
+        let mut snapshot = GamepadSnapshot::default();
+        snapshot.buttons[GamepadButton::ButtonDpadUp as usize] = true;
+        let mut pad = Gamepad::new(JoystickId::Joystick2, "Pad");
+
+        pad.update(&snapshot, &GamepadSettings::default());
+        assert!(pad.is_button_down(GamepadButton::ButtonDpadUp));
+        assert!(!pad.is_button_down(GamepadButton::ButtonA));
+
+        pad.update(&GamepadSnapshot::default(), &GamepadSettings::default());
+        assert!(!pad.is_button_down(GamepadButton::ButtonDpadUp));
+    }
+}
@@ -0,0 +1,471 @@
+use super::bindings::{is_trigger, AnalogSource, AxisBinding, InputBinding, InputMap};
+use super::gamepad::{self, Gamepad, GamepadEvent, GamepadSnapshot, GAMEPAD_AXIS_COUNT, GAMEPAD_BUTTON_COUNT};
+use crate::math::Vec2;
+use glfw::{Action, GamepadAxis, GamepadButton, Glfw, JoystickId, Key, MouseButton, WindowEvent};
+use std::collections::HashSet;
+
+/// Per-frame keyboard, mouse and gamepad state.
+///
+/// Call [`Input::begin_frame`] before polling events, then [`Input::handle_event`] for each
+/// event and [`Input::update_gamepads`] once. "Pressed" and "released" are true only for the
+/// frame the change happened in.
+#[derive(Debug, Default)]
+pub struct Input {
+    map: InputMap,
+    held: HashSet<InputBinding>,
+    pressed: HashSet<InputBinding>,
+    released: HashSet<InputBinding>,
+    last_pressed: Option<InputBinding>,
+    cursor_position: Vec2,
+    cursor_delta: Vec2,
+    scroll_delta: Vec2,
+    has_cursor_position: bool,
+    gamepads: Vec<Gamepad>,
+    gamepad_events: Vec<GamepadEvent>,
+}
+
+impl Input {
+    pub fn new(map: InputMap) -> Self {
+        Self {
+            map,
+            ..Self::default()
+        }
+    }
+
+    pub fn map(&self) -> &InputMap {
+        &self.map
+    }
+
+    /// Bindings can be changed at any time; the new ones apply from the next query.
+    pub fn map_mut(&mut self) -> &mut InputMap {
+        &mut self.map
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.last_pressed = None;
+        self.cursor_delta = Vec2::ZERO;
+        self.scroll_delta = Vec2::ZERO;
+        self.gamepad_events.clear();
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::Key(key, _, action, _) => {
+                self.set_state(InputBinding::Key(key), action);
+            }
+            WindowEvent::MouseButton(button, action, _) => {
+                self.set_state(InputBinding::MouseButton(button), action);
+            }
+            WindowEvent::CursorPos(x, y) => {
+                let position = Vec2::new(x as f32, y as f32);
+                if self.has_cursor_position {
+                    self.cursor_delta += position - self.cursor_position;
+                }
+                self.cursor_position = position;
+                self.has_cursor_position = true;
+            }
+            WindowEvent::Scroll(x, y) => {
+                self.scroll_delta += Vec2::new(x as f32, y as f32);
+            }
+            WindowEvent::Focus(false) => {
+                self.release_all();
+            }
+            _ => {}
+        }
+    }
+
+    /// Releases every held key and mouse button, e.g. when the window loses focus and their
+    /// release events will never arrive.
+    ///
+    /// Gamepad bindings are left alone: gamepads are polled rather than evented, so
+    /// [`Input::update_gamepads`] releases them once they are actually let go.
+    pub fn release_all(&mut self) {
+        let released: Vec<InputBinding> = self
+            .held
+            .iter()
+            .filter(|binding| matches!(binding, InputBinding::Key(_) | InputBinding::MouseButton(_)))
+            .copied()
+            .collect();
+        for binding in released {
+            self.set_state(binding, Action::Release);
+        }
+    }
+
+    /// Polls every gamepad GLFW knows about. Call after polling window events.
+    pub fn update_gamepads(&mut self, glfw: &Glfw) {
+        let states = gamepad::poll_gamepads(glfw);
+
+        let disconnected: Vec<JoystickId> = self
+            .gamepads
+            .iter()
+            .map(|gamepad| gamepad.id)
+            .filter(|id| !states.iter().any(|(state_id, ..)| state_id == id))
+            .collect();
+        for id in disconnected {
+            self.set_gamepad_state(id, None);
+        }
+
+        for (id, name, snapshot) in &states {
+            self.set_gamepad_state(*id, Some((name, snapshot)));
+        }
+    }
+
+    /// Feeds the state of one gamepad, or `None` when it is unplugged.
+    ///
+    /// Emits connect/disconnect events and press/release transitions for gamepad bindings.
+    pub fn set_gamepad_state(&mut self, id: JoystickId, state: Option<(&str, &GamepadSnapshot)>) {
+        let held_before = self.gamepad_bindings_held();
+        let position = self.gamepads.iter().position(|gamepad| gamepad.id == id);
+
+        match (state, position) {
+            (Some((name, snapshot)), position) => {
+                let index = position.unwrap_or_else(|| {
+                    self.gamepads.push(Gamepad::new(id, name));
+                    self.gamepad_events.push(GamepadEvent::Connected(id, name.to_string()));
+                    self.gamepads.len() - 1
+                });
+                self.gamepads[index].update(snapshot, &self.map.gamepad);
+            }
+            (None, Some(index)) => {
+                self.gamepads.remove(index);
+                self.gamepad_events.push(GamepadEvent::Disconnected(id));
+            }
+            (None, None) => {}
+        }
+
+        let held_after = self.gamepad_bindings_held();
+        for binding in held_after.difference(&held_before) {
+            self.set_state(*binding, Action::Press);
+        }
+        for binding in held_before.difference(&held_after) {
+            self.set_state(*binding, Action::Release);
+        }
+    }
+
+    fn gamepad_bindings_held(&self) -> HashSet<InputBinding> {
+        let threshold = self.map.gamepad.button_threshold;
+        let mut held = HashSet::new();
+
+        for gamepad in &self.gamepads {
+            for button in (0..GAMEPAD_BUTTON_COUNT as i32).filter_map(GamepadButton::from_i32) {
+                if gamepad.is_button_down(button) {
+                    held.insert(InputBinding::GamepadButton(button));
+                }
+            }
+
+            for axis in (0..GAMEPAD_AXIS_COUNT as i32).filter_map(GamepadAxis::from_i32) {
+                for is_positive in [true, false] {
+                    let has_direction = is_positive || !is_trigger(axis);
+                    if has_direction && gamepad.is_axis_past(axis, is_positive, threshold) {
+                        held.insert(InputBinding::GamepadAxis(axis, is_positive));
+                    }
+                }
+            }
+        }
+
+        held
+    }
+
+    pub fn gamepads(&self) -> &[Gamepad] {
+        &self.gamepads
+    }
+
+    pub fn gamepad(&self, id: JoystickId) -> Option<&Gamepad> {
+        self.gamepads.iter().find(|gamepad| gamepad.id == id)
+    }
+
+    /// Gamepads connected or disconnected this frame.
+    pub fn gamepad_events(&self) -> &[GamepadEvent] {
+        &self.gamepad_events
+    }
+
+    /// The axis value from whichever connected gamepad pushes it furthest.
+    pub fn gamepad_axis(&self, axis: GamepadAxis) -> f32 {
+        self.gamepads
+            .iter()
+            .map(|gamepad| gamepad.axis(axis))
+            .fold(0.0, |strongest, value| if value.abs() > strongest.abs() { value } else { strongest })
+    }
+
+    fn set_state(&mut self, binding: InputBinding, action: Action) {
+        match action {
+            Action::Press => {
+                if self.held.insert(binding) {
+                    self.pressed.insert(binding);
+                    self.last_pressed = Some(binding);
+                }
+            }
+            Action::Release => {
+                if self.held.remove(&binding) {
+                    self.released.insert(binding);
+                }
+            }
+            Action::Repeat => {}
+        }
+    }
+
+    pub fn is_held(&self, binding: InputBinding) -> bool {
+        self.held.contains(&binding)
+    }
+
+    pub fn is_pressed(&self, binding: InputBinding) -> bool {
+        self.pressed.contains(&binding)
+    }
+
+    pub fn is_released(&self, binding: InputBinding) -> bool {
+        self.released.contains(&binding)
+    }
+
+    pub fn is_key_held(&self, key: Key) -> bool {
+        self.is_held(InputBinding::Key(key))
+    }
+
+    pub fn is_key_pressed(&self, key: Key) -> bool {
+        self.is_pressed(InputBinding::Key(key))
+    }
+
+    pub fn is_key_released(&self, key: Key) -> bool {
+        self.is_released(InputBinding::Key(key))
+    }
+
+    pub fn is_mouse_button_held(&self, button: MouseButton) -> bool {
+        self.is_held(InputBinding::MouseButton(button))
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.is_pressed(InputBinding::MouseButton(button))
+    }
+
+    pub fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.is_released(InputBinding::MouseButton(button))
+    }
+
+    /// The binding pressed most recently this frame, for "press a key to rebind" prompts.
+    pub fn last_pressed(&self) -> Option<InputBinding> {
+        self.last_pressed
+    }
+
+    pub fn cursor_position(&self) -> Vec2 {
+        self.cursor_position
+    }
+
+    pub fn cursor_delta(&self) -> Vec2 {
+        self.cursor_delta
+    }
+
+    pub fn scroll_delta(&self) -> Vec2 {
+        self.scroll_delta
+    }
+
+    pub fn is_action_held(&self, action: &str) -> bool {
+        self.map
+            .action_bindings(action)
+            .iter()
+            .any(|binding| self.is_held(*binding))
+    }
+
+    /// `true` on the frame the first of the action's bindings goes down.
+    pub fn is_action_pressed(&self, action: &str) -> bool {
+        let bindings = self.map.action_bindings(action);
+        bindings.iter().any(|binding| self.is_pressed(*binding))
+            && bindings
+                .iter()
+                .filter(|binding| self.is_held(**binding))
+                .all(|binding| self.is_pressed(*binding))
+    }
+
+    /// `true` on the frame the last of the action's held bindings goes up.
+    pub fn is_action_released(&self, action: &str) -> bool {
+        let bindings = self.map.action_bindings(action);
+        bindings.iter().any(|binding| self.is_released(*binding))
+            && !bindings.iter().any(|binding| self.is_held(*binding))
+    }
+
+    /// Sum of every binding's contribution to `axis`.
+    pub fn axis(&self, axis: &str) -> f32 {
+        self.map
+            .axis_bindings(axis)
+            .iter()
+            .map(|binding| self.axis_binding_value(binding))
+            .sum()
+    }
+
+    fn axis_binding_value(&self, binding: &AxisBinding) -> f32 {
+        match *binding {
+            AxisBinding::Buttons { positive, negative } => {
+                let is_held = |binding: Option<InputBinding>| binding.is_some_and(|binding| self.is_held(binding));
+                match (is_held(positive), is_held(negative)) {
+                    (true, false) => 1.0,
+                    (false, true) => -1.0,
+                    _ => 0.0,
+                }
+            }
+            AxisBinding::Analog { source, scale } => {
+                let value = match source {
+                    AnalogSource::MouseX => self.cursor_delta.x,
+                    AnalogSource::MouseY => self.cursor_delta.y,
+                    AnalogSource::ScrollX => self.scroll_delta.x,
+                    AnalogSource::ScrollY => self.scroll_delta.y,
+                    AnalogSource::Gamepad(axis) => self.gamepad_axis(axis),
+                };
+                value * scale
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glfw::Modifiers;
+
+    fn key(key: Key, action: Action) -> WindowEvent {
+        WindowEvent::Key(key, 0, action, Modifiers::empty())
+    }
+
+    fn jump_input() -> Input {
+        let mut map = InputMap::new();
+        map.bind_action("jump", InputBinding::Key(Key::Space));
+        map.bind_action("jump", InputBinding::GamepadButton(GamepadButton::ButtonA));
+        Input::new(map)
+    }
+
+    #[test]
+    fn press_is_held_across_frames_until_released() {
+        let mut input = jump_input();
+
+        input.begin_frame();
+        input.handle_event(&key(Key::Space, Action::Press));
+        assert!(input.is_key_pressed(Key::Space) && input.is_key_held(Key::Space));
+        assert!(input.is_action_pressed("jump") && input.is_action_held("jump"));
+        assert_eq!(input.last_pressed(), Some(InputBinding::Key(Key::Space)));
+
+        input.begin_frame();
+        input.handle_event(&key(Key::Space, Action::Repeat));
+        assert!(!input.is_key_pressed(Key::Space) && input.is_key_held(Key::Space));
+        assert!(!input.is_action_pressed("jump") && input.is_action_held("jump"));
+        assert_eq!(input.last_pressed(), None);
+
+        input.begin_frame();
+        input.handle_event(&key(Key::Space, Action::Release));
+        assert!(input.is_key_released(Key::Space) && !input.is_key_held(Key::Space));
+        assert!(input.is_action_released("jump") && !input.is_action_held("jump"));
+
+        input.begin_frame();
+        assert!(!input.is_key_released(Key::Space));
+        assert!(!input.is_action_released("jump"));
+    }
+
+    #[test]
+    fn action_is_pressed_once_and_released_with_its_last_binding() {
+        let mut input = jump_input();
+        let pad = GamepadSnapshot {
+            buttons: {
+                let mut buttons = [false; GAMEPAD_BUTTON_COUNT];
+                buttons[GamepadButton::ButtonA as usize] = true;
+                buttons
+            },
+            ..GamepadSnapshot::default()
+        };
+
+        input.begin_frame();
+        input.handle_event(&key(Key::Space, Action::Press));
+        input.begin_frame();
+        input.set_gamepad_state(JoystickId::Joystick1, Some(("Pad", &pad)));
+        assert!(input.is_pressed(InputBinding::GamepadButton(GamepadButton::ButtonA)));
+        assert!(!input.is_action_pressed("jump"));
+
+        input.begin_frame();
+        input.handle_event(&key(Key::Space, Action::Release));
+        assert!(!input.is_action_released("jump"));
+
+        input.begin_frame();
+        input.set_gamepad_state(JoystickId::Joystick1, Some(("Pad", &GamepadSnapshot::default())));
+        assert!(input.is_action_released("jump"));
+    }
+
+    #[test]
+    fn mouse_buttons_and_cursor_deltas() {
+        let mut input = Input::default();
+
+        input.begin_frame();
+        input.handle_event(&WindowEvent::CursorPos(10.0, 20.0));
+        input.handle_event(&WindowEvent::MouseButton(MouseButton::Button1, Action::Press, Modifiers::empty()));
+        assert_eq!(input.cursor_delta(), Vec2::ZERO);
+        assert!(input.is_mouse_button_pressed(MouseButton::Button1));
+
+        input.begin_frame();
+        input.handle_event(&WindowEvent::CursorPos(13.0, 16.0));
+        input.handle_event(&WindowEvent::Scroll(0.0, 1.0));
+        assert_eq!(input.cursor_delta(), Vec2::new(3.0, -4.0));
+        assert_eq!(input.scroll_delta(), Vec2::new(0.0, 1.0));
+        assert!(input.is_mouse_button_held(MouseButton::Button1));
+
+        input.begin_frame();
+        assert_eq!(input.cursor_delta(), Vec2::ZERO);
+        assert_eq!(input.cursor_position(), Vec2::new(13.0, 16.0));
+    }
+
+    #[test]
+    fn rebound_action_follows_the_new_key() {
+        let mut input = jump_input();
+        assert!(input
+            .map_mut()
+            .rebind_action("jump", InputBinding::Key(Key::Space), InputBinding::Key(Key::J)));
+
+        input.begin_frame();
+        input.handle_event(&key(Key::Space, Action::Press));
+        assert!(!input.is_action_pressed("jump"));
+        input.handle_event(&key(Key::J, Action::Press));
+        assert!(input.is_action_pressed("jump"));
+    }
+
+    #[test]
+    fn focus_loss_releases_keys_but_not_gamepads() {
+        let mut input = jump_input();
+        let mut pad = GamepadSnapshot::default();
+        pad.buttons[GamepadButton::ButtonA as usize] = true;
+
+        input.begin_frame();
+        input.handle_event(&key(Key::W, Action::Press));
+        input.handle_event(&WindowEvent::MouseButton(MouseButton::Button2, Action::Press, Modifiers::empty()));
+        input.set_gamepad_state(JoystickId::Joystick1, Some(("Pad", &pad)));
+
+        input.begin_frame();
+        input.handle_event(&WindowEvent::Focus(false));
+        assert!(input.is_key_released(Key::W) && !input.is_key_held(Key::W));
+        assert!(input.is_mouse_button_released(MouseButton::Button2));
+        assert!(input.is_held(InputBinding::GamepadButton(GamepadButton::ButtonA)));
+
+        input.begin_frame();
+        input.set_gamepad_state(JoystickId::Joystick1, Some(("Pad", &pad)));
+        assert!(input.is_action_held("jump"));
+        assert!(!input.is_action_pressed("jump"));
+
+        input.begin_frame();
+        input.set_gamepad_state(JoystickId::Joystick1, Some(("Pad", &GamepadSnapshot::default())));
+        assert!(input.is_action_released("jump"));
+    }
+
+    #[test]
+    fn gamepad_axis_past_threshold_acts_as_a_button() {
+        let mut map = InputMap::new();
+        map.bind_action("fire", InputBinding::parse("PadRightTrigger").unwrap());
+        let mut input = Input::new(map);
+        let mut pad = GamepadSnapshot::default();
+
+        input.begin_frame();
+        pad.axes[GamepadAxis::AxisRightTrigger as usize] = 0.5;
+        input.set_gamepad_state(JoystickId::Joystick1, Some(("Pad", &pad)));
+        assert_eq!(input.gamepad_events(), [GamepadEvent::Connected(JoystickId::Joystick1, "Pad".to_string())]);
+        assert!(input.is_action_pressed("fire"));
+
+        input.begin_frame();
+        input.set_gamepad_state(JoystickId::Joystick1, None);
+        assert_eq!(input.gamepad_events(), [GamepadEvent::Disconnected(JoystickId::Joystick1)]);
+        assert!(input.is_action_released("fire"));
+        assert!(input.gamepads().is_empty());
+    }
+}
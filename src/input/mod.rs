@@ -0,0 +1,7 @@
+pub mod bindings;
+pub mod gamepad;
+pub mod input;
+
+pub use bindings::*;
+pub use gamepad::*;
+pub use input::Input;
@@ -0,0 +1,38 @@
+//! UranX: a small OpenGL engine built on GLFW.
+//!
+//! A game implements [`Game`] and hands it to [`App::run`]; reusable systems are
+//! [`Plugin`]s registered with [`App::add_plugin`].
+//!
+//! ```no_run
+//! use UranX_engine::{App, FlyCamera, Game};
+//!
+//! struct Sandbox;
+//!
+//! impl Game for Sandbox {
+//!     fn update(&mut self, app: &mut App, delta: f32) {
+//!         let _ = (app, delta);
+//!     }
+//! }
+//!
+//! let mut app = App::new().unwrap();
+//! app.add_plugin(FlyCamera::default());
+//! app.run(Sandbox);
+//! ```
+
+#![allow(
+    non_snake_case,
+    non_camel_case_types,
+    clippy::module_inception,
+    clippy::upper_case_acronyms
+)]
+
+pub mod app;
+pub mod graphics;
+pub mod input;
+pub mod math;
+pub mod scene;
+pub mod utils;
+
+pub use app::{App, FlyCamera, Game, Plugin, Time};
+pub use glfw;
+pub use glfw::WindowEvent;
@@ -1,16 +1,15 @@
-#![allow(non_snake_case, non_camel_case_types)]
-
-mod app;
-mod graphics;
-mod utils;
-
-use crate::app::App;
-use glfw::Context;
+use UranX_engine::utils::errors::error_report;
+use UranX_engine::{App, FlyCamera};
 
 fn main() {
-    let mut app = App::new();
-
-    app.start();
-
-    app.end()
+    let mut app = match App::new() {
+        Ok(app) => app,
+        Err(error) => {
+            eprintln!("{}", error_report(&error));
+            std::process::exit(1);
+        }
+    };
+    app.add_plugin(FlyCamera::default());
+
+    app.run(());
 }
@@ -0,0 +1,540 @@
+use super::{Quat, Vec3, Vec4};
+use std::ops::{Mul, MulAssign};
+
+/// Column-major 3x3 matrix.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat3 {
+    pub cols: [Vec3; 3],
+}
+
+/// Column-major 4x4 matrix, laid out the way OpenGL expects it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub cols: [Vec4; 4],
+}
+
+impl Default for Mat3 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mat3 {
+    pub const IDENTITY: Mat3 = Mat3 {
+        cols: [Vec3::X, Vec3::Y, Vec3::Z],
+    };
+
+    pub const fn from_cols(x: Vec3, y: Vec3, z: Vec3) -> Self {
+        Self { cols: [x, y, z] }
+    }
+
+    pub fn from_cols_array(array: &[f32; 9]) -> Self {
+        Self::from_cols(
+            Vec3::new(array[0], array[1], array[2]),
+            Vec3::new(array[3], array[4], array[5]),
+            Vec3::new(array[6], array[7], array[8]),
+        )
+    }
+
+    pub fn to_cols_array(self) -> [f32; 9] {
+        let [x, y, z] = self.cols;
+        [x.x, x.y, x.z, y.x, y.y, y.z, z.x, z.y, z.z]
+    }
+
+    /// Upper-left 3x3 block of `matrix`.
+    pub fn from_mat4(matrix: Mat4) -> Self {
+        Self::from_cols(
+            matrix.cols[0].truncate(),
+            matrix.cols[1].truncate(),
+            matrix.cols[2].truncate(),
+        )
+    }
+
+    pub fn from_quat(rotation: Quat) -> Self {
+        let Quat { x, y, z, w } = rotation.normalize();
+        let (x2, y2, z2) = (x + x, y + y, z + z);
+        let (xx, yy, zz) = (x * x2, y * y2, z * z2);
+        let (xy, xz, yz) = (x * y2, x * z2, y * z2);
+        let (wx, wy, wz) = (w * x2, w * y2, w * z2);
+
+        Self::from_cols(
+            Vec3::new(1.0 - (yy + zz), xy + wz, xz - wy),
+            Vec3::new(xy - wz, 1.0 - (xx + zz), yz + wx),
+            Vec3::new(xz + wy, yz - wx, 1.0 - (xx + yy)),
+        )
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self::from_cols(
+            Vec3::new(scale.x, 0.0, 0.0),
+            Vec3::new(0.0, scale.y, 0.0),
+            Vec3::new(0.0, 0.0, scale.z),
+        )
+    }
+
+    pub fn row(self, index: usize) -> Vec3 {
+        Vec3::new(self.cols[0][index], self.cols[1][index], self.cols[2][index])
+    }
+
+    pub fn transpose(self) -> Self {
+        Self::from_cols(self.row(0), self.row(1), self.row(2))
+    }
+
+    pub fn determinant(self) -> f32 {
+        let [x, y, z] = self.cols;
+        x.dot(y.cross(z))
+    }
+
+    /// Returns `None` when the matrix is singular. Small but valid matrices, e.g. a uniform
+    /// scale of `0.01`, still invert.
+    pub fn inverse(self) -> Option<Self> {
+        let [x, y, z] = self.cols;
+        let determinant = self.determinant();
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        let inverse_rows = Self::from_cols(y.cross(z), z.cross(x), x.cross(y));
+        let inverse = inverse_rows.transpose() * (1.0 / determinant);
+        inverse.to_cols_array().iter().all(|value| value.is_finite()).then_some(inverse)
+    }
+
+    pub fn abs_diff_eq(self, other: Self, epsilon: f32) -> bool {
+        self.cols
+            .iter()
+            .zip(other.cols.iter())
+            .all(|(a, b)| a.abs_diff_eq(*b, epsilon))
+    }
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4 {
+        cols: [Vec4::X, Vec4::Y, Vec4::Z, Vec4::W],
+    };
+
+    pub const fn from_cols(x: Vec4, y: Vec4, z: Vec4, w: Vec4) -> Self {
+        Self { cols: [x, y, z, w] }
+    }
+
+    pub fn from_cols_array(array: &[f32; 16]) -> Self {
+        Self::from_cols(
+            Vec4::new(array[0], array[1], array[2], array[3]),
+            Vec4::new(array[4], array[5], array[6], array[7]),
+            Vec4::new(array[8], array[9], array[10], array[11]),
+            Vec4::new(array[12], array[13], array[14], array[15]),
+        )
+    }
+
+    /// Column-major array suitable for `glUniformMatrix4fv` with `transpose = GL_FALSE`.
+    pub fn to_cols_array(self) -> [f32; 16] {
+        let mut array = [0.0; 16];
+        for (i, col) in self.cols.iter().enumerate() {
+            array[i * 4..i * 4 + 4].copy_from_slice(&col.to_array());
+        }
+        array
+    }
+
+    pub fn from_mat3(matrix: Mat3) -> Self {
+        Self::from_cols(
+            matrix.cols[0].extend(0.0),
+            matrix.cols[1].extend(0.0),
+            matrix.cols[2].extend(0.0),
+            Vec4::W,
+        )
+    }
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        let mut matrix = Self::IDENTITY;
+        matrix.cols[3] = translation.extend(1.0);
+        matrix
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self::from_mat3(Mat3::from_scale(scale))
+    }
+
+    pub fn from_quat(rotation: Quat) -> Self {
+        Self::from_mat3(Mat3::from_quat(rotation))
+    }
+
+    /// Rotation of `angle` radians about `axis`.
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+        Self::from_quat(Quat::from_axis_angle(axis, angle))
+    }
+
+    /// Equivalent to `T * R * S`.
+    pub fn from_scale_rotation_translation(scale: Vec3, rotation: Quat, translation: Vec3) -> Self {
+        let rotation = Mat3::from_quat(rotation);
+        Self::from_cols(
+            (rotation.cols[0] * scale.x).extend(0.0),
+            (rotation.cols[1] * scale.y).extend(0.0),
+            (rotation.cols[2] * scale.z).extend(0.0),
+            translation.extend(1.0),
+        )
+    }
+
+    /// Splits an affine `T * R * S` matrix back into its parts. A mirroring matrix gets a
+    /// negative X scale; shear is lost.
+    pub fn to_scale_rotation_translation(self) -> (Vec3, Quat, Vec3) {
+        let basis = Mat3::from_mat4(self);
+        let sign = if basis.determinant() < 0.0 { -1.0 } else { 1.0 };
+        let scale = Vec3::new(
+            basis.cols[0].length() * sign,
+            basis.cols[1].length(),
+            basis.cols[2].length(),
+        );
+
+        let rotation = if scale.x == 0.0 || scale.y == 0.0 || scale.z == 0.0 {
+            Quat::IDENTITY
+        } else {
+            Quat::from_mat3(Mat3::from_cols(
+                basis.cols[0] / scale.x,
+                basis.cols[1] / scale.y,
+                basis.cols[2] / scale.z,
+            ))
+        };
+
+        (scale, rotation, self.translation())
+    }
+
+    /// Right-handed perspective projection with OpenGL's `[-1, 1]` clip depth.
+    /// `fov_y` is in radians.
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let f = 1.0 / (fov_y / 2.0).tan();
+        let range = near - far;
+
+        Self::from_cols(
+            Vec4::new(f / aspect, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, f, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, (far + near) / range, -1.0),
+            Vec4::new(0.0, 0.0, (2.0 * far * near) / range, 0.0),
+        )
+    }
+
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        let width = right - left;
+        let height = top - bottom;
+        let depth = far - near;
+
+        Self::from_cols(
+            Vec4::new(2.0 / width, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 2.0 / height, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, -2.0 / depth, 0.0),
+            Vec4::new(
+                -(right + left) / width,
+                -(top + bottom) / height,
+                -(far + near) / depth,
+                1.0,
+            ),
+        )
+    }
+
+    /// Right-handed view matrix looking from `eye` towards `target`.
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let forward = (eye - target).normalize();
+        let right = up.cross(forward).normalize();
+        let up = forward.cross(right);
+
+        Self::from_cols(
+            Vec4::new(right.x, up.x, forward.x, 0.0),
+            Vec4::new(right.y, up.y, forward.y, 0.0),
+            Vec4::new(right.z, up.z, forward.z, 0.0),
+            Vec4::new(-right.dot(eye), -up.dot(eye), -forward.dot(eye), 1.0),
+        )
+    }
+
+    pub fn row(self, index: usize) -> Vec4 {
+        Vec4::new(
+            self.cols[0][index],
+            self.cols[1][index],
+            self.cols[2][index],
+            self.cols[3][index],
+        )
+    }
+
+    pub fn transpose(self) -> Self {
+        Self::from_cols(self.row(0), self.row(1), self.row(2), self.row(3))
+    }
+
+    pub fn determinant(self) -> f32 {
+        let m = self.to_cols_array();
+        let cofactors = Self::cofactors(&m);
+        m[0] * cofactors[0] + m[1] * cofactors[4] + m[2] * cofactors[8] + m[3] * cofactors[12]
+    }
+
+    /// Returns `None` when the matrix is singular. Small but valid matrices, e.g. a uniform
+    /// scale of `0.01`, still invert.
+    pub fn inverse(self) -> Option<Self> {
+        let m = self.to_cols_array();
+        let cofactors = Self::cofactors(&m);
+        let determinant =
+            m[0] * cofactors[0] + m[1] * cofactors[4] + m[2] * cofactors[8] + m[3] * cofactors[12];
+
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        let inverse_determinant = 1.0 / determinant;
+        let inverse = cofactors.map(|c| c * inverse_determinant);
+        inverse.iter().all(|value| value.is_finite()).then(|| Self::from_cols_array(&inverse))
+    }
+
+    /// Transposed cofactor matrix (the adjugate) of a column-major 4x4 array.
+    fn cofactors(m: &[f32; 16]) -> [f32; 16] {
+        let mut inv = [0.0; 16];
+
+        inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+            + m[9] * m[7] * m[14]
+            + m[13] * m[6] * m[11]
+            - m[13] * m[7] * m[10];
+        inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+            - m[8] * m[7] * m[14]
+            - m[12] * m[6] * m[11]
+            + m[12] * m[7] * m[10];
+        inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+            + m[8] * m[7] * m[13]
+            + m[12] * m[5] * m[11]
+            - m[12] * m[7] * m[9];
+        inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+            - m[8] * m[6] * m[13]
+            - m[12] * m[5] * m[10]
+            + m[12] * m[6] * m[9];
+        inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+            - m[9] * m[3] * m[14]
+            - m[13] * m[2] * m[11]
+            + m[13] * m[3] * m[10];
+        inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+            + m[8] * m[3] * m[14]
+            + m[12] * m[2] * m[11]
+            - m[12] * m[3] * m[10];
+        inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+            - m[8] * m[3] * m[13]
+            - m[12] * m[1] * m[11]
+            + m[12] * m[3] * m[9];
+        inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+            + m[8] * m[2] * m[13]
+            + m[12] * m[1] * m[10]
+            - m[12] * m[2] * m[9];
+        inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+            + m[5] * m[3] * m[14]
+            + m[13] * m[2] * m[7]
+            - m[13] * m[3] * m[6];
+        inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+            - m[4] * m[3] * m[14]
+            - m[12] * m[2] * m[7]
+            + m[12] * m[3] * m[6];
+        inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+            + m[4] * m[3] * m[13]
+            + m[12] * m[1] * m[7]
+            - m[12] * m[3] * m[5];
+        inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+            - m[4] * m[2] * m[13]
+            - m[12] * m[1] * m[6]
+            + m[12] * m[2] * m[5];
+        inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+            - m[5] * m[3] * m[10]
+            - m[9] * m[2] * m[7]
+            + m[9] * m[3] * m[6];
+        inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+            + m[4] * m[3] * m[10]
+            + m[8] * m[2] * m[7]
+            - m[8] * m[3] * m[6];
+        inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+            - m[4] * m[3] * m[9]
+            - m[8] * m[1] * m[7]
+            + m[8] * m[3] * m[5];
+        inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+            + m[4] * m[2] * m[9]
+            + m[8] * m[1] * m[6]
+            - m[8] * m[2] * m[5];
+
+        inv
+    }
+
+    pub fn translation(self) -> Vec3 {
+        self.cols[3].truncate()
+    }
+
+    pub fn transform_point3(self, point: Vec3) -> Vec3 {
+        (self * point.extend(1.0)).truncate()
+    }
+
+    pub fn transform_vector3(self, vector: Vec3) -> Vec3 {
+        (self * vector.extend(0.0)).truncate()
+    }
+
+    pub fn abs_diff_eq(self, other: Self, epsilon: f32) -> bool {
+        self.cols
+            .iter()
+            .zip(other.cols.iter())
+            .all(|(a, b)| a.abs_diff_eq(*b, epsilon))
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::from_cols(self * other.cols[0], self * other.cols[1], self * other.cols[2])
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, vector: Vec3) -> Vec3 {
+        self.cols[0] * vector.x + self.cols[1] * vector.y + self.cols[2] * vector.z
+    }
+}
+
+impl Mul<f32> for Mat3 {
+    type Output = Self;
+
+    fn mul(self, scalar: f32) -> Self {
+        Self {
+            cols: self.cols.map(|col| col * scalar),
+        }
+    }
+}
+
+impl MulAssign for Mat3 {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::from_cols(
+            self * other.cols[0],
+            self * other.cols[1],
+            self * other.cols[2],
+            self * other.cols[3],
+        )
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+
+    fn mul(self, vector: Vec4) -> Vec4 {
+        self.cols[0] * vector.x
+            + self.cols[1] * vector.y
+            + self.cols[2] * vector.z
+            + self.cols[3] * vector.w
+    }
+}
+
+impl Mul<f32> for Mat4 {
+    type Output = Self;
+
+    fn mul(self, scalar: f32) -> Self {
+        Self {
+            cols: self.cols.map(|col| col * scalar),
+        }
+    }
+}
+
+impl MulAssign for Mat4 {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl From<[f32; 16]> for Mat4 {
+    fn from(array: [f32; 16]) -> Self {
+        Self::from_cols_array(&array)
+    }
+}
+
+impl From<Mat4> for [f32; 16] {
+    fn from(matrix: Mat4) -> Self {
+        matrix.to_cols_array()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::EPSILON;
+
+    /// A spread of invertible affine matrices, including a mirror and a tiny uniform scale.
+    fn samples() -> Vec<Mat4> {
+        let mut samples = vec![Mat4::IDENTITY, Mat4::from_scale(Vec3::splat(0.01)), Mat4::from_scale(Vec3::new(-1.0, 2.0, 0.5))];
+        for i in 0..8 {
+            let t = i as f32;
+            samples.push(Mat4::from_scale_rotation_translation(
+                Vec3::new(0.5 + t * 0.3, 1.0 + t * 0.1, 2.0 - t * 0.2),
+                Quat::from_axis_angle(Vec3::new(1.0, t, -0.5 * t), 0.4 * t + 0.1),
+                Vec3::new(t, -2.0 * t, 3.0),
+            ));
+        }
+        samples.push(Mat4::perspective(1.0, 16.0 / 9.0, 0.1, 100.0));
+        samples
+    }
+
+    #[test]
+    fn inverse_times_matrix_is_identity() {
+        for matrix in samples() {
+            let inverse = matrix.inverse().expect("sample matrices are invertible");
+            assert!((inverse * matrix).abs_diff_eq(Mat4::IDENTITY, 1.0e-4), "{:?}", matrix);
+            assert!((matrix * inverse).abs_diff_eq(Mat4::IDENTITY, 1.0e-4), "{:?}", matrix);
+
+            let basis = Mat3::from_mat4(matrix);
+            let basis_inverse = basis.inverse().expect("sample bases are invertible");
+            assert!((basis_inverse * basis).abs_diff_eq(Mat3::IDENTITY, 1.0e-4), "{:?}", basis);
+        }
+    }
+
+    #[test]
+    fn small_scale_inverts() {
+        let inverse = Mat4::from_scale(Vec3::splat(0.01)).inverse().unwrap();
+        assert!(inverse.abs_diff_eq(Mat4::from_scale(Vec3::splat(100.0)), 1.0e-3));
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert_eq!(Mat4::from_scale(Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+        assert_eq!(Mat3::from_scale(Vec3::new(1.0, 1.0, 0.0)).inverse(), None);
+        assert_eq!(Mat4::from_cols_array(&[f32::NAN; 16]).inverse(), None);
+    }
+
+    #[test]
+    fn look_at_basis_is_orthonormal() {
+        let eye = Vec3::new(3.0, 4.0, 5.0);
+        let target = Vec3::new(-1.0, 0.5, 2.0);
+        let view = Mat4::look_at(eye, target, Vec3::Y);
+
+        let basis = Mat3::from_mat4(view);
+        assert!((basis * basis.transpose()).abs_diff_eq(Mat3::IDENTITY, EPSILON));
+        assert!((basis.determinant() - 1.0).abs() < EPSILON);
+
+        assert!(view.transform_point3(eye).abs_diff_eq(Vec3::ZERO, 1.0e-4));
+        let forward = view.transform_vector3((target - eye).normalize());
+        assert!(forward.abs_diff_eq(-Vec3::Z, EPSILON));
+        assert!(view.transform_vector3(Vec3::Y).x.abs() < EPSILON);
+    }
+
+    #[test]
+    fn scale_rotation_translation_round_trips() {
+        let scale = Vec3::new(2.0, 0.5, 3.0);
+        let rotation = Quat::from_axis_angle(Vec3::new(1.0, 2.0, 3.0), 0.7);
+        let translation = Vec3::new(1.0, -2.0, 3.0);
+        let matrix = Mat4::from_scale_rotation_translation(scale, rotation, translation);
+
+        let (out_scale, out_rotation, out_translation) = matrix.to_scale_rotation_translation();
+        assert!(out_scale.abs_diff_eq(scale, EPSILON));
+        assert!(out_rotation.abs_diff_eq(rotation, EPSILON));
+        assert!(out_translation.abs_diff_eq(translation, EPSILON));
+    }
+}
@@ -0,0 +1,13 @@
+mod mat;
+mod quat;
+mod vec;
+
+pub use mat::*;
+pub use quat::*;
+pub use vec::*;
+
+pub const EPSILON: f32 = 1.0e-5;
+
+pub fn approx_eq(a: f32, b: f32, epsilon: f32) -> bool {
+    (a - b).abs() <= epsilon * a.abs().max(b.abs()).max(1.0)
+}
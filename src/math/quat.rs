@@ -0,0 +1,288 @@
+use super::{Mat3, Mat4, Vec3, Vec4};
+use serde_derive::{Deserialize, Serialize};
+use std::ops::{Mul, MulAssign};
+
+/// Unit quaternion representing a rotation.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(from = "[f32; 4]", into = "[f32; 4]")]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Quat {
+    pub const IDENTITY: Quat = Quat {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+        w: 1.0,
+    };
+
+    pub const fn from_xyzw(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
+    /// Rotation of `angle` radians about `axis`.
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle * 0.5).sin_cos();
+        Self::from_xyzw(axis.x * sin, axis.y * sin, axis.z * sin, cos)
+    }
+
+    /// Applies `yaw` about Y, then `pitch` about X, then `roll` about Z (radians).
+    pub fn from_euler(yaw: f32, pitch: f32, roll: f32) -> Self {
+        Self::from_axis_angle(Vec3::Y, yaw)
+            * Self::from_axis_angle(Vec3::X, pitch)
+            * Self::from_axis_angle(Vec3::Z, roll)
+    }
+
+    /// Extracts the rotation from a matrix whose columns are orthonormal.
+    pub fn from_mat3(matrix: Mat3) -> Self {
+        let [x, y, z] = matrix.cols;
+        let trace = x.x + y.y + z.z;
+
+        let quat = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self::from_xyzw((y.z - z.y) / s, (z.x - x.z) / s, (x.y - y.x) / s, 0.25 * s)
+        } else if x.x > y.y && x.x > z.z {
+            let s = (1.0 + x.x - y.y - z.z).sqrt() * 2.0;
+            Self::from_xyzw(0.25 * s, (y.x + x.y) / s, (z.x + x.z) / s, (y.z - z.y) / s)
+        } else if y.y > z.z {
+            let s = (1.0 + y.y - x.x - z.z).sqrt() * 2.0;
+            Self::from_xyzw((y.x + x.y) / s, 0.25 * s, (z.y + y.z) / s, (z.x - x.z) / s)
+        } else {
+            let s = (1.0 + z.z - x.x - y.y).sqrt() * 2.0;
+            Self::from_xyzw((z.x + x.z) / s, (z.y + y.z) / s, 0.25 * s, (x.y - y.x) / s)
+        };
+
+        quat.normalize()
+    }
+
+    /// Rotation that turns `-Z` towards `direction` while keeping `up` as close as possible.
+    pub fn look_rotation(direction: Vec3, up: Vec3) -> Self {
+        let back = (-direction).normalize();
+        let right = up.cross(back).normalize();
+        let up = back.cross(right);
+        Self::from_mat3(Mat3::from_cols(right, up, back))
+    }
+
+    pub fn to_axis_angle(self) -> (Vec3, f32) {
+        let quat = self.normalize();
+        let angle = 2.0 * quat.w.clamp(-1.0, 1.0).acos();
+        let sin = (1.0 - quat.w * quat.w).max(0.0).sqrt();
+
+        if sin < 1.0e-6 {
+            (Vec3::X, angle)
+        } else {
+            (Vec3::new(quat.x, quat.y, quat.z) / sin, angle)
+        }
+    }
+
+    pub fn to_mat3(self) -> Mat3 {
+        Mat3::from_quat(self)
+    }
+
+    pub fn to_mat4(self) -> Mat4 {
+        Mat4::from_quat(self)
+    }
+
+    pub fn dot(self, other: Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// Returns a unit-length copy, or identity if the quaternion has no length.
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length != 0.0 {
+            Self::from_xyzw(self.x / length, self.y / length, self.z / length, self.w / length)
+        } else {
+            Self::IDENTITY
+        }
+    }
+
+    pub fn conjugate(self) -> Self {
+        Self::from_xyzw(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn inverse(self) -> Self {
+        let length_squared = self.dot(self);
+        if length_squared == 0.0 {
+            return Self::IDENTITY;
+        }
+
+        let conjugate = self.conjugate();
+        Self::from_xyzw(
+            conjugate.x / length_squared,
+            conjugate.y / length_squared,
+            conjugate.z / length_squared,
+            conjugate.w / length_squared,
+        )
+    }
+
+    /// Spherical interpolation along the shortest arc.
+    pub fn slerp(self, other: Self, t: f32) -> Self {
+        let mut other = other;
+        let mut cos = self.dot(other);
+
+        if cos < 0.0 {
+            other = Self::from_xyzw(-other.x, -other.y, -other.z, -other.w);
+            cos = -cos;
+        }
+
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+
+        Self::from_xyzw(
+            self.x * a + other.x * b,
+            self.y * a + other.y * b,
+            self.z * a + other.z * b,
+            self.w * a + other.w * b,
+        )
+        .normalize()
+    }
+
+    pub fn abs_diff_eq(self, other: Self, epsilon: f32) -> bool {
+        Vec4::from(self).abs_diff_eq(Vec4::from(other), epsilon)
+    }
+}
+
+impl Mul for Quat {
+    type Output = Self;
+
+    /// Composes rotations: `(a * b) * v == a * (b * v)`.
+    fn mul(self, other: Self) -> Self {
+        Self::from_xyzw(
+            self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+            self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+        )
+    }
+}
+
+impl Mul<Vec3> for Quat {
+    type Output = Vec3;
+
+    fn mul(self, vector: Vec3) -> Vec3 {
+        let axis = Vec3::new(self.x, self.y, self.z);
+        let t = axis.cross(vector) * 2.0;
+        vector + t * self.w + axis.cross(t)
+    }
+}
+
+impl MulAssign for Quat {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl From<Quat> for Vec4 {
+    fn from(quat: Quat) -> Self {
+        Vec4::new(quat.x, quat.y, quat.z, quat.w)
+    }
+}
+
+impl From<Vec4> for Quat {
+    fn from(vector: Vec4) -> Self {
+        Quat::from_xyzw(vector.x, vector.y, vector.z, vector.w)
+    }
+}
+
+impl From<[f32; 4]> for Quat {
+    fn from(array: [f32; 4]) -> Self {
+        Quat::from_xyzw(array[0], array[1], array[2], array[3])
+    }
+}
+
+impl From<Quat> for [f32; 4] {
+    fn from(quat: Quat) -> Self {
+        [quat.x, quat.y, quat.z, quat.w]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::EPSILON;
+
+    fn samples() -> Vec<Quat> {
+        let mut samples = vec![Quat::IDENTITY, Quat::from_axis_angle(Vec3::X, std::f32::consts::PI)];
+        for i in 0..12 {
+            let t = i as f32;
+            samples.push(Quat::from_axis_angle(Vec3::new(t.sin(), 1.0 - t * 0.2, t.cos()), t * 0.55 - 3.0));
+        }
+        samples
+    }
+
+    /// `q` and `-q` are the same rotation.
+    fn same_rotation(a: Quat, b: Quat) -> bool {
+        a.dot(b).abs() > 1.0 - EPSILON
+    }
+
+    #[test]
+    fn quat_matrix_round_trip() {
+        for quat in samples() {
+            let matrix = quat.to_mat3();
+            assert!(same_rotation(Quat::from_mat3(matrix), quat), "{:?}", quat);
+            assert!(Quat::from_mat3(matrix).to_mat3().abs_diff_eq(matrix, EPSILON));
+        }
+    }
+
+    #[test]
+    fn matrix_rotates_like_quat() {
+        let vector = Vec3::new(0.3, -1.2, 2.0);
+        for quat in samples() {
+            assert!((quat * vector).abs_diff_eq(quat.to_mat3() * vector, 1.0e-4));
+            assert!((quat * vector).abs_diff_eq(quat.to_mat4().transform_vector3(vector), 1.0e-4));
+        }
+    }
+
+    #[test]
+    fn composition_matches_matrix_product() {
+        let quats = samples();
+        for pair in quats.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert!((a * b).to_mat3().abs_diff_eq(a.to_mat3() * b.to_mat3(), 1.0e-4));
+        }
+    }
+
+    #[test]
+    fn axis_angle_round_trip() {
+        let quat = Quat::from_axis_angle(Vec3::new(0.0, 3.0, 4.0), 1.25);
+        let (axis, angle) = quat.to_axis_angle();
+        assert!(axis.abs_diff_eq(Vec3::new(0.0, 0.6, 0.8), EPSILON));
+        assert!((angle - 1.25).abs() < EPSILON);
+    }
+
+    #[test]
+    fn inverse_undoes_rotation() {
+        for quat in samples() {
+            assert!(same_rotation(quat * quat.inverse(), Quat::IDENTITY));
+        }
+    }
+
+    #[test]
+    fn look_rotation_turns_forward_to_direction() {
+        let direction = Vec3::new(1.0, -0.5, 2.0).normalize();
+        let rotation = Quat::look_rotation(direction, Vec3::Y);
+        assert!((rotation * -Vec3::Z).abs_diff_eq(direction, EPSILON));
+    }
+}
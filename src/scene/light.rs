@@ -0,0 +1,60 @@
+use crate::math::Vec3;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LightKind {
+    /// Infinitely far light shining along the node's forward axis.
+    Directional,
+    /// Light radiating from the node's position up to `range`.
+    Point { range: f32 },
+    /// Cone along the node's forward axis; angles are in radians.
+    Spot {
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Light {
+    #[serde(flatten)]
+    pub kind: LightKind,
+    pub color: Vec3,
+    pub intensity: f32,
+}
+
+impl Light {
+    pub fn directional(color: Vec3, intensity: f32) -> Self {
+        Self {
+            kind: LightKind::Directional,
+            color,
+            intensity,
+        }
+    }
+
+    pub fn point(color: Vec3, intensity: f32, range: f32) -> Self {
+        Self {
+            kind: LightKind::Point { range },
+            color,
+            intensity,
+        }
+    }
+
+    pub fn spot(color: Vec3, intensity: f32, range: f32, inner_angle: f32, outer_angle: f32) -> Self {
+        Self {
+            kind: LightKind::Spot {
+                range,
+                inner_angle,
+                outer_angle,
+            },
+            color,
+            intensity,
+        }
+    }
+
+    /// Color premultiplied by intensity, as the shaders consume it.
+    pub fn radiance(&self) -> Vec3 {
+        self.color * self.intensity
+    }
+}
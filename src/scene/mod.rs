@@ -0,0 +1,6 @@
+pub mod light;
+pub mod scene;
+pub mod scene_file;
+pub mod transform;
+
+pub use scene::*;
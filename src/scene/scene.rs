@@ -0,0 +1,299 @@
+use super::light::Light;
+use super::transform::{Transform, TransformId, TransformTree};
+use crate::graphics::camera::Camera;
+use crate::graphics::material::Material;
+use crate::graphics::model::Model;
+use crate::graphics::shader_program::ShaderProgram;
+use crate::graphics::uniform_buffer::{ObjectData, UniformBuffer};
+use crate::math::Mat4;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub type NodeId = TransformId;
+
+/// Payload of a scene node. A node may carry any combination of a model, a camera and a light;
+/// its placement lives in the scene's transform tree.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub model: Option<Rc<Model>>,
+    pub camera: Option<Camera>,
+    pub light: Option<Light>,
+    pub material: Option<String>,
+    pub is_visible: bool,
+}
+
+impl Node {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            model: None,
+            camera: None,
+            light: None,
+            material: None,
+            is_visible: true,
+        }
+    }
+
+    pub fn with_model(mut self, model: Rc<Model>) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    pub fn with_camera(mut self, camera: Camera) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    pub fn with_light(mut self, light: Light) -> Self {
+        self.light = Some(light);
+        self
+    }
+
+    pub fn with_material(mut self, material: &str) -> Self {
+        self.material = Some(material.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Scene {
+    transforms: TransformTree,
+    nodes: HashMap<NodeId, Node>,
+    active_camera: Option<NodeId>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, node: Node, transform: Transform) -> NodeId {
+        let id = self.transforms.insert(transform);
+        if node.camera.is_some() && self.active_camera.is_none() {
+            self.active_camera = Some(id);
+        }
+        self.nodes.insert(id, node);
+        id
+    }
+
+    /// Adds `node` below `parent`. Returns `None`, without adding anything, if `parent` is
+    /// not in the scene.
+    pub fn add_child(&mut self, parent: NodeId, node: Node, transform: Transform) -> Option<NodeId> {
+        if !self.contains(parent) {
+            return None;
+        }
+
+        let id = self.add(node, transform);
+        self.transforms.set_parent(id, Some(parent));
+        Some(id)
+    }
+
+    /// Removes a node together with everything below it and returns the node itself.
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        let mut subtree = self.descendants(id);
+        subtree.push(id);
+
+        let mut removed = None;
+        for node_id in subtree.into_iter().rev() {
+            self.transforms.remove(node_id);
+            let node = self.nodes.remove(&node_id);
+            if self.active_camera == Some(node_id) {
+                self.active_camera = None;
+            }
+            if node_id == id {
+                removed = node;
+            }
+        }
+
+        removed
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes.contains_key(&id)
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(&id)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.get_mut(&id)
+    }
+
+    pub fn transform(&self, id: NodeId) -> Option<&Transform> {
+        self.transforms.get(id)
+    }
+
+    pub fn transform_mut(&mut self, id: NodeId) -> Option<&mut Transform> {
+        self.transforms.get_mut(id)
+    }
+
+    pub fn transforms(&self) -> &TransformTree {
+        &self.transforms
+    }
+
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> bool {
+        self.transforms.set_parent(id, parent)
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.transforms.get(id)?.parent()
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.transforms
+            .get(id)
+            .map(|transform| transform.children())
+            .unwrap_or(&[])
+    }
+
+    /// First node with the given name, in depth-first order.
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.walk().find(|id| self.nodes[id].name == name)
+    }
+
+    /// Node addressed by a `/`-separated path of names from a root, e.g. `"player/camera"`.
+    pub fn find_path(&self, path: &str) -> Option<NodeId> {
+        let mut candidates: Vec<NodeId> = self.roots().collect();
+        let mut found = None;
+
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            let id = *candidates.iter().find(|id| self.nodes[*id].name == name)?;
+            candidates = self.children(id).to_vec();
+            found = Some(id);
+        }
+
+        found
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.transforms.roots()
+    }
+
+    /// All nodes in depth-first order, parents before children.
+    pub fn walk(&self) -> impl Iterator<Item = NodeId> + '_ {
+        let mut stack: Vec<NodeId> = self.roots().collect();
+        stack.reverse();
+
+        std::iter::from_fn(move || {
+            let id = stack.pop()?;
+            stack.extend(self.children(id).iter().rev());
+            Some(id)
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.walk().map(|id| (id, &self.nodes[&id]))
+    }
+
+    pub fn descendants(&self, id: NodeId) -> Vec<NodeId> {
+        let mut result = Vec::new();
+        let mut stack = self.children(id).to_vec();
+        while let Some(child) = stack.pop() {
+            result.push(child);
+            stack.extend_from_slice(self.children(child));
+        }
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn cameras(&self) -> impl Iterator<Item = (NodeId, &Camera)> {
+        self.iter()
+            .filter_map(|(id, node)| Some((id, node.camera.as_ref()?)))
+    }
+
+    pub fn lights(&self) -> impl Iterator<Item = (NodeId, &Light)> {
+        self.iter()
+            .filter_map(|(id, node)| Some((id, node.light.as_ref()?)))
+    }
+
+    pub fn active_camera(&self) -> Option<NodeId> {
+        self.active_camera
+    }
+
+    pub fn set_active_camera(&mut self, id: NodeId) -> bool {
+        let has_camera = self
+            .nodes
+            .get(&id)
+            .is_some_and(|node| node.camera.is_some());
+
+        if has_camera {
+            self.active_camera = Some(id);
+        }
+        has_camera
+    }
+
+    /// View matrix of a camera node. The camera's own pose is taken relative to its node.
+    pub fn view_matrix(&self, id: NodeId) -> Option<Mat4> {
+        let camera = self.nodes.get(&id)?.camera.as_ref()?;
+        let node_world = self.transforms.get(id)?.world_matrix();
+        Some(camera.view_matrix() * node_world.inverse()?)
+    }
+
+    pub fn update(&mut self) {
+        self.transforms.update();
+    }
+
+    /// Draws every visible node that carries a model, uploading its world matrix to
+    /// `object_data` first. A node's `material` is looked up in `materials` and replaces the
+    /// model's own ones; `shader` draws every material without a shader. Call
+    /// [`Scene::update`] first.
+    pub fn draw(
+        &self,
+        shader: &ShaderProgram,
+        materials: &HashMap<String, Rc<Material>>,
+        object_data: &mut UniformBuffer<ObjectData>,
+    ) {
+        for (id, node) in self.iter() {
+            if !node.is_visible {
+                continue;
+            }
+
+            if let Some(model) = &node.model {
+                object_data.update(&ObjectData::new(self.transforms.get(id).unwrap().world_matrix()));
+                let material = node.material.as_ref().and_then(|name| materials.get(name));
+                model.draw(shader, material.map(|material| material.as_ref()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3;
+
+    #[test]
+    fn add_child_needs_a_parent_in_the_scene() {
+        let mut scene = Scene::new();
+        let parent = scene.add(Node::new("parent"), Transform::new());
+        let child = scene.add_child(parent, Node::new("child"), Transform::new()).unwrap();
+        assert_eq!(scene.parent(child), Some(parent));
+        assert_eq!(scene.find_path("parent/child"), Some(child));
+
+        scene.remove(parent);
+        assert_eq!(scene.add_child(parent, Node::new("orphan"), Transform::new()), None);
+        assert!(scene.is_empty());
+    }
+
+    #[test]
+    fn first_camera_becomes_active() {
+        let mut scene = Scene::new();
+        let camera = || Node::new("camera").with_camera(Camera::new(Vec3::ZERO, Vec3::Y, 0.3, 0.0, 0.0));
+        let first = scene.add(camera(), Transform::new());
+        let second = scene.add_child(first, camera(), Transform::new()).unwrap();
+        assert_eq!(scene.active_camera(), Some(first));
+
+        assert!(scene.set_active_camera(second));
+        scene.remove(first);
+        assert_eq!(scene.active_camera(), None);
+    }
+}
@@ -0,0 +1,505 @@
+use super::light::Light;
+use super::scene::{Node, NodeId, Scene};
+use super::transform::Transform;
+use crate::graphics::camera::Camera;
+use crate::graphics::material::{Material, MaterialValue, TextureSlot};
+use crate::graphics::model::Model;
+use crate::graphics::shader_program::ShaderProgram;
+use crate::graphics::texture::Texture;
+use crate::math::{Quat, Vec3};
+use crate::utils::errors::ERRUranXError;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::rc::Rc;
+
+/// Declarative description of a scene, stored as TOML.
+///
+/// ```toml
+/// name = "demo"
+/// active_camera = "camera"
+///
+/// [shader]
+/// vertex = "res/shaders/shd.vert"
+/// fragment = "res/shaders/shd.frag"
+///
+/// [[materials]]
+/// name = "backpack"
+/// textures = { albedo = "res/objects/diffuse.jpg" }
+///
+/// [[nodes]]
+/// name = "backpack"
+/// model = "res/objects/backpack.obj"
+/// material = "backpack"
+/// transform = { translation = [0.0, 0.0, -3.0] }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SceneFile {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_camera: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shader: Option<ShaderDesc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub materials: Vec<MaterialDesc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<NodeDesc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShaderDesc {
+    pub vertex: String,
+    pub fragment: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct MaterialDesc {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shader: Option<ShaderDesc>,
+    /// Scalar and vector parameters; a single element is a scalar.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parameters: BTreeMap<String, Vec<f32>>,
+    /// Texture path per slot name (`albedo`, `normal`, ...).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub textures: BTreeMap<String, String>,
+}
+
+impl MaterialDesc {
+    /// Creates the material, asking `load_shader` for its shader if it names one and
+    /// `load_texture` for each texture path. Unknown slot names and parameters that aren't
+    /// 1 to 4 floats are errors.
+    pub fn build(
+        &self,
+        load_shader: impl FnOnce(&ShaderDesc) -> Result<Rc<ShaderProgram>, ERRUranXError>,
+        mut load_texture: impl FnMut(&str, TextureSlot) -> Result<Rc<Texture>, ERRUranXError>,
+    ) -> Result<Material, ERRUranXError> {
+        let mut material = Material::new(&self.name);
+        let error = |message: String| ERRUranXError::INVALID_MATERIAL {
+            material: self.name.clone(),
+            message,
+        };
+
+        if let Some(shader) = &self.shader {
+            material.set_shader(Some(load_shader(shader)?));
+        }
+
+        for (name, values) in &self.parameters {
+            let value = MaterialValue::from_floats(values)
+                .ok_or_else(|| error(format!("parameter `{}` must have 1 to 4 components, not {}", name, values.len())))?;
+            material.set_parameter(name, value);
+        }
+
+        for (slot_name, path) in &self.textures {
+            let slot = TextureSlot::from_name(slot_name).ok_or_else(|| {
+                let slots: Vec<&str> = TextureSlot::ALL.iter().map(|slot| slot.name()).collect();
+                error(format!("unknown texture slot `{}`, expected one of {}", slot_name, slots.join(", ")))
+            })?;
+            material.set_texture(slot, load_texture(path, slot)?);
+        }
+
+        Ok(material)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeDesc {
+    pub name: String,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub visible: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<CameraDesc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub light: Option<Light>,
+    #[serde(default)]
+    pub transform: TransformDesc,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<NodeDesc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TransformDesc {
+    #[serde(default)]
+    pub translation: Vec3,
+    #[serde(default)]
+    pub rotation: Quat,
+    #[serde(default = "default_scale")]
+    pub scale: Vec3,
+}
+
+/// Camera lens settings and the camera's own pose, which is relative to its node's transform
+/// and is what [`FlyCamera`](crate::app::FlyCamera) moves.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraDesc {
+    #[serde(default)]
+    pub position: Vec3,
+    /// Degrees about `+Y`; `-90` looks down `-Z`.
+    #[serde(default = "default_yaw")]
+    pub yaw: f32,
+    /// Degrees up from the horizon, within `[-89, 89]`.
+    #[serde(default)]
+    pub pitch: f32,
+    /// Vertical field of view in degrees.
+    #[serde(default = "default_fov")]
+    pub fov: f32,
+    #[serde(default = "default_near")]
+    pub near: f32,
+    #[serde(default = "default_far")]
+    pub far: f32,
+    #[serde(default = "default_sensitivity")]
+    pub sensitivity: f32,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn default_scale() -> Vec3 {
+    Vec3::ONE
+}
+
+fn default_yaw() -> f32 {
+    -90.0
+}
+
+fn default_fov() -> f32 {
+    45.0
+}
+
+fn default_near() -> f32 {
+    0.1
+}
+
+fn default_far() -> f32 {
+    100.0
+}
+
+fn default_sensitivity() -> f32 {
+    0.3
+}
+
+impl Default for TransformDesc {
+    fn default() -> Self {
+        Self {
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+        }
+    }
+}
+
+impl Default for CameraDesc {
+    fn default() -> Self {
+        Self {
+            position: Vec3::ZERO,
+            yaw: default_yaw(),
+            pitch: 0.0,
+            fov: default_fov(),
+            near: default_near(),
+            far: default_far(),
+            sensitivity: default_sensitivity(),
+        }
+    }
+}
+
+impl From<&Transform> for TransformDesc {
+    fn from(transform: &Transform) -> Self {
+        Self {
+            translation: transform.translation(),
+            rotation: transform.rotation(),
+            scale: transform.scale(),
+        }
+    }
+}
+
+impl From<TransformDesc> for Transform {
+    fn from(desc: TransformDesc) -> Self {
+        Transform::from_trs(desc.translation, desc.rotation, desc.scale)
+    }
+}
+
+impl From<&Camera> for CameraDesc {
+    fn from(camera: &Camera) -> Self {
+        Self {
+            position: camera.gl_camera_pos,
+            yaw: camera.gl_camera_yaw,
+            pitch: camera.gl_camera_pitch,
+            fov: camera.gl_camera_fov,
+            near: camera.gl_camera_near,
+            far: camera.gl_camera_far,
+            sensitivity: camera.gl_camera_sensitivity,
+        }
+    }
+}
+
+impl From<CameraDesc> for Camera {
+    fn from(desc: CameraDesc) -> Self {
+        let mut camera = Camera::new(desc.position, Vec3::Y, desc.sensitivity, 0.0, 0.0);
+        camera.gl_camera_yaw = desc.yaw;
+        camera.gl_camera_pitch = desc.pitch;
+        camera.rotate(0.0, 0.0);
+        camera.gl_camera_fov = desc.fov;
+        camera.gl_camera_near = desc.near;
+        camera.gl_camera_far = desc.far;
+        camera
+    }
+}
+
+impl NodeDesc {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            visible: true,
+            model: None,
+            material: None,
+            camera: None,
+            light: None,
+            transform: TransformDesc::default(),
+            children: Vec::new(),
+        }
+    }
+}
+
+impl SceneFile {
+    pub fn load(path: &str) -> Result<SceneFile, ERRUranXError> {
+        let source = fs::read_to_string(path).map_err(|source| ERRUranXError::io(path, source))?;
+        Self::parse(&source).map_err(|error| ERRUranXError::toml(path, &source, &error))
+    }
+
+    pub fn parse(source: &str) -> Result<SceneFile, toml::de::Error> {
+        toml::from_str(source)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), ERRUranXError> {
+        let source = self.to_toml().map_err(|error| ERRUranXError::CANNOT_SAVE_SCENE {
+            path: path.to_string(),
+            message: error.to_string(),
+        })?;
+        fs::write(path, source).map_err(|source| ERRUranXError::io(path, source))
+    }
+
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(self)
+    }
+
+    pub fn material(&self, name: &str) -> Option<&MaterialDesc> {
+        self.materials.iter().find(|material| material.name == name)
+    }
+
+    /// Instantiates the scene, loading every referenced model once.
+    pub fn build(&self) -> Result<Scene, ERRUranXError> {
+        let mut models: HashMap<String, Rc<Model>> = HashMap::new();
+        self.build_with(|path| {
+            if let Some(model) = models.get(path) {
+                return Ok(model.clone());
+            }
+
+            let model = Rc::new(Model::new(path)?);
+            models.insert(path.to_string(), model.clone());
+            Ok(model)
+        })
+    }
+
+    /// Instantiates the scene, asking `load_model` for each model path it meets.
+    /// Stops at the first model that fails to load.
+    pub fn build_with(
+        &self,
+        mut load_model: impl FnMut(&str) -> Result<Rc<Model>, ERRUranXError>,
+    ) -> Result<Scene, ERRUranXError> {
+        let mut scene = Scene::new();
+        for node in &self.nodes {
+            Self::build_node(&mut scene, None, node, &mut load_model)?;
+        }
+
+        if let Some(id) = self.active_camera.as_deref().and_then(|name| scene.find(name)) {
+            scene.set_active_camera(id);
+        }
+
+        Ok(scene)
+    }
+
+    fn build_node(
+        scene: &mut Scene,
+        parent: Option<NodeId>,
+        desc: &NodeDesc,
+        load_model: &mut impl FnMut(&str) -> Result<Rc<Model>, ERRUranXError>,
+    ) -> Result<(), ERRUranXError> {
+        let mut node = Node::new(&desc.name);
+        node.is_visible = desc.visible;
+        node.model = desc.model.as_deref().map(&mut *load_model).transpose()?;
+        node.material = desc.material.clone();
+        node.camera = desc.camera.map(Camera::from);
+        node.light = desc.light;
+
+        let transform = Transform::from(desc.transform);
+        let id = match parent {
+            Some(parent) => scene.add_child(parent, node, transform).unwrap(),
+            None => scene.add(node, transform),
+        };
+
+        for child in &desc.children {
+            Self::build_node(scene, Some(id), child, load_model)?;
+        }
+
+        Ok(())
+    }
+
+    /// Describes the nodes of `scene`. The name, shader and material definitions are not part
+    /// of the engine scene, so they are kept from `base`, usually the file the scene was built
+    /// from.
+    pub fn from_scene(base: &SceneFile, scene: &Scene) -> SceneFile {
+        SceneFile {
+            name: base.name.clone(),
+            active_camera: scene
+                .active_camera()
+                .and_then(|id| scene.node(id))
+                .map(|node| node.name.clone()),
+            shader: base.shader.clone(),
+            materials: base.materials.clone(),
+            nodes: scene
+                .roots()
+                .map(|id| Self::describe_node(scene, id))
+                .collect(),
+        }
+    }
+
+    fn describe_node(scene: &Scene, id: NodeId) -> NodeDesc {
+        let node = scene.node(id).unwrap();
+
+        NodeDesc {
+            name: node.name.clone(),
+            visible: node.is_visible,
+            model: node.model.as_ref().map(|model| model.path().to_string()),
+            material: node.material.clone(),
+            camera: node.camera.as_ref().map(CameraDesc::from),
+            light: node.light,
+            transform: scene
+                .transform(id)
+                .map(TransformDesc::from)
+                .unwrap_or_default(),
+            children: scene
+                .children(id)
+                .iter()
+                .map(|child| Self::describe_node(scene, *child))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::EPSILON;
+    use crate::scene::light::Light;
+    use std::path::Path;
+
+    const SCENE: &str = r#"
+name = "round trip"
+active_camera = "camera"
+
+[shader]
+vertex = "shd.vert"
+fragment = "shd.frag"
+
+[[materials]]
+name = "brick"
+shader = { vertex = "brick.vert", fragment = "brick.frag" }
+parameters = { roughness = [0.8], tint = [1.0, 0.5, 0.25] }
+textures = { albedo = "brick.png", normal = "brick_normal.png" }
+
+[[nodes]]
+name = "camera"
+transform = { translation = [0.0, 1.5, 6.0] }
+camera = { position = [0.5, 0.0, -1.0], yaw = -45.0, pitch = 10.0, fov = 60.0, near = 0.5, far = 250.0, sensitivity = 0.2 }
+
+[[nodes]]
+name = "sun"
+transform = { rotation = [-0.3826834, 0.0, 0.0, 0.9238795] }
+light = { kind = "directional", color = [1.0, 0.9, 0.8], intensity = 2.0 }
+
+[[nodes]]
+name = "wall"
+material = "brick"
+visible = false
+transform = { translation = [1.0, 2.0, 3.0], scale = [2.0, 1.0, 0.5] }
+
+[[nodes.children]]
+name = "lamp"
+light = { kind = "point", color = [1.0, 1.0, 1.0], intensity = 5.0, range = 10.0 }
+transform = { translation = [0.0, 1.0, 0.0] }
+"#;
+
+    #[test]
+    fn toml_round_trip() {
+        let file = SceneFile::parse(SCENE).unwrap();
+        assert_eq!(SceneFile::parse(&file.to_toml().unwrap()).unwrap(), file);
+    }
+
+    #[test]
+    fn scene_round_trip() {
+        let file = SceneFile::parse(SCENE).unwrap();
+        let scene = file.build_with(|path| panic!("the scene has no models, asked for `{}`", path)).unwrap();
+
+        let described = SceneFile::from_scene(&file, &scene);
+        assert_eq!(described, file);
+        assert_eq!(described.nodes[2].material.as_deref(), Some("brick"));
+        assert_eq!(described.nodes[2].children[0].light, Some(Light::point(Vec3::ONE, 5.0, 10.0)));
+    }
+
+    #[test]
+    fn saves_the_pose_a_fly_camera_gave_its_camera() {
+        let file = SceneFile::parse(SCENE).unwrap();
+        let mut scene = file.build_with(|path| panic!("the scene has no models, asked for `{}`", path)).unwrap();
+        let id = scene.active_camera().unwrap();
+
+        let camera = scene.node_mut(id).unwrap().camera.as_mut().unwrap();
+        camera.rotate(30.0, -15.0);
+        camera.move_forward(2.0);
+        let (view, front) = (camera.view_matrix(), camera.gl_camera_front);
+
+        let saved = SceneFile::parse(&SceneFile::from_scene(&file, &scene).to_toml().unwrap()).unwrap();
+        let reloaded = saved.build_with(|path| panic!("asked for `{}`", path)).unwrap();
+        let camera = reloaded.node(reloaded.active_camera().unwrap()).unwrap().camera.as_ref().unwrap();
+        assert!(camera.gl_camera_front.abs_diff_eq(front, EPSILON));
+        assert!(camera.view_matrix().abs_diff_eq(view, EPSILON));
+    }
+
+    #[test]
+    fn default_camera_looks_down_negative_z() {
+        let camera = Camera::from(CameraDesc::default());
+        assert!(camera.gl_camera_front.abs_diff_eq(-Vec3::Z, EPSILON));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let file = SceneFile::parse(SCENE).unwrap();
+        let path = std::env::temp_dir().join(format!("uranx-scene-{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        file.save(path).unwrap();
+        let loaded = SceneFile::load(path);
+        fs::remove_file(path).unwrap();
+        assert_eq!(loaded.unwrap(), file);
+    }
+
+    #[test]
+    fn bundled_scene_models_exist() {
+        let file = SceneFile::load("res/scenes/main.toml").unwrap();
+        let mut stack: Vec<&NodeDesc> = file.nodes.iter().collect();
+        while let Some(node) = stack.pop() {
+            if let Some(model) = &node.model {
+                assert!(Path::new(model).is_file(), "`{}` is missing", model);
+            }
+            stack.extend(&node.children);
+        }
+    }
+}
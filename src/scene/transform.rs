@@ -0,0 +1,495 @@
+use crate::math::{Mat4, Quat, Vec3};
+use std::cell::Cell;
+
+/// Handle to a transform in a [`TransformTree`].
+///
+/// Slots are reused after removal; the generation tells a stale id apart from the transform
+/// that took its slot, so it is never silently aliased.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransformId {
+    index: usize,
+    generation: u32,
+}
+
+/// Translation, rotation and scale of an object, composed as `T * R * S`.
+///
+/// The local matrix is cached and rebuilt lazily. The world matrix is filled in by
+/// [`TransformTree::update`]; a transform outside of a tree uses its local matrix.
+#[derive(Debug, Clone)]
+pub struct Transform {
+    translation: Vec3,
+    rotation: Quat,
+    scale: Vec3,
+    local_matrix: Cell<Mat4>,
+    is_local_dirty: Cell<bool>,
+    world_matrix: Mat4,
+    is_world_dirty: bool,
+    parent: Option<TransformId>,
+    children: Vec<TransformId>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::from_trs(Vec3::ZERO, Quat::IDENTITY, Vec3::ONE)
+    }
+}
+
+impl Transform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_trs(translation: Vec3, rotation: Quat, scale: Vec3) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+            local_matrix: Cell::new(Mat4::IDENTITY),
+            is_local_dirty: Cell::new(true),
+            world_matrix: Mat4::IDENTITY,
+            is_world_dirty: true,
+            parent: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self::from_trs(translation, Quat::IDENTITY, Vec3::ONE)
+    }
+
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.set_rotation(rotation);
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.set_scale(scale);
+        self
+    }
+
+    pub fn translation(&self) -> Vec3 {
+        self.translation
+    }
+
+    pub fn rotation(&self) -> Quat {
+        self.rotation
+    }
+
+    pub fn scale(&self) -> Vec3 {
+        self.scale
+    }
+
+    pub fn set_translation(&mut self, translation: Vec3) {
+        self.translation = translation;
+        self.mark_dirty();
+    }
+
+    pub fn set_rotation(&mut self, rotation: Quat) {
+        self.rotation = rotation.normalize();
+        self.mark_dirty();
+    }
+
+    pub fn set_scale(&mut self, scale: Vec3) {
+        self.scale = scale;
+        self.mark_dirty();
+    }
+
+    pub fn translate(&mut self, offset: Vec3) {
+        self.set_translation(self.translation + offset);
+    }
+
+    /// Applies `rotation` on top of the current one, in parent space.
+    pub fn rotate(&mut self, rotation: Quat) {
+        self.set_rotation(rotation * self.rotation);
+    }
+
+    pub fn rotate_axis(&mut self, axis: Vec3, angle: f32) {
+        self.rotate(Quat::from_axis_angle(axis, angle));
+    }
+
+    /// Turns the transform so that `forward` points at `target`.
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        self.set_rotation(Quat::look_rotation(target - self.translation, up));
+    }
+
+    /// Local `-Z` axis, in parent space.
+    pub fn forward(&self) -> Vec3 {
+        self.rotation * -Vec3::Z
+    }
+
+    /// Local `+X` axis, in parent space.
+    pub fn right(&self) -> Vec3 {
+        self.rotation * Vec3::X
+    }
+
+    /// Local `+Y` axis, in parent space.
+    pub fn up(&self) -> Vec3 {
+        self.rotation * Vec3::Y
+    }
+
+    pub fn local_matrix(&self) -> Mat4 {
+        if self.is_local_dirty.get() {
+            self.local_matrix.set(Mat4::from_scale_rotation_translation(
+                self.scale,
+                self.rotation,
+                self.translation,
+            ));
+            self.is_local_dirty.set(false);
+        }
+
+        self.local_matrix.get()
+    }
+
+    /// Parent world matrix times the local matrix, as of the last tree update.
+    pub fn world_matrix(&self) -> Mat4 {
+        if self.parent.is_none() && self.is_world_dirty {
+            self.local_matrix()
+        } else {
+            self.world_matrix
+        }
+    }
+
+    pub fn world_translation(&self) -> Vec3 {
+        self.world_matrix().translation()
+    }
+
+    pub fn world_forward(&self) -> Vec3 {
+        self.world_matrix().transform_vector3(-Vec3::Z).normalize()
+    }
+
+    pub fn world_right(&self) -> Vec3 {
+        self.world_matrix().transform_vector3(Vec3::X).normalize()
+    }
+
+    pub fn world_up(&self) -> Vec3 {
+        self.world_matrix().transform_vector3(Vec3::Y).normalize()
+    }
+
+    pub fn parent(&self) -> Option<TransformId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[TransformId] {
+        &self.children
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.is_world_dirty
+    }
+
+    fn mark_dirty(&mut self) {
+        self.is_local_dirty.set(true);
+        self.is_world_dirty = true;
+    }
+}
+
+#[derive(Debug)]
+struct Slot {
+    generation: u32,
+    transform: Option<Transform>,
+}
+
+/// Owns transforms and their parent/child links.
+#[derive(Debug, Default)]
+pub struct TransformTree {
+    slots: Vec<Slot>,
+    free_slots: Vec<usize>,
+}
+
+impl TransformTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, mut transform: Transform) -> TransformId {
+        transform.parent = None;
+        transform.children.clear();
+        transform.is_world_dirty = true;
+
+        match self.free_slots.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.transform = Some(transform);
+                TransformId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    transform: Some(transform),
+                });
+                TransformId {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    pub fn insert_child(&mut self, parent: TransformId, transform: Transform) -> TransformId {
+        let id = self.insert(transform);
+        self.set_parent(id, Some(parent));
+        id
+    }
+
+    /// Removes a transform. Its children are re-parented to its former parent.
+    ///
+    /// `id` stays invalid afterwards, even once its slot is reused.
+    pub fn remove(&mut self, id: TransformId) -> Option<Transform> {
+        let parent = self.get(id)?.parent;
+        let children = self.get(id)?.children.clone();
+
+        for child in children {
+            self.set_parent(child, parent);
+        }
+        self.set_parent(id, None);
+
+        let slot = &mut self.slots[id.index];
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(id.index);
+        slot.transform.take()
+    }
+
+    pub fn get(&self, id: TransformId) -> Option<&Transform> {
+        self.slots
+            .get(id.index)
+            .filter(|slot| slot.generation == id.generation)?
+            .transform
+            .as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: TransformId) -> Option<&mut Transform> {
+        self.slots
+            .get_mut(id.index)
+            .filter(|slot| slot.generation == id.generation)?
+            .transform
+            .as_mut()
+    }
+
+    pub fn contains(&self, id: TransformId) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Moves `child` under `parent` (or to the root when `None`).
+    /// Returns `false` if either id is unknown or the link would create a cycle.
+    pub fn set_parent(&mut self, child: TransformId, parent: Option<TransformId>) -> bool {
+        if !self.contains(child) {
+            return false;
+        }
+
+        if let Some(parent) = parent {
+            if !self.contains(parent) || self.is_ancestor(child, parent) {
+                return false;
+            }
+        }
+
+        if let Some(old_parent) = self.get(child).unwrap().parent {
+            if let Some(old_parent) = self.get_mut(old_parent) {
+                old_parent.children.retain(|id| *id != child);
+            }
+        }
+
+        if let Some(parent) = parent {
+            self.get_mut(parent).unwrap().children.push(child);
+        }
+
+        let transform = self.get_mut(child).unwrap();
+        transform.parent = parent;
+        transform.is_world_dirty = true;
+        true
+    }
+
+    /// `true` if `ancestor` is `id` itself or one of its parents.
+    pub fn is_ancestor(&self, ancestor: TransformId, id: TransformId) -> bool {
+        let mut current = Some(id);
+        while let Some(node) = current {
+            if node == ancestor {
+                return true;
+            }
+            current = self.get(node).and_then(|transform| transform.parent);
+        }
+        false
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = TransformId> + '_ {
+        self.iter()
+            .filter(|(_, transform)| transform.parent.is_none())
+            .map(|(id, _)| id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TransformId, &Transform)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let id = TransformId {
+                index,
+                generation: slot.generation,
+            };
+            Some((id, slot.transform.as_ref()?))
+        })
+    }
+
+    /// Recomputes world matrices of every dirty transform and of everything below it.
+    pub fn update(&mut self) {
+        let roots: Vec<TransformId> = self.roots().collect();
+        let mut stack: Vec<(TransformId, Mat4, bool)> = roots
+            .into_iter()
+            .map(|id| (id, Mat4::IDENTITY, false))
+            .collect();
+
+        while let Some((id, parent_world, is_parent_changed)) = stack.pop() {
+            let transform = self.get_mut(id).unwrap();
+            let is_changed = is_parent_changed || transform.is_world_dirty;
+
+            if is_changed {
+                transform.world_matrix = parent_world * transform.local_matrix();
+                transform.is_world_dirty = false;
+            }
+
+            let world = transform.world_matrix;
+            stack.extend(
+                transform
+                    .children
+                    .iter()
+                    .map(|child| (*child, world, is_changed)),
+            );
+        }
+    }
+
+    /// World matrix of `id`, refreshing the branch above it if anything there is dirty.
+    pub fn world_matrix(&mut self, id: TransformId) -> Option<Mat4> {
+        let mut chain = Vec::new();
+        let mut current = Some(id);
+        while let Some(node) = current {
+            chain.push(node);
+            current = self.get(node)?.parent;
+        }
+
+        let mut world = Mat4::IDENTITY;
+        let mut is_changed = false;
+        for node in chain.into_iter().rev() {
+            let transform = self.get_mut(node)?;
+            if is_changed || transform.is_world_dirty {
+                transform.world_matrix = world * transform.local_matrix();
+                is_changed = true;
+                if transform.children.is_empty() {
+                    transform.is_world_dirty = false;
+                }
+            }
+            world = transform.world_matrix;
+        }
+
+        Some(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::EPSILON;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_translation(tree: &mut TransformTree, id: TransformId, expected: Vec3) {
+        let actual = tree.world_matrix(id).unwrap().translation();
+        assert!(actual.abs_diff_eq(expected, EPSILON), "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn child_follows_its_parent() {
+        let mut tree = TransformTree::new();
+        let parent = tree.insert(Transform::from_translation(Vec3::new(1.0, 0.0, 0.0)));
+        let child = tree.insert_child(parent, Transform::from_translation(Vec3::new(0.0, 0.0, -2.0)));
+        tree.update();
+        assert!(tree.get(child).unwrap().world_translation().abs_diff_eq(Vec3::new(1.0, 0.0, -2.0), EPSILON));
+
+        let parent_transform = tree.get_mut(parent).unwrap();
+        parent_transform.set_translation(Vec3::new(0.0, 5.0, 0.0));
+        parent_transform.rotate_axis(Vec3::Y, FRAC_PI_2);
+        tree.update();
+
+        let child_transform = tree.get(child).unwrap();
+        assert!(!child_transform.is_dirty());
+        assert!(child_transform.world_translation().abs_diff_eq(Vec3::new(-2.0, 5.0, 0.0), EPSILON));
+        assert!(child_transform.world_forward().abs_diff_eq(-Vec3::X, EPSILON));
+    }
+
+    #[test]
+    fn world_matrix_refreshes_a_dirty_branch() {
+        let mut tree = TransformTree::new();
+        let root = tree.insert(Transform::new().with_scale(Vec3::splat(2.0)));
+        let middle = tree.insert_child(root, Transform::from_translation(Vec3::X));
+        let leaf = tree.insert_child(middle, Transform::from_translation(Vec3::Y));
+        assert_translation(&mut tree, leaf, Vec3::new(2.0, 2.0, 0.0));
+
+        tree.get_mut(root).unwrap().set_translation(Vec3::Z);
+        assert_translation(&mut tree, leaf, Vec3::new(2.0, 2.0, 1.0));
+    }
+
+    #[test]
+    fn reparenting_moves_the_child_and_keeps_its_local_transform() {
+        let mut tree = TransformTree::new();
+        let a = tree.insert(Transform::from_translation(Vec3::X));
+        let b = tree.insert(Transform::from_translation(Vec3::Y));
+        let child = tree.insert_child(a, Transform::from_translation(Vec3::Z));
+
+        assert!(tree.set_parent(child, Some(b)));
+        assert!(tree.get(a).unwrap().children().is_empty());
+        assert_eq!(tree.get(b).unwrap().children(), [child]);
+        assert_eq!(tree.get(child).unwrap().parent(), Some(b));
+        assert_translation(&mut tree, child, Vec3::new(0.0, 1.0, 1.0));
+
+        assert!(tree.set_parent(child, None));
+        assert!(tree.get(b).unwrap().children().is_empty());
+        assert_eq!(tree.roots().count(), 3);
+        assert_translation(&mut tree, child, Vec3::Z);
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let mut tree = TransformTree::new();
+        let root = tree.insert(Transform::new());
+        let child = tree.insert_child(root, Transform::new());
+        let grandchild = tree.insert_child(child, Transform::new());
+
+        assert!(!tree.set_parent(root, Some(grandchild)));
+        assert!(!tree.set_parent(child, Some(child)));
+        assert_eq!(tree.get(root).unwrap().parent(), None);
+        assert_eq!(tree.get(child).unwrap().parent(), Some(root));
+        assert!(tree.is_ancestor(root, grandchild));
+        assert!(!tree.is_ancestor(grandchild, root));
+    }
+
+    #[test]
+    fn removal_hands_children_to_the_grandparent() {
+        let mut tree = TransformTree::new();
+        let root = tree.insert(Transform::from_translation(Vec3::X));
+        let middle = tree.insert_child(root, Transform::from_translation(Vec3::Y));
+        let leaf = tree.insert_child(middle, Transform::from_translation(Vec3::Z));
+
+        let removed = tree.remove(middle).unwrap();
+        assert_eq!(removed.translation(), Vec3::Y);
+        assert!(!tree.contains(middle));
+        assert!(tree.remove(middle).is_none());
+        assert_eq!(tree.get(leaf).unwrap().parent(), Some(root));
+        assert_eq!(tree.get(root).unwrap().children(), [leaf]);
+        assert_translation(&mut tree, leaf, Vec3::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn removed_ids_stay_invalid_when_their_slot_is_reused() {
+        let mut tree = TransformTree::new();
+        let parent = tree.insert(Transform::new());
+        let removed = tree.insert(Transform::from_translation(Vec3::X));
+        tree.remove(removed);
+
+        let reused = tree.insert(Transform::from_translation(Vec3::Y));
+        assert_ne!(reused, removed);
+        assert!(tree.get(removed).is_none());
+        assert!(tree.get_mut(removed).is_none());
+        assert!(!tree.set_parent(removed, Some(parent)));
+        assert!(!tree.set_parent(reused, Some(removed)));
+        assert!(tree.world_matrix(removed).is_none());
+        assert_eq!(tree.get(reused).unwrap().translation(), Vec3::Y);
+        assert_eq!(tree.iter().map(|(id, _)| id).collect::<Vec<_>>(), [parent, reused]);
+    }
+}
@@ -0,0 +1,79 @@
+/// Decodes standard or URL-safe base64. Padding is optional and ASCII whitespace is ignored;
+/// any other character outside the alphabet gives `None`.
+pub fn decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    let mut padding = 0;
+
+    for character in text.bytes() {
+        let value = match character {
+            b'A'..=b'Z' => character - b'A',
+            b'a'..=b'z' => character - b'a' + 26,
+            b'0'..=b'9' => character - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            b' ' | b'\t' | b'\n' | b'\r' => continue,
+            _ => return None,
+        };
+        if padding > 0 {
+            // Data after `=`.
+            return None;
+        }
+
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    // A lone trailing character can't encode a whole byte.
+    if bits >= 6 || padding > 2 {
+        return None;
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_padded_and_unpadded() {
+        assert_eq!(decode("").unwrap(), b"");
+        assert_eq!(decode("TQ==").unwrap(), b"M");
+        assert_eq!(decode("TWE=").unwrap(), b"Ma");
+        assert_eq!(decode("TWFu").unwrap(), b"Man");
+        assert_eq!(decode("TQ").unwrap(), b"M");
+        assert_eq!(decode("TWE").unwrap(), b"Ma");
+        assert_eq!(decode("aGVsbG8sIHdvcmxkIQ==").unwrap(), b"hello, world!");
+    }
+
+    #[test]
+    fn decodes_both_alphabets() {
+        assert_eq!(decode("+/+/").unwrap(), [0xFB, 0xFF, 0xBF]);
+        assert_eq!(decode("-_-_").unwrap(), [0xFB, 0xFF, 0xBF]);
+    }
+
+    #[test]
+    fn skips_whitespace() {
+        assert_eq!(decode(" aGVs\nbG8=\r\n").unwrap(), b"hello");
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert_eq!(decode("aGVs*bG8="), None);
+        assert_eq!(decode("TQ==TQ=="), None);
+        assert_eq!(decode("T"), None);
+        assert_eq!(decode("TWFuT"), None);
+        assert_eq!(decode("T==="), None);
+    }
+}
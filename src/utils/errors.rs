@@ -1,11 +1,24 @@
+use crate::graphics::shader_program::ShaderStage;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
+use std::io;
 
 #[derive(Debug)]
 pub enum ERRUranXError {
     CANNOT_INIT_LIBRARY(Library),
-    CANNOT_INIT_SETTINGS,
+    CANNOT_SAVE_SCENE { path: String, message: String },
+    MISSING_PATH { setting: String, path: String },
+    INVALID_INPUT_BINDING(String),
     RUNTIME_LIBRARY_ERROR(Library),
+    IO_ERROR { path: String, source: io::Error },
+    PARSE_ERROR { path: String, line: usize, column: usize, message: String },
+    OBJ_PARSE_ERROR { path: String, source: tobj::LoadError },
+    IMAGE_DECODE_ERROR { path: String, source: image::ImageError },
+    SHADER_COMPILE_ERROR { stage: ShaderStage, path: String, log: String },
+    SHADER_PREPROCESS_ERROR { path: String, line: usize, message: String },
+    SHADER_LINK_ERROR { vertex_path: String, fragment_path: String, log: String },
+    INVALID_MATERIAL { material: String, message: String },
+    GLTF_ERROR { path: String, message: String },
 }
 
 #[derive(Debug)]
@@ -14,10 +27,136 @@ pub enum Library {
     OPEN_GL
 }
 
+impl ERRUranXError {
+    pub fn io(path: &str, source: io::Error) -> Self {
+        ERRUranXError::IO_ERROR { path: path.to_string(), source }
+    }
+
+    /// Wraps a TOML error, locating it in `source` by line and column (both 1-based).
+    pub fn toml(path: &str, source: &str, error: &toml::de::Error) -> Self {
+        let (line, column) = error
+            .span()
+            .map(|span| line_column(source, span.start))
+            .unwrap_or((0, 0));
+
+        ERRUranXError::PARSE_ERROR {
+            path: path.to_string(),
+            line,
+            column,
+            message: error.message().to_string(),
+        }
+    }
+}
+
+/// 1-based line and column of the byte `offset` in `source`.
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
 impl Display for ERRUranXError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "UranX can't work because We have a critical error.")
+        match self {
+            ERRUranXError::MISSING_PATH { setting, path } => {
+                write!(f, "UranX can't find `{}` configured as `{}` in res/settings.toml.", path, setting)
+            }
+            ERRUranXError::CANNOT_SAVE_SCENE { path, message } => {
+                write!(f, "UranX can't save the scene `{}`: {}.", path, message)
+            }
+            ERRUranXError::INVALID_INPUT_BINDING(binding) => {
+                write!(f, "UranX doesn't know the input binding `{}` in res/settings.toml.", binding)
+            }
+            ERRUranXError::CANNOT_INIT_LIBRARY(library) => {
+                write!(f, "UranX can't initialize {:?}; check that a display is available and the graphics drivers are installed.", library)
+            }
+            ERRUranXError::RUNTIME_LIBRARY_ERROR(library) => write!(
+                f,
+                "UranX got an error from {:?} creating the window; check the `[graphics]` settings in res/settings.toml against what the display supports.",
+                library
+            ),
+            ERRUranXError::IO_ERROR { path, .. } => write!(f, "UranX can't access `{}`.", path),
+            ERRUranXError::PARSE_ERROR { path, line, column, message } => {
+                write!(f, "UranX can't parse `{}` at line {}, column {}: {}", path, line, column, message.trim_end())
+            }
+            ERRUranXError::OBJ_PARSE_ERROR { path, .. } => write!(f, "UranX can't load the model `{}`.", path),
+            ERRUranXError::IMAGE_DECODE_ERROR { path, .. } => write!(f, "UranX can't decode the image `{}`.", path),
+            ERRUranXError::SHADER_COMPILE_ERROR { stage, path, log } => {
+                write!(f, "UranX can't compile the {} shader `{}`:\n{}", stage, path, log.trim_end())
+            }
+            ERRUranXError::SHADER_PREPROCESS_ERROR { path, line, message } => {
+                write!(f, "UranX can't preprocess the shader `{}` at line {}: {}.", path, line, message)
+            }
+            ERRUranXError::SHADER_LINK_ERROR { vertex_path, fragment_path, log } => {
+                write!(f, "UranX can't link the shaders `{}` and `{}`:\n{}", vertex_path, fragment_path, log.trim_end())
+            }
+            ERRUranXError::INVALID_MATERIAL { material, message } => {
+                write!(f, "UranX can't build the material `{}`: {}.", material, message)
+            }
+            ERRUranXError::GLTF_ERROR { path, message } => {
+                write!(f, "UranX can't load the glTF file `{}`: {}.", path, message)
+            }
+        }
+    }
+}
+
+/// `error` followed by every error in its `source()` chain, one per line.
+pub fn error_report(error: &dyn Error) -> String {
+    let mut report = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        report.push_str(&format!("\n  caused by: {}", error));
+        source = error.source();
     }
+    report
 }
 
-impl Error for ERRUranXError {}
\ No newline at end of file
+impl Error for ERRUranXError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ERRUranXError::IO_ERROR { source, .. } => Some(source),
+            ERRUranXError::OBJ_PARSE_ERROR { source, .. } => Some(source),
+            ERRUranXError::IMAGE_DECODE_ERROR { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_column_is_one_based() {
+        let source = "a = 1\nb = [\n  2,";
+        assert_eq!(line_column(source, 0), (1, 1));
+        assert_eq!(line_column(source, 6), (2, 1));
+        assert_eq!(line_column(source, 14), (3, 3));
+        assert_eq!(line_column(source, 1000), (3, 5));
+    }
+
+    #[test]
+    fn toml_errors_point_at_the_problem() {
+        #[derive(Debug, serde_derive::Deserialize)]
+        #[allow(dead_code)]
+        struct Graphics {
+            window_width: u32,
+        }
+        #[derive(Debug, serde_derive::Deserialize)]
+        #[allow(dead_code)]
+        struct Settings {
+            graphics: Graphics,
+        }
+        let source = "[graphics]\nwindow_width = \"wide\"\n";
+        let error = toml::from_str::<Settings>(source).unwrap_err();
+        let message = ERRUranXError::toml("res/settings.toml", source, &error).to_string();
+        assert!(message.starts_with("UranX can't parse `res/settings.toml` at line 2, column 16"), "{}", message);
+    }
+
+    #[test]
+    fn report_follows_the_source_chain() {
+        let error = ERRUranXError::io("res/missing.png", io::Error::new(io::ErrorKind::NotFound, "no such file"));
+        assert_eq!(error_report(&error), "UranX can't access `res/missing.png`.\n  caused by: no such file");
+    }
+}
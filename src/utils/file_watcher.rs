@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Detects changes to a set of files by polling their modification times.
+///
+/// A file that disappears or appears also counts as changed. Polling is throttled to
+/// `interval`, so [`FileWatcher::poll`] is cheap to call every frame.
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+    files: HashMap<PathBuf, Option<SystemTime>>,
+    interval: Duration,
+    last_poll: Option<Instant>,
+}
+
+impl FileWatcher {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            files: HashMap::new(),
+            interval,
+            last_poll: None,
+        }
+    }
+
+    pub fn watch(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified_time(&path);
+        self.files.insert(path, modified);
+    }
+
+    pub fn unwatch(&mut self, path: impl AsRef<Path>) {
+        self.files.remove(path.as_ref());
+    }
+
+    /// Replaces the watched set, keeping the known times of files that stay in it.
+    pub fn set_files<P: AsRef<Path>>(&mut self, paths: impl IntoIterator<Item = P>) {
+        let mut files = HashMap::new();
+        for path in paths {
+            let path = path.as_ref().to_path_buf();
+            let modified = self
+                .files
+                .get(&path)
+                .copied()
+                .unwrap_or_else(|| modified_time(&path));
+            files.insert(path, modified);
+        }
+        self.files = files;
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Files changed since the last poll, or nothing if the interval has not passed yet.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        if self
+            .last_poll
+            .is_some_and(|last_poll| now.duration_since(last_poll) < self.interval)
+        {
+            return Vec::new();
+        }
+        self.last_poll = Some(now);
+
+        self.poll_now()
+    }
+
+    /// Files changed since the last poll, ignoring the interval.
+    pub fn poll_now(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, modified) in &mut self.files {
+            let current = modified_time(path);
+            if current != *modified {
+                *modified = current;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+
+    /// A path in the temp directory, removed again when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("uranx-watch-{}-{}", std::process::id(), name));
+            let _ = fs::remove_file(&path);
+            Self(path)
+        }
+
+        fn write(&self) {
+            fs::write(&self.0, "x").unwrap();
+        }
+
+        /// Moves the modification time forward without relying on the clock's resolution.
+        fn touch(&self) {
+            let modified = modified_time(&self.0).unwrap();
+            let file = File::options().write(true).open(&self.0).unwrap();
+            file.set_modified(modified + Duration::from_secs(10)).unwrap();
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn reports_a_bumped_modification_time_once() {
+        let file = TempFile::new("bumped");
+        file.write();
+        let mut watcher = FileWatcher::new(Duration::ZERO);
+        watcher.watch(&file.0);
+        assert!(watcher.poll_now().is_empty());
+
+        file.touch();
+        assert_eq!(watcher.poll_now(), vec![file.0.clone()]);
+        assert!(watcher.poll_now().is_empty());
+    }
+
+    #[test]
+    fn deleted_and_created_files_count_as_changed() {
+        let deleted = TempFile::new("deleted");
+        let created = TempFile::new("created");
+        deleted.write();
+        let mut watcher = FileWatcher::new(Duration::ZERO);
+        watcher.set_files([&deleted.0, &created.0]);
+
+        fs::remove_file(&deleted.0).unwrap();
+        assert_eq!(watcher.poll_now(), vec![deleted.0.clone()]);
+
+        created.write();
+        assert_eq!(watcher.poll_now(), vec![created.0.clone()]);
+        assert!(watcher.poll_now().is_empty());
+    }
+
+    #[test]
+    fn poll_is_quiet_within_the_interval() {
+        let file = TempFile::new("throttled");
+        file.write();
+        let mut watcher = FileWatcher::new(Duration::from_secs(3600));
+        watcher.watch(&file.0);
+        assert!(watcher.poll().is_empty());
+
+        file.touch();
+        assert!(watcher.poll().is_empty());
+        assert_eq!(watcher.poll_now(), vec![file.0.clone()]);
+    }
+
+    #[test]
+    fn set_files_keeps_known_times() {
+        let kept = TempFile::new("kept");
+        let added = TempFile::new("added");
+        kept.write();
+        added.write();
+        let mut watcher = FileWatcher::new(Duration::ZERO);
+        watcher.watch(&kept.0);
+
+        kept.touch();
+        watcher.set_files([&kept.0, &added.0]);
+        assert_eq!(watcher.poll_now(), vec![kept.0.clone()]);
+
+        watcher.set_files([&added.0]);
+        watcher.set_files([&added.0, &kept.0]);
+        assert!(watcher.poll_now().is_empty());
+        assert_eq!(watcher.files().count(), 2);
+    }
+}
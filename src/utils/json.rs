@@ -0,0 +1,489 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// A parsed JSON document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(BTreeMap<String, JsonValue>),
+}
+
+/// Where and why a JSON document failed to parse. `offset` is a byte offset into the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+static NULL: JsonValue = JsonValue::Null;
+
+impl JsonValue {
+    /// Parses a complete document; anything but whitespace after the value is an error.
+    pub fn parse(source: &str) -> Result<JsonValue, JsonError> {
+        let mut parser = Parser { source: source.as_bytes(), position: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < parser.source.len() {
+            return Err(parser.error("unexpected data after the document"));
+        }
+        Ok(value)
+    }
+
+    /// Member `key` of an object, or `Null` when it is missing or `self` isn't an object,
+    /// so lookups can be chained: `value.get("asset").get("version")`.
+    pub fn get(&self, key: &str) -> &JsonValue {
+        match self {
+            JsonValue::Object(members) => members.get(key).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+
+    /// Element `index` of an array, or `Null`.
+    pub fn at(&self, index: usize) -> &JsonValue {
+        match self {
+            JsonValue::Array(elements) => elements.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        self.as_f64().map(|value| value as f32)
+    }
+
+    /// A non-negative integer, e.g. an index.
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_f64()
+            .filter(|value| *value >= 0.0 && value.fract() == 0.0 && *value <= usize::MAX as f64)
+            .map(|value| value as usize)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&BTreeMap<String, JsonValue>> {
+        match self {
+            JsonValue::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    /// The elements of an array; empty for anything else, including a missing member.
+    pub fn elements(&self) -> &[JsonValue] {
+        self.as_array().unwrap_or(&[])
+    }
+
+    /// An array of numbers as `f32`s; `None` if any element isn't a number.
+    pub fn as_f32_vec(&self) -> Option<Vec<f32>> {
+        self.as_array()?.iter().map(JsonValue::as_f32).collect()
+    }
+}
+
+/// Nesting deeper than this is rejected instead of overflowing the stack.
+const MAX_DEPTH: usize = 256;
+
+struct Parser<'a> {
+    source: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> JsonError {
+        JsonError { offset: self.position, message: message.to_string() }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", byte as char)))
+        }
+    }
+
+    fn literal(&mut self, text: &str, value: JsonValue) -> Result<JsonValue, JsonError> {
+        if self.source[self.position..].starts_with(text.as_bytes()) {
+            self.position += text.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonValue, JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => self.string().map(JsonValue::String),
+            Some(b't') => self.literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.literal("false", JsonValue::Bool(false)),
+            Some(b'n') => self.literal("null", JsonValue::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of the document")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<JsonValue, JsonError>) -> Result<JsonValue, JsonError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error("nesting is too deep"));
+        }
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<JsonValue, JsonError> {
+        self.expect(b'{')?;
+        let mut members = BTreeMap::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(JsonValue::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a member name"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            let value = self.value()?;
+            members.insert(key, value);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(JsonValue::Object(members));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue, JsonError> {
+        self.expect(b'[')?;
+        let mut elements = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(JsonValue::Array(elements));
+        }
+
+        loop {
+            elements.push(self.value()?);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(JsonValue::Array(elements));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    /// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`; leading zeros, a bare `.`
+    /// and `+` signs are rejected like any other JSON parser would.
+    fn number(&mut self) -> Result<JsonValue, JsonError> {
+        let start = self.position;
+        let invalid = || JsonError { offset: start, message: "invalid number".to_string() };
+
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.position += 1,
+            Some(b'1'..=b'9') => {
+                self.digits();
+            }
+            _ => return Err(invalid()),
+        }
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            if self.digits() == 0 {
+                return Err(invalid());
+            }
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.position += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.position += 1;
+            }
+            if self.digits() == 0 {
+                return Err(invalid());
+            }
+        }
+
+        std::str::from_utf8(&self.source[start..self.position])
+            .ok()
+            .and_then(|text| text.parse::<f64>().ok())
+            .map(JsonValue::Number)
+            .ok_or_else(invalid)
+    }
+
+    /// Skips a run of decimal digits and returns its length.
+    fn digits(&mut self) -> usize {
+        let start = self.position;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        self.position - start
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+
+        loop {
+            let Some(byte) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.position += 1;
+
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(escape) = self.peek() else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.position += 1;
+                    match escape {
+                        b'"' => bytes.push(b'"'),
+                        b'\\' => bytes.push(b'\\'),
+                        b'/' => bytes.push(b'/'),
+                        b'b' => bytes.push(0x08),
+                        b'f' => bytes.push(0x0C),
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'u' => {
+                            let character = self.unicode_escape()?;
+                            let mut buffer = [0; 4];
+                            bytes.extend_from_slice(character.encode_utf8(&mut buffer).as_bytes());
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                0x00..=0x1F => return Err(self.error("control character in string")),
+                _ => bytes.push(byte),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| self.error("string is not valid UTF-8"))
+    }
+
+    /// The code point after `\u`, joining a surrogate pair written as two escapes.
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.hex4()?;
+        let code_point = if (0xD800..0xDC00).contains(&high) {
+            if !self.source[self.position..].starts_with(b"\\u") {
+                return Err(self.error("unpaired surrogate"));
+            }
+            self.position += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+
+        char::from_u32(code_point).ok_or_else(|| self.error("invalid code point"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .source
+            .get(self.position..self.position + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("expected 4 hex digits"))?;
+        self.position += 4;
+        Ok(digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> JsonValue {
+        JsonValue::parse(source).unwrap()
+    }
+
+    fn error(source: &str) -> JsonError {
+        JsonValue::parse(source).unwrap_err()
+    }
+
+    #[test]
+    fn parses_literals_and_numbers() {
+        assert_eq!(parse("null"), JsonValue::Null);
+        assert_eq!(parse(" true "), JsonValue::Bool(true));
+        assert_eq!(parse("false"), JsonValue::Bool(false));
+
+        for (source, value) in [
+            ("0", 0.0),
+            ("-0", 0.0),
+            ("42", 42.0),
+            ("-17", -17.0),
+            ("3.25", 3.25),
+            ("1e3", 1000.0),
+            ("2.5E-2", 0.025),
+            ("-1.5e+2", -150.0),
+            ("5126", 5126.0),
+        ] {
+            assert_eq!(parse(source).as_f64(), Some(value), "{source}");
+        }
+
+        assert_eq!(parse("3").as_usize(), Some(3));
+        assert_eq!(parse("3.5").as_usize(), None);
+        assert_eq!(parse("-1").as_usize(), None);
+    }
+
+    #[test]
+    fn rejects_numbers_outside_the_grammar() {
+        for source in ["01", "1.", ".5", "+1", "-", "1e", "1e+", "0x10", "1.2.3", "--1", "NaN", "Infinity"] {
+            assert!(JsonValue::parse(source).is_err(), "`{source}` parsed");
+        }
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        assert_eq!(parse(r#""a\"b\\c\/d""#).as_str(), Some("a\"b\\c/d"));
+        assert_eq!(parse(r#""\b\f\n\r\t""#).as_str(), Some("\u{8}\u{c}\n\r\t"));
+        assert_eq!(parse(r#""caf\u00e9 \u00E9""#).as_str(), Some("café é"));
+        assert_eq!(parse(r#""\u0041\u4e2d""#).as_str(), Some("A中"));
+        // Raw UTF-8 passes through untouched.
+        assert_eq!(parse(r#""héllo 😀""#).as_str(), Some("héllo 😀"));
+    }
+
+    #[test]
+    fn joins_surrogate_pairs() {
+        assert_eq!(parse(r#""\ud83d\ude00""#).as_str(), Some("😀"));
+        assert_eq!(parse(r#""x\uD834\uDD1Ey""#).as_str(), Some("x𝄞y"));
+
+        assert_eq!(error(r#""\ud83d""#).message, "unpaired surrogate");
+        assert_eq!(error(r#""\ud83dx""#).message, "unpaired surrogate");
+        assert_eq!(error(r#""\ud83d\u0041""#).message, "unpaired surrogate");
+        assert_eq!(error(r#""\ude00""#).message, "invalid code point");
+        assert_eq!(error(r#""\u12G4""#).message, "expected 4 hex digits");
+    }
+
+    #[test]
+    fn parses_nested_values() {
+        let value = parse(
+            r#"{
+                "asset": { "version": "2.0" },
+                "nodes": [ { "children": [1, 2] }, {}, { "matrix": [] } ],
+                "empty": {},
+                "list": [[], [[null]]]
+            }"#,
+        );
+
+        assert_eq!(value.get("asset").get("version").as_str(), Some("2.0"));
+        assert_eq!(value.get("nodes").at(0).get("children").as_f32_vec(), Some(vec![1.0, 2.0]));
+        assert_eq!(value.get("nodes").elements().len(), 3);
+        assert_eq!(value.get("empty").as_object().map(BTreeMap::len), Some(0));
+        assert!(value.get("list").at(1).at(0).at(0).is_null());
+
+        // Missing members and wrong types read as `Null` all the way down.
+        assert!(value.get("missing").get("deeper").at(4).is_null());
+        assert!(value.get("asset").at(0).is_null());
+        assert!(value.get("nodes").get("children").elements().is_empty());
+    }
+
+    #[test]
+    fn later_duplicate_members_win() {
+        assert_eq!(parse(r#"{"a": 1, "a": 2}"#).get("a").as_f64(), Some(2.0));
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let deep = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(JsonValue::parse(&deep(MAX_DEPTH)).is_ok());
+        assert_eq!(error(&deep(MAX_DEPTH + 1)).message, "nesting is too deep");
+    }
+
+    #[test]
+    fn reports_malformed_documents() {
+        for (source, message, offset) in [
+            ("", "unexpected end of the document", 0),
+            ("[1, 2,]", "unexpected character", 6),
+            ("[1 2]", "expected `,` or `]`", 3),
+            (r#"{"a" 1}"#, "expected `:`", 5),
+            (r#"{"a": 1,}"#, "expected a member name", 8),
+            (r#"{a: 1}"#, "expected a member name", 1),
+            (r#"{"a": 1"#, "expected `,` or `}`", 7),
+            (r#""abc"#, "unterminated string", 4),
+            ("\"a\nb\"", "control character in string", 3),
+            (r#""\q""#, "invalid escape", 3),
+            ("tru", "unexpected character", 0),
+            ("{} {}", "unexpected data after the document", 3),
+        ] {
+            assert_eq!(error(source), JsonError { offset, message: message.to_string() }, "{source:?}");
+        }
+    }
+}
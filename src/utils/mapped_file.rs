@@ -0,0 +1,65 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::Deref;
+use std::path::Path;
+
+/// A read-only view of a whole file.
+///
+/// On Unix the file is memory-mapped, so its pages are only read when touched and can be
+/// handed to GL without an intermediate copy. Elsewhere it is read into memory.
+pub struct MappedFile {
+    inner: Inner,
+}
+
+enum Inner {
+    #[cfg(unix)]
+    Mapped { pointer: *mut libc::c_void, length: usize },
+    Read(Vec<u8>),
+}
+
+impl MappedFile {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::open(path.as_ref())?;
+        let length = file.metadata()?.len() as usize;
+
+        #[cfg(unix)]
+        if length > 0 {
+            use std::os::unix::io::AsRawFd;
+
+            let pointer = unsafe {
+                libc::mmap(std::ptr::null_mut(), length, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0)
+            };
+            if pointer == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            return Ok(Self { inner: Inner::Mapped { pointer, length } });
+        }
+
+        let mut bytes = Vec::with_capacity(length);
+        file.read_to_end(&mut bytes)?;
+        Ok(Self { inner: Inner::Read(bytes) })
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.inner {
+            #[cfg(unix)]
+            Inner::Mapped { pointer, length } => unsafe { std::slice::from_raw_parts(*pointer as *const u8, *length) },
+            Inner::Read(bytes) => bytes,
+        }
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Inner::Mapped { pointer, length } = self.inner {
+            unsafe {
+                libc::munmap(pointer, length);
+            }
+        }
+    }
+}
@@ -1 +1,6 @@
-pub mod errors;
\ No newline at end of file
+pub mod base64;
+pub mod errors;
+pub mod file_watcher;
+pub mod hash;
+pub mod json;
+pub mod mapped_file;